@@ -22,7 +22,7 @@ impl AccountCheckUpCommand {
     pub async fn execute(self, printer: &mut impl Printer, config: &TomlConfig) -> Result<()> {
         info!("executing check up account command");
 
-        let account = self.account.name.as_ref().map(String::as_str);
+        let account = self.account.name.as_deref();
 
         printer.print_log("Checking configuration integrity…")?;
 
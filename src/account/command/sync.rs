@@ -2,9 +2,12 @@ use crate::{
     account::{arg::name::OptionalAccountNameArg, config::TomlAccountConfig},
     backend::{Backend, BackendContextBuilder, BackendKind},
     config::TomlConfig,
+    envelope::watch::{spawn_watch, watch_folder_changes},
+    outbox::{flush_outbox_or_warn, FLUSH_INTERVAL},
     printer::Printer,
+    ui::progress::{MAIN_PROGRESS_STYLE, SUB_PROGRESS_DONE_STYLE, SUB_PROGRESS_STYLE},
 };
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::{ArgAction, Parser};
 #[cfg(feature = "imap")]
 use email::imap::ImapContextBuilder;
@@ -15,33 +18,23 @@ use email::maildir::MaildirContextBuilder;
 use email::notmuch::NotmuchContextBuilder;
 use email::{
     account::{config::AccountConfig, sync::AccountSyncBuilder},
-    backend::BackendBuilder,
-    folder::sync::config::FolderSyncStrategy,
+    backend::{feature::BackendFeatureSource, BackendBuilder},
+    folder::{list::ListFolders, sync::config::FolderSyncStrategy, INBOX},
     sync::SyncEvent,
 };
-use indicatif::{MultiProgress, ProgressBar, ProgressFinish, ProgressStyle};
-use log::info;
-use once_cell::sync::Lazy;
+use futures::{
+    channel::mpsc::{unbounded, UnboundedReceiver},
+    StreamExt,
+};
+use indicatif::{MultiProgress, ProgressBar, ProgressFinish};
+use log::{debug, info, warn};
 use std::{
     collections::{BTreeSet, HashMap},
     ops::Deref,
     sync::{Arc, Mutex},
+    time::Duration,
 };
-
-static MAIN_PROGRESS_STYLE: Lazy<ProgressStyle> = Lazy::new(|| {
-    ProgressStyle::with_template(" {spinner:.dim} {msg:.dim}\n {wide_bar:.cyan/blue} \n").unwrap()
-});
-
-static SUB_PROGRESS_STYLE: Lazy<ProgressStyle> = Lazy::new(|| {
-    ProgressStyle::with_template(
-        "   {prefix:.bold} — {wide_msg:.dim} \n   {wide_bar:.black/black} {percent}% ",
-    )
-    .unwrap()
-});
-
-static SUB_PROGRESS_DONE_STYLE: Lazy<ProgressStyle> = Lazy::new(|| {
-    ProgressStyle::with_template("   {prefix:.bold} \n   {wide_bar:.green} {percent}% ").unwrap()
-});
+use tokio::time::MissedTickBehavior;
 
 /// Synchronize an account.
 ///
@@ -87,6 +80,31 @@ pub struct AccountSyncCommand {
     #[arg(long, short = 'A')]
     #[arg(conflicts_with = "include_folder", conflicts_with = "exclude_folder")]
     pub all_folders: bool,
+
+    /// Keep synchronizing the account in the background.
+    ///
+    /// Watched folders are watched for changes (using IMAP IDLE or
+    /// Maildir file system events), and only the changed folder is
+    /// synchronized when a change occurs. A full synchronization is
+    /// also run periodically, as a safety net.
+    #[arg(long, short = 'w', conflicts_with = "dry_run")]
+    pub watch: bool,
+
+    /// Watch specific folders for changes.
+    ///
+    /// Only used in watch mode. Every watched folder needs its own
+    /// connection, so only the inbox is watched by default (or the
+    /// folders defined in envelope.watch.folders). Other synchronized
+    /// folders are synchronized by the full synchronization.
+    #[arg(long, value_name = "FOLDER", action = ArgAction::Append)]
+    #[arg(requires = "watch")]
+    pub watch_folder: Vec<String>,
+
+    /// The interval between two full synchronizations, in seconds.
+    ///
+    /// Only used in watch mode.
+    #[arg(long, value_name = "SECS", default_value_t = 900)]
+    pub full_sync_interval: u64,
 }
 
 impl AccountSyncCommand {
@@ -111,8 +129,30 @@ impl AccountSyncCommand {
             config.clone().into_account_configs(account, true)?;
         let account_name = account_config.name.as_str();
 
+        if self.watch {
+            let interval = Duration::from_secs(self.full_sync_interval);
+            let watched_folders = if !self.watch_folder.is_empty() {
+                self.watch_folder
+            } else if let Some(folders) = toml_account_config.watch_envelopes_folders() {
+                folders.to_vec()
+            } else {
+                vec![INBOX.to_owned()]
+            };
+
+            return watch_account(
+                printer,
+                toml_account_config,
+                account_config,
+                strategy,
+                &watched_folders,
+                interval,
+            )
+            .await;
+        }
+
         let backend_builder =
-            AccountSyncBackendBuilder::new(toml_account_config, account_config.clone()).await?;
+            AccountSyncBackendBuilder::new(toml_account_config.clone(), account_config.clone())
+                .await?;
         let sync_builder = AccountSyncBuilder::new(backend_builder.into())?
             .with_dry_run(self.dry_run)
             .with_some_folders_filter(strategy);
@@ -247,10 +287,236 @@ impl AccountSyncCommand {
             printer.print(format!("Account {account_name} successfully synchronized!"))?;
         }
 
+        if !self.dry_run {
+            let sent = flush_outbox_or_warn(toml_account_config, account_config.clone()).await;
+            if sent > 0 {
+                printer.print_log(format!("Sent {sent} outbox message(s)"))?;
+            }
+        }
+
         Ok(())
     }
 }
 
+/// Synchronize the given account continuously.
+///
+/// The given folders are watched concurrently, each one on its own
+/// thread, as long as they are synchronized. When a change occurs,
+/// only the changed folders are synchronized. A full synchronization
+/// runs at startup, then at the given interval.
+async fn watch_account(
+    printer: &mut impl Printer,
+    toml_account_config: Arc<TomlAccountConfig>,
+    account_config: Arc<AccountConfig>,
+    strategy: Option<FolderSyncStrategy>,
+    watched_folders: &[String],
+    interval: Duration,
+) -> Result<()> {
+    let account_name = account_config.name.as_str();
+    let synced_folders =
+        list_synced_folders(&toml_account_config, &account_config, strategy.as_ref()).await?;
+    let folders = select_watched_folders(&synced_folders, watched_folders);
+    let (tx, rx) = unbounded();
+
+    for folder in &folders {
+        let tx = tx.clone();
+        let folder = folder.clone();
+        let toml_account_config = toml_account_config.clone();
+        let account_config = account_config.clone();
+
+        spawn_watch(move || async move {
+            let account_name = account_config.name.clone();
+            let policy = toml_account_config.watch_envelopes_reconnect();
+
+            // each folder needs its own backend, since a watch
+            // monopolizes the backend connection
+            let connect = || async {
+                Backend::new(
+                    toml_account_config.clone(),
+                    account_config.clone(),
+                    toml_account_config.watch_envelopes_kind(),
+                    |builder| builder.set_watch_envelopes(BackendFeatureSource::Context),
+                )
+                .await
+            };
+
+            let res = watch_folder_changes(&account_name, &folder, &policy, connect, || {
+                debug!("folder {folder} changed");
+                tx.unbounded_send(folder.clone())
+                    .context("cannot notify folder change")
+            })
+            .await;
+
+            // the full synchronization still covers the folder
+            if let Err(err) = res {
+                warn!("cannot watch folder {folder} of account {account_name}: {err:#}");
+            }
+        });
+    }
+
+    // only watchers are meant to notify changes
+    drop(tx);
+
+    if folders.is_empty() {
+        warn!("no synchronized folder to watch, only running full synchronizations");
+    } else {
+        printer.print_log(format!(
+            "Start watching folders {} of account {account_name} for changes…",
+            folders.join(", ")
+        ))?;
+    }
+
+    sync_on_changes(
+        printer,
+        &toml_account_config,
+        &account_config,
+        strategy,
+        interval,
+        rx,
+    )
+    .await
+}
+
+/// Select the folders to watch among the synchronized ones.
+///
+/// The inbox is matched case-insensitively, like IMAP does.
+fn select_watched_folders(synced_folders: &[String], watched_folders: &[String]) -> Vec<String> {
+    synced_folders
+        .iter()
+        .filter(|synced| {
+            watched_folders.iter().any(|watched| {
+                watched == *synced
+                    || (watched.eq_ignore_ascii_case(INBOX) && synced.eq_ignore_ascii_case(INBOX))
+            })
+        })
+        .cloned()
+        .collect()
+}
+
+/// List the folders to synchronize from the backend of the given
+/// account, following the given strategy (or the folder
+/// synchronization filter of the account).
+async fn list_synced_folders(
+    toml_account_config: &Arc<TomlAccountConfig>,
+    account_config: &Arc<AccountConfig>,
+    strategy: Option<&FolderSyncStrategy>,
+) -> Result<Vec<String>> {
+    let backend = Backend::new(
+        toml_account_config.clone(),
+        account_config.clone(),
+        toml_account_config.list_folders_kind(),
+        |builder| builder.set_list_folders(BackendFeatureSource::Context),
+    )
+    .await?;
+
+    let filter = match strategy {
+        Some(strategy) => strategy.clone(),
+        None => toml_account_config
+            .folder
+            .as_ref()
+            .and_then(|folder| folder.sync.as_ref())
+            .map(|sync| sync.filter.clone())
+            .unwrap_or_default(),
+    };
+
+    let folders = backend
+        .list_folders()
+        .await?
+        .iter()
+        .map(|folder| folder.name.clone())
+        .filter(|folder| filter.matches(folder))
+        .collect();
+
+    Ok(folders)
+}
+
+/// Synchronize folders as soon as they are notified by the given
+/// receiver, and synchronize the whole account at the given
+/// interval.
+///
+/// Notifications received during a synchronization are merged
+/// together, so that a folder is synchronized once even if it changed
+/// several times. The due messages of the outbox are sent in between.
+async fn sync_on_changes(
+    printer: &mut impl Printer,
+    toml_account_config: &Arc<TomlAccountConfig>,
+    account_config: &Arc<AccountConfig>,
+    strategy: Option<FolderSyncStrategy>,
+    interval: Duration,
+    mut changes: UnboundedReceiver<String>,
+) -> Result<()> {
+    let account_name = account_config.name.as_str();
+    let mut full_sync = tokio::time::interval(interval);
+    full_sync.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut outbox_flush = tokio::time::interval(FLUSH_INTERVAL);
+    outbox_flush.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        let (strategy, target) = tokio::select! {
+            _ = outbox_flush.tick() => {
+                let sent =
+                    flush_outbox_or_warn(toml_account_config.clone(), account_config.clone())
+                        .await;
+                if sent > 0 {
+                    printer.print_log(format!("Sent {sent} outbox message(s)"))?;
+                }
+                continue;
+            }
+            _ = full_sync.tick() => (strategy.clone(), format!("account {account_name}")),
+            Some(folder) = changes.next() => {
+                let mut folders = BTreeSet::from_iter([folder]);
+                while let Ok(Some(folder)) = changes.try_next() {
+                    folders.insert(folder);
+                }
+                let target = format!(
+                    "folders {} of account {account_name}",
+                    folders.iter().cloned().collect::<Vec<_>>().join(", "),
+                );
+                (Some(FolderSyncStrategy::Include(folders)), target)
+            }
+        };
+
+        info!("synchronizing {target}");
+
+        let backend_builder =
+            AccountSyncBackendBuilder::new(toml_account_config.clone(), account_config.clone())
+                .await?;
+        let report = AccountSyncBuilder::new(backend_builder.into())?
+            .with_some_folders_filter(strategy)
+            .sync()
+            .await;
+
+        // a failing synchronization should not stop the daemon, the
+        // next one will catch up
+        match report {
+            Ok(report) => {
+                let errors_count = report
+                    .folder
+                    .patch
+                    .iter()
+                    .filter(|(_, err)| err.is_some())
+                    .count()
+                    + report
+                        .email
+                        .patch
+                        .iter()
+                        .filter(|(_, err)| err.is_some())
+                        .count();
+
+                if errors_count > 0 {
+                    warn!("{errors_count} errors occurred while synchronizing {target}");
+                }
+
+                printer.print_log(format!("Synchronized {target}"))?;
+            }
+            Err(err) => {
+                warn!("cannot synchronize {target}: {err:#}");
+                debug!("{err:?}");
+            }
+        }
+    }
+}
+
 pub struct AccountSyncBackendBuilder {
     toml_account_config: Arc<TomlAccountConfig>,
     builder: BackendBuilder<BackendContextBuilder>,
@@ -282,7 +548,7 @@ impl AccountSyncBackendBuilder {
                     .imap
                     .as_ref()
                     .filter(|_| is_imap_used)
-                    .map(Clone::clone)
+                    .cloned()
                     .map(Arc::new)
                     .map(|config| {
                         ImapContextBuilder::new(account_config.clone(), config)
@@ -299,23 +565,23 @@ impl AccountSyncBackendBuilder {
                 .maildir
                 .as_ref()
                 .filter(|_| is_maildir_used)
-                .map(Clone::clone)
+                .cloned()
                 .map(Arc::new)
                 .map(|mdir_config| MaildirContextBuilder::new(account_config.clone(), mdir_config)),
 
-            maildir_for_sync: Some(MaildirConfig {
-                root_dir: account_config.get_sync_dir()?,
-            })
-            .filter(|_| is_maildir_for_sync_used)
-            .map(Arc::new)
-            .map(|mdir_config| MaildirContextBuilder::new(account_config.clone(), mdir_config)),
+            maildir_for_sync: is_maildir_for_sync_used
+                .then_some(MaildirConfig {
+                    root_dir: account_config.get_sync_dir()?,
+                })
+                .map(Arc::new)
+                .map(|mdir_config| MaildirContextBuilder::new(account_config.clone(), mdir_config)),
 
             #[cfg(feature = "notmuch")]
             notmuch: toml_account_config
                 .notmuch
                 .as_ref()
                 .filter(|_| is_notmuch_used)
-                .map(Clone::clone)
+                .cloned()
                 .map(Arc::new)
                 .map(|notmuch_config| {
                     NotmuchContextBuilder::new(account_config.clone(), notmuch_config)
@@ -357,3 +623,22 @@ impl From<AccountSyncBackendBuilder> for BackendBuilder<BackendContextBuilder> {
         backend_builder.builder
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::select_watched_folders;
+
+    #[test]
+    fn watched_folders() {
+        let synced = ["INBOX", "Sent", "Archives/2024"].map(String::from);
+
+        let folders = select_watched_folders(&synced, &["inbox".into()]);
+        assert_eq!(folders, vec!["INBOX"]);
+
+        let folders = select_watched_folders(&synced, &["Archives/2024".into(), "Spam".into()]);
+        assert_eq!(folders, vec!["Archives/2024"]);
+
+        let folders = select_watched_folders(&synced, &["sent".into()]);
+        assert!(folders.is_empty());
+    }
+}
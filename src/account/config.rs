@@ -18,11 +18,23 @@ use email::sendmail::config::SendmailConfig;
 #[cfg(feature = "smtp")]
 use email::smtp::config::SmtpConfig;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashSet, path::PathBuf};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
 
 use crate::{
-    backend::BackendKind, envelope::config::EnvelopeConfig, flag::config::FlagConfig,
-    folder::config::FolderConfig, message::config::MessageConfig,
+    backend::BackendKind,
+    envelope::{
+        config::{EnvelopeConfig, WatchReconnectConfig},
+        sort::Sort,
+        EnvelopeColumn,
+    },
+    flag::config::{FlagConfig, FlagDisplayConfig},
+    folder::config::FolderConfig,
+    journal::config::JournalConfig,
+    message::{config::MessageConfig, render::MessageRender},
+    outbox::config::{OutboxConfig, OutboxRetryConfig},
 };
 
 /// Represents all existing kind of account config.
@@ -46,6 +58,8 @@ pub struct TomlAccountConfig {
     pub envelope: Option<EnvelopeConfig>,
     pub flag: Option<FlagConfig>,
     pub message: Option<MessageConfig>,
+    pub journal: Option<JournalConfig>,
+    pub outbox: Option<OutboxConfig>,
 
     #[cfg(feature = "imap")]
     pub imap: Option<ImapConfig>,
@@ -116,6 +130,56 @@ impl TomlAccountConfig {
             .or(self.backend.as_ref())
     }
 
+    pub fn list_envelopes_sort(&self) -> Option<&Sort> {
+        self.envelope
+            .as_ref()
+            .and_then(|envelope| envelope.list.as_ref())
+            .and_then(|list| list.sort.as_ref())
+    }
+
+    pub fn list_envelopes_columns(&self) -> Option<&[EnvelopeColumn]> {
+        self.envelope
+            .as_ref()
+            .and_then(|envelope| envelope.list.as_ref())
+            .and_then(|list| list.columns.as_deref())
+    }
+
+    pub fn journal_retention(&self) -> u64 {
+        self.journal
+            .as_ref()
+            .and_then(|journal| journal.retention)
+            .unwrap_or(JournalConfig::DEFAULT_RETENTION)
+    }
+
+    pub fn outbox_fallback(&self) -> bool {
+        self.outbox
+            .as_ref()
+            .and_then(|outbox| outbox.fallback)
+            .unwrap_or_default()
+    }
+
+    pub fn outbox_retry(&self) -> OutboxRetryConfig {
+        self.outbox
+            .as_ref()
+            .and_then(|outbox| outbox.retry.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn watch_envelopes_folders(&self) -> Option<&[String]> {
+        self.envelope
+            .as_ref()
+            .and_then(|envelope| envelope.watch.as_ref())
+            .and_then(|watch| watch.folders.as_deref())
+    }
+
+    pub fn watch_envelopes_reconnect(&self) -> WatchReconnectConfig {
+        self.envelope
+            .as_ref()
+            .and_then(|envelope| envelope.watch.as_ref())
+            .and_then(|watch| watch.reconnect.clone())
+            .unwrap_or_default()
+    }
+
     pub fn watch_envelopes_kind(&self) -> Option<&BackendKind> {
         self.envelope
             .as_ref()
@@ -124,6 +188,13 @@ impl TomlAccountConfig {
             .or(self.backend.as_ref())
     }
 
+    pub fn flags_display(&self) -> HashMap<String, FlagDisplayConfig> {
+        self.flag
+            .as_ref()
+            .and_then(|flag| flag.display.clone())
+            .unwrap_or_default()
+    }
+
     pub fn add_flags_kind(&self) -> Option<&BackendKind> {
         self.flag
             .as_ref()
@@ -172,6 +243,14 @@ impl TomlAccountConfig {
             .or(self.backend.as_ref())
     }
 
+    pub fn read_message_render(&self) -> MessageRender {
+        self.message
+            .as_ref()
+            .and_then(|message| message.read.as_ref())
+            .and_then(|get| get.render)
+            .unwrap_or_default()
+    }
+
     pub fn copy_messages_kind(&self) -> Option<&BackendKind> {
         self.message
             .as_ref()
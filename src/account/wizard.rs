@@ -20,9 +20,7 @@ use crate::{
 use super::TomlAccountConfig;
 
 pub(crate) async fn configure() -> Result<Option<(String, TomlAccountConfig)>> {
-    let mut config = TomlAccountConfig::default();
-
-    config.email = Input::with_theme(&*THEME)
+    let email = Input::with_theme(&*THEME)
         .with_prompt("Email address")
         .validate_with(|email: &String| {
             if EmailAddress::is_valid(email) {
@@ -33,6 +31,11 @@ pub(crate) async fn configure() -> Result<Option<(String, TomlAccountConfig)>> {
         })
         .interact()?;
 
+    let mut config = TomlAccountConfig {
+        email,
+        ..Default::default()
+    };
+
     let addr = EmailAddress::from_str(&config.email).unwrap();
 
     #[cfg(feature = "account-discovery")]
@@ -1,9 +1,16 @@
 pub mod config;
 pub(crate) mod wizard;
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
 use async_trait::async_trait;
-use std::{ops::Deref, sync::Arc};
+use chrono::{DateTime, Utc};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    future::Future,
+    ops::Deref,
+    sync::Arc,
+};
 
 #[cfg(feature = "imap")]
 use email::imap::{ImapContextBuilder, ImapContextSync};
@@ -33,9 +40,60 @@ use email::{
         peek::PeekMessages, r#move::MoveMessages, send::SendMessage, Messages,
     },
 };
+use log::{debug, warn};
+use mail_parser::MessageParser;
 use serde::{Deserialize, Serialize};
 
-use crate::{account::config::TomlAccountConfig, cache::IdMapper, envelope::Envelopes};
+#[cfg(feature = "imap")]
+use crate::envelope::get::imap::{get_imap_envelopes, get_imap_uid_next, search_imap_message_ids};
+#[cfg(any(feature = "maildir", feature = "account-sync"))]
+use crate::envelope::get::maildir::get_maildir_envelopes;
+#[cfg(feature = "notmuch")]
+use crate::envelope::get::notmuch::get_notmuch_envelopes;
+#[cfg(feature = "imap")]
+use crate::envelope::query::imap::search_imap_envelopes;
+#[cfg(any(feature = "maildir", feature = "account-sync"))]
+use crate::envelope::query::maildir::search_maildir_envelopes;
+#[cfg(feature = "notmuch")]
+use crate::envelope::query::notmuch::search_notmuch_envelopes;
+#[cfg(feature = "imap")]
+use crate::envelope::thread::imap::thread_imap_envelopes;
+#[cfg(any(feature = "maildir", feature = "account-sync"))]
+use crate::envelope::thread::maildir::thread_maildir_envelopes;
+#[cfg(feature = "notmuch")]
+use crate::envelope::thread::notmuch::thread_notmuch_envelopes;
+#[cfg(any(feature = "maildir", feature = "account-sync"))]
+use crate::envelope::watch::maildir::MaildirWatcher;
+#[cfg(feature = "imap")]
+use crate::folder::stats::imap::get_imap_folder_stats;
+#[cfg(any(feature = "maildir", feature = "account-sync"))]
+use crate::folder::stats::maildir::get_maildir_folder_stats;
+#[cfg(feature = "notmuch")]
+use crate::folder::stats::notmuch::get_notmuch_folder_stats;
+#[cfg(feature = "imap")]
+use crate::message::add::imap::add_imap_message_with_date;
+#[cfg(any(feature = "maildir", feature = "account-sync"))]
+use crate::message::add::maildir::add_maildir_message_with_date;
+#[cfg(feature = "sendmail")]
+use crate::message::redirect::sendmail::redirect_sendmail_message;
+#[cfg(feature = "smtp")]
+use crate::message::redirect::smtp::redirect_smtp_message;
+use crate::{
+    account::config::TomlAccountConfig,
+    cache::IdMapper,
+    envelope::{
+        arg::ids::BATCH_SIZE,
+        get::FullEnvelope,
+        query::Query,
+        raw::RawEnvelope,
+        sort::Sort,
+        thread::EnvelopeThreads,
+        watch::{diff_snapshots, EnvelopesWatcher, Snapshot, WatchEvent, WatchEventKind},
+        Envelope, Envelopes,
+    },
+    folder::stats::FolderStats,
+    journal::{Journal, JournalEnvelope, Operation},
+};
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
@@ -56,8 +114,8 @@ pub enum BackendKind {
     None,
 }
 
-impl ToString for BackendKind {
-    fn to_string(&self) -> String {
+impl fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let kind = match self {
             #[cfg(feature = "imap")]
             Self::Imap => "IMAP",
@@ -74,7 +132,7 @@ impl ToString for BackendKind {
             Self::None => "None",
         };
 
-        kind.to_string()
+        write!(f, "{kind}")
     }
 }
 
@@ -118,7 +176,7 @@ impl BackendContextBuilder {
                     .imap
                     .as_ref()
                     .filter(|_| kinds.contains(&&BackendKind::Imap))
-                    .map(Clone::clone)
+                    .cloned()
                     .map(Arc::new)
                     .map(|imap_config| {
                         ImapContextBuilder::new(account_config.clone(), imap_config)
@@ -135,24 +193,25 @@ impl BackendContextBuilder {
                 .maildir
                 .as_ref()
                 .filter(|_| kinds.contains(&&BackendKind::Maildir))
-                .map(Clone::clone)
+                .cloned()
                 .map(Arc::new)
                 .map(|mdir_config| MaildirContextBuilder::new(account_config.clone(), mdir_config)),
 
             #[cfg(feature = "account-sync")]
-            maildir_for_sync: Some(MaildirConfig {
-                root_dir: account_config.get_sync_dir()?,
-            })
-            .filter(|_| kinds.contains(&&BackendKind::MaildirForSync))
-            .map(Arc::new)
-            .map(|mdir_config| MaildirContextBuilder::new(account_config.clone(), mdir_config)),
+            maildir_for_sync: kinds
+                .contains(&&BackendKind::MaildirForSync)
+                .then_some(MaildirConfig {
+                    root_dir: account_config.get_sync_dir()?,
+                })
+                .map(Arc::new)
+                .map(|mdir_config| MaildirContextBuilder::new(account_config.clone(), mdir_config)),
 
             #[cfg(feature = "notmuch")]
             notmuch: toml_account_config
                 .notmuch
                 .as_ref()
                 .filter(|_| kinds.contains(&&BackendKind::Notmuch))
-                .map(Clone::clone)
+                .cloned()
                 .map(Arc::new)
                 .map(|notmuch_config| {
                     NotmuchContextBuilder::new(account_config.clone(), notmuch_config)
@@ -163,7 +222,7 @@ impl BackendContextBuilder {
                 .smtp
                 .as_ref()
                 .filter(|_| kinds.contains(&&BackendKind::Smtp))
-                .map(Clone::clone)
+                .cloned()
                 .map(Arc::new)
                 .map(|smtp_config| SmtpContextBuilder::new(account_config.clone(), smtp_config)),
 
@@ -172,7 +231,7 @@ impl BackendContextBuilder {
                 .sendmail
                 .as_ref()
                 .filter(|_| kinds.contains(&&BackendKind::Sendmail))
-                .map(Clone::clone)
+                .cloned()
                 .map(Arc::new)
                 .map(|sendmail_config| {
                     SendmailContextBuilder::new(account_config.clone(), sendmail_config)
@@ -509,7 +568,16 @@ impl email::backend::context::BackendContextBuilder for BackendContextBuilder {
 
         #[cfg(feature = "smtp")]
         if let Some(smtp) = self.smtp {
-            ctx.smtp = Some(smtp.build().await?);
+            match smtp.build().await {
+                Ok(smtp) => ctx.smtp = Some(smtp),
+                // messages cannot be sent, but they can still be
+                // queued to the outbox
+                Err(err) if self.toml_account_config.outbox_fallback() => {
+                    warn!("cannot build smtp context, skipping it: {err}");
+                    debug!("{err:?}");
+                }
+                Err(err) => return Err(err),
+            }
         }
 
         #[cfg(feature = "sendmail")]
@@ -618,10 +686,8 @@ impl Backend {
 
         match backend_kind {
             #[cfg(feature = "maildir")]
-            Some(BackendKind::Maildir) => {
-                if let Some(_) = &self.toml_account_config.maildir {
-                    id_mapper = IdMapper::new(&self.backend.account_config, folder)?;
-                }
+            Some(BackendKind::Maildir) if self.toml_account_config.maildir.is_some() => {
+                id_mapper = IdMapper::new(&self.backend.account_config, folder)?;
             }
 
             #[cfg(feature = "account-sync")]
@@ -630,10 +696,8 @@ impl Backend {
             }
 
             #[cfg(feature = "notmuch")]
-            Some(BackendKind::Notmuch) => {
-                if let Some(_) = &self.toml_account_config.notmuch {
-                    id_mapper = IdMapper::new(&self.backend.account_config, folder)?;
-                }
+            Some(BackendKind::Notmuch) if self.toml_account_config.notmuch.is_some() => {
+                id_mapper = IdMapper::new(&self.backend.account_config, folder)?;
             }
             _ => (),
         };
@@ -641,6 +705,54 @@ impl Backend {
         Ok(id_mapper)
     }
 
+    /// Get statistics of the given folder.
+    ///
+    /// Statistics are computed by the backend when it supports it
+    /// (IMAP STATUS), otherwise they are computed locally from the
+    /// folder entries.
+    pub async fn get_folder_stats(&self, folder: &str) -> Result<FolderStats> {
+        let backend_kind = self.toml_account_config.list_folders_kind();
+        #[allow(unused)]
+        let ctx = &self.backend.context;
+
+        match backend_kind {
+            #[cfg(feature = "imap")]
+            Some(BackendKind::Imap) => {
+                let ctx = ctx
+                    .imap
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("cannot find imap context"))?;
+                get_imap_folder_stats(ctx, folder).await
+            }
+            #[cfg(feature = "maildir")]
+            Some(BackendKind::Maildir) => {
+                let ctx = ctx
+                    .maildir
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("cannot find maildir context"))?;
+                get_maildir_folder_stats(ctx, folder).await
+            }
+            #[cfg(feature = "account-sync")]
+            Some(BackendKind::MaildirForSync) => {
+                let ctx = ctx
+                    .maildir_for_sync
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("cannot find maildir context"))?;
+                get_maildir_folder_stats(ctx, folder).await
+            }
+            #[cfg(feature = "notmuch")]
+            Some(BackendKind::Notmuch) => {
+                let ctx = ctx
+                    .notmuch
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("cannot find notmuch context"))?;
+                get_notmuch_folder_stats(ctx, folder).await
+            }
+            Some(kind) => bail!("cannot get folder stats: backend {kind} not supported"),
+            None => bail!("cannot get folder stats: backend not defined"),
+        }
+    }
+
     pub async fn list_envelopes(
         &self,
         folder: &str,
@@ -650,50 +762,357 @@ impl Backend {
         let backend_kind = self.toml_account_config.list_envelopes_kind();
         let id_mapper = self.build_id_mapper(folder, backend_kind)?;
         let envelopes = self.backend.list_envelopes(folder, page_size, page).await?;
-        let envelopes = Envelopes::from_backend(&self.account_config, &id_mapper, envelopes)?;
+        Envelopes::from_backend(&self.account_config, &id_mapper, envelopes)
+    }
+
+    /// Search envelopes matching the given query, ordered by the
+    /// given sort.
+    ///
+    /// The query and the sort are translated into backend-specific
+    /// commands when the backend supports them (IMAP, Notmuch),
+    /// otherwise they are evaluated locally (Maildir). Pagination is
+    /// applied after sorting.
+    pub async fn search_envelopes(
+        &self,
+        folder: &str,
+        query: Option<&Query>,
+        sort: Option<&Sort>,
+        page_size: usize,
+        page: usize,
+    ) -> Result<Envelopes> {
+        let envelopes = self
+            .search_raw_envelopes(folder, query, sort, page_size, page)
+            .await?;
+        self.to_envelopes(folder, &envelopes)
+    }
+
+    /// Search raw envelopes matching the given query, ordered by the
+    /// given sort.
+    ///
+    /// See [`Backend::search_envelopes`].
+    pub async fn search_raw_envelopes(
+        &self,
+        folder: &str,
+        query: Option<&Query>,
+        sort: Option<&Sort>,
+        page_size: usize,
+        page: usize,
+    ) -> Result<Vec<RawEnvelope>> {
+        let backend_kind = self.toml_account_config.list_envelopes_kind();
+        #[allow(unused)]
+        let ctx = &self.backend.context;
+
+        let envelopes = match backend_kind {
+            #[cfg(feature = "imap")]
+            Some(BackendKind::Imap) => {
+                let ctx = ctx
+                    .imap
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("cannot find imap context"))?;
+                search_imap_envelopes(ctx, folder, query, sort, page_size, page).await?
+            }
+            #[cfg(feature = "maildir")]
+            Some(BackendKind::Maildir) => {
+                let ctx = ctx
+                    .maildir
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("cannot find maildir context"))?;
+                search_maildir_envelopes(ctx, folder, query, sort, page_size, page).await?
+            }
+            #[cfg(feature = "account-sync")]
+            Some(BackendKind::MaildirForSync) => {
+                let ctx = ctx
+                    .maildir_for_sync
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("cannot find maildir context"))?;
+                search_maildir_envelopes(ctx, folder, query, sort, page_size, page).await?
+            }
+            #[cfg(feature = "notmuch")]
+            Some(BackendKind::Notmuch) => {
+                let ctx = ctx
+                    .notmuch
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("cannot find notmuch context"))?;
+                search_notmuch_envelopes(ctx, folder, query, sort, page_size, page).await?
+            }
+            Some(kind) => bail!("cannot search envelopes: backend {kind} not supported"),
+            None => bail!("cannot search envelopes: backend not defined"),
+        };
+
         Ok(envelopes)
     }
 
+    /// Build envelopes from the given raw envelopes of the given
+    /// folder, aliasing their ids.
+    pub fn to_envelopes(&self, folder: &str, envelopes: &[RawEnvelope]) -> Result<Envelopes> {
+        let backend_kind = self.toml_account_config.list_envelopes_kind();
+        let id_mapper = self.build_id_mapper(folder, backend_kind)?;
+        Envelopes::from_raw(&self.account_config, &id_mapper, envelopes)
+    }
+
+    /// Build envelopes from the given raw envelopes of the given
+    /// folder, aliasing their ids, alongside with the raw envelope
+    /// each of them was built from.
+    pub fn to_paired_envelopes<'a>(
+        &self,
+        folder: &str,
+        envelopes: &'a [RawEnvelope],
+    ) -> Result<Vec<(&'a RawEnvelope, Envelope)>> {
+        let backend_kind = self.toml_account_config.list_envelopes_kind();
+        let id_mapper = self.build_id_mapper(folder, backend_kind)?;
+        Envelopes::pair_from_raw(&self.account_config, &id_mapper, envelopes)
+    }
+
+    /// Get the full envelope matching the given id.
+    ///
+    /// The flags, the headers and the size of the envelope are
+    /// fetched at once from the backend, without the message body.
+    pub async fn get_full_envelope(&self, folder: &str, id: usize) -> Result<FullEnvelope> {
+        let backend_kind = self.toml_account_config.get_envelope_kind();
+        let id_mapper = self.build_id_mapper(folder, backend_kind)?;
+        let id = id_mapper.get_id(id)?;
+
+        let raw = self
+            .get_raw_envelopes(backend_kind, folder, std::slice::from_ref(&id))
+            .await?
+            .into_iter()
+            .find(|envelope| envelope.id == id)
+            .ok_or_else(|| anyhow!("cannot find envelope {id} from folder {folder}"))?;
+
+        FullEnvelope::from_raw(&self.account_config, &id_mapper, &raw)
+    }
+
+    /// Group envelopes of the given folder into threads.
+    ///
+    /// Threads are built by the backend when it supports it (IMAP
+    /// THREAD extension), otherwise they are built locally from
+    /// message headers. Only envelopes matching the given query are
+    /// threaded.
+    pub async fn thread_envelopes(
+        &self,
+        folder: &str,
+        query: Option<&Query>,
+    ) -> Result<EnvelopeThreads> {
+        let backend_kind = self.toml_account_config.list_envelopes_kind();
+        let id_mapper = self.build_id_mapper(folder, backend_kind)?;
+        #[allow(unused)]
+        let ctx = &self.backend.context;
+
+        let threads = match backend_kind {
+            #[cfg(feature = "imap")]
+            Some(BackendKind::Imap) => {
+                let ctx = ctx
+                    .imap
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("cannot find imap context"))?;
+                thread_imap_envelopes(ctx, folder, query).await?
+            }
+            #[cfg(feature = "maildir")]
+            Some(BackendKind::Maildir) => {
+                let ctx = ctx
+                    .maildir
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("cannot find maildir context"))?;
+                thread_maildir_envelopes(ctx, folder, query).await?
+            }
+            #[cfg(feature = "account-sync")]
+            Some(BackendKind::MaildirForSync) => {
+                let ctx = ctx
+                    .maildir_for_sync
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("cannot find maildir context"))?;
+                thread_maildir_envelopes(ctx, folder, query).await?
+            }
+            #[cfg(feature = "notmuch")]
+            Some(BackendKind::Notmuch) => {
+                let ctx = ctx
+                    .notmuch
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("cannot find notmuch context"))?;
+                thread_notmuch_envelopes(ctx, folder, query).await?
+            }
+            Some(kind) => bail!("cannot thread envelopes: backend {kind} not supported"),
+            None => bail!("cannot thread envelopes: backend not defined"),
+        };
+
+        EnvelopeThreads::from_raw(&self.account_config, &id_mapper, threads)
+    }
+
+    /// Process the changes between the given snapshots of the given
+    /// folder, calling the given function for every change.
+    ///
+    /// Envelope hooks (envelope.watch.received and envelope.watch.any)
+    /// are executed as well. Envelopes that cannot be built are
+    /// skipped with a warning.
+    pub async fn process_envelopes_changes(
+        &self,
+        folder: &str,
+        prev: &Snapshot,
+        next: &Snapshot,
+        on_event: &mut impl FnMut(WatchEvent) -> Result<()>,
+    ) -> Result<()> {
+        let events = diff_snapshots(prev, next);
+
+        if events.is_empty() {
+            return Ok(());
+        }
+
+        let backend_kind = self.toml_account_config.watch_envelopes_kind();
+        let id_mapper = self.build_id_mapper(folder, backend_kind)?;
+
+        for (kind, raw) in events {
+            let backend_envelope = raw.to_envelope();
+            match kind {
+                WatchEventKind::Received => {
+                    self.account_config
+                        .exec_received_envelope_hook(&backend_envelope)
+                        .await
+                }
+                _ => {
+                    self.account_config
+                        .exec_any_envelope_hook(&backend_envelope)
+                        .await
+                }
+            }
+
+            let envelope = match Envelope::from_raw(&self.account_config, &id_mapper, raw) {
+                Ok(envelope) => envelope,
+                Err(err) => {
+                    warn!(
+                        "cannot build envelope {}, skipping {kind} event: {err:#}",
+                        raw.id
+                    );
+                    continue;
+                }
+            };
+
+            on_event(WatchEvent {
+                event: kind,
+                account: self.account_config.name.clone(),
+                folder: folder.to_owned(),
+                envelope,
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Build the watcher of the given folder, based on the watch
+    /// envelopes backend.
+    pub async fn build_envelopes_watcher(&self, folder: &str) -> Result<EnvelopesWatcher> {
+        let backend_kind = self.toml_account_config.watch_envelopes_kind();
+        #[allow(unused)]
+        let ctx = &self.backend.context;
+
+        match backend_kind {
+            #[cfg(feature = "imap")]
+            Some(BackendKind::Imap) => {
+                let ctx = ctx
+                    .imap
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("cannot find imap context"))?;
+                Ok(EnvelopesWatcher::Imap(ctx.clone(), Default::default()))
+            }
+            #[cfg(feature = "maildir")]
+            Some(BackendKind::Maildir) => {
+                let ctx = ctx
+                    .maildir
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("cannot find maildir context"))?;
+                let watcher = MaildirWatcher::new(ctx, folder).await?;
+                Ok(EnvelopesWatcher::Maildir(ctx.clone(), watcher))
+            }
+            #[cfg(feature = "account-sync")]
+            Some(BackendKind::MaildirForSync) => {
+                let ctx = ctx
+                    .maildir_for_sync
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("cannot find maildir context"))?;
+                let watcher = MaildirWatcher::new(ctx, folder).await?;
+                Ok(EnvelopesWatcher::Maildir(ctx.clone(), watcher))
+            }
+            Some(kind) => bail!("cannot watch envelopes events: backend {kind} not supported"),
+            None => bail!("cannot watch envelopes events: backend not defined"),
+        }
+    }
+
     pub async fn add_flags(&self, folder: &str, ids: &[usize], flags: &Flags) -> Result<()> {
         let backend_kind = self.toml_account_config.add_flags_kind();
         let id_mapper = self.build_id_mapper(folder, backend_kind)?;
-        let ids = Id::multiple(id_mapper.get_ids(ids)?);
-        self.backend.add_flags(folder, &ids, flags).await
+        let ids = id_mapper.get_ids(ids)?;
+        let snapshot = self
+            .snapshot_journal_envelopes(backend_kind, folder, &ids, None)
+            .await;
+        let (done, res) = apply_by_batches(&ids, |id| async move {
+            self.backend.add_flags(folder, &id, flags).await
+        })
+        .await;
+        self.record_journal_operation(snapshot, backend_kind, None, done, |envelopes| {
+            Operation::AddFlags {
+                folder: folder.to_owned(),
+                flags: flags.clone().into(),
+                envelopes,
+            }
+        })
+        .await;
+        res
     }
 
     pub async fn add_flag(&self, folder: &str, ids: &[usize], flag: Flag) -> Result<()> {
-        let backend_kind = self.toml_account_config.add_flags_kind();
-        let id_mapper = self.build_id_mapper(folder, backend_kind)?;
-        let ids = Id::multiple(id_mapper.get_ids(ids)?);
-        self.backend.add_flag(folder, &ids, flag).await
+        self.add_flags(folder, ids, &Flags::from_iter([flag])).await
     }
 
     pub async fn set_flags(&self, folder: &str, ids: &[usize], flags: &Flags) -> Result<()> {
         let backend_kind = self.toml_account_config.set_flags_kind();
         let id_mapper = self.build_id_mapper(folder, backend_kind)?;
-        let ids = Id::multiple(id_mapper.get_ids(ids)?);
-        self.backend.set_flags(folder, &ids, flags).await
+        let ids = id_mapper.get_ids(ids)?;
+        let snapshot = self
+            .snapshot_journal_envelopes(backend_kind, folder, &ids, None)
+            .await;
+        let (done, res) = apply_by_batches(&ids, |id| async move {
+            self.backend.set_flags(folder, &id, flags).await
+        })
+        .await;
+        self.record_journal_operation(snapshot, backend_kind, None, done, |envelopes| {
+            Operation::SetFlags {
+                folder: folder.to_owned(),
+                flags: flags.clone().into(),
+                envelopes,
+            }
+        })
+        .await;
+        res
     }
 
     pub async fn set_flag(&self, folder: &str, ids: &[usize], flag: Flag) -> Result<()> {
-        let backend_kind = self.toml_account_config.set_flags_kind();
-        let id_mapper = self.build_id_mapper(folder, backend_kind)?;
-        let ids = Id::multiple(id_mapper.get_ids(ids)?);
-        self.backend.set_flag(folder, &ids, flag).await
+        self.set_flags(folder, ids, &Flags::from_iter([flag])).await
     }
 
     pub async fn remove_flags(&self, folder: &str, ids: &[usize], flags: &Flags) -> Result<()> {
         let backend_kind = self.toml_account_config.remove_flags_kind();
         let id_mapper = self.build_id_mapper(folder, backend_kind)?;
-        let ids = Id::multiple(id_mapper.get_ids(ids)?);
-        self.backend.remove_flags(folder, &ids, flags).await
+        let ids = id_mapper.get_ids(ids)?;
+        let snapshot = self
+            .snapshot_journal_envelopes(backend_kind, folder, &ids, None)
+            .await;
+        let (done, res) = apply_by_batches(&ids, |id| async move {
+            self.backend.remove_flags(folder, &id, flags).await
+        })
+        .await;
+        self.record_journal_operation(snapshot, backend_kind, None, done, |envelopes| {
+            Operation::RemoveFlags {
+                folder: folder.to_owned(),
+                flags: flags.clone().into(),
+                envelopes,
+            }
+        })
+        .await;
+        res
     }
 
     pub async fn remove_flag(&self, folder: &str, ids: &[usize], flag: Flag) -> Result<()> {
-        let backend_kind = self.toml_account_config.remove_flags_kind();
-        let id_mapper = self.build_id_mapper(folder, backend_kind)?;
-        let ids = Id::multiple(id_mapper.get_ids(ids)?);
-        self.backend.remove_flag(folder, &ids, flag).await
+        self.remove_flags(folder, ids, &Flags::from_iter([flag]))
+            .await
     }
 
     pub async fn add_message(&self, folder: &str, email: &[u8]) -> Result<SingleId> {
@@ -704,6 +1123,80 @@ impl Backend {
         Ok(id)
     }
 
+    pub async fn add_message_with_flags(
+        &self,
+        folder: &str,
+        email: &[u8],
+        flags: &Flags,
+    ) -> Result<SingleId> {
+        let backend_kind = self.toml_account_config.add_message_kind();
+        let id_mapper = self.build_id_mapper(folder, backend_kind)?;
+        let id = self
+            .backend
+            .add_message_with_flags(folder, email, flags)
+            .await?;
+        id_mapper.create_alias(&*id)?;
+        Ok(id)
+    }
+
+    /// Add the given raw message to the given folder, keeping the
+    /// given date as date of the folder entry when the backend
+    /// supports it.
+    ///
+    /// Notmuch indexes messages by their Date header only, so the
+    /// given date is ignored.
+    pub async fn add_message_with_date(
+        &self,
+        folder: &str,
+        email: &[u8],
+        flags: &Flags,
+        date: Option<DateTime<Utc>>,
+    ) -> Result<SingleId> {
+        let Some(date) = date else {
+            return self.add_message_with_flags(folder, email, flags).await;
+        };
+
+        let backend_kind = self.toml_account_config.add_message_kind();
+        let id_mapper = self.build_id_mapper(folder, backend_kind)?;
+        #[allow(unused)]
+        let ctx = &self.backend.context;
+
+        let id = match backend_kind {
+            #[cfg(feature = "imap")]
+            Some(BackendKind::Imap) => {
+                let ctx = ctx
+                    .imap
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("cannot find imap context"))?;
+                add_imap_message_with_date(ctx, folder, email, flags, date).await?
+            }
+            #[cfg(feature = "maildir")]
+            Some(BackendKind::Maildir) => {
+                let ctx = ctx
+                    .maildir
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("cannot find maildir context"))?;
+                add_maildir_message_with_date(ctx, folder, email, flags, date).await?
+            }
+            #[cfg(feature = "account-sync")]
+            Some(BackendKind::MaildirForSync) => {
+                let ctx = ctx
+                    .maildir_for_sync
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("cannot find maildir context"))?;
+                add_maildir_message_with_date(ctx, folder, email, flags, date).await?
+            }
+            Some(_) => {
+                debug!("backend cannot keep the date of added messages, skipping it");
+                return self.add_message_with_flags(folder, email, flags).await;
+            }
+            None => bail!("cannot add message: backend not defined"),
+        };
+
+        id_mapper.create_alias(&*id)?;
+        Ok(id)
+    }
+
     pub async fn peek_messages(&self, folder: &str, ids: &[usize]) -> Result<Messages> {
         let backend_kind = self.toml_account_config.get_messages_kind();
         let id_mapper = self.build_id_mapper(folder, backend_kind)?;
@@ -711,6 +1204,86 @@ impl Backend {
         self.backend.peek_messages(folder, &ids).await
     }
 
+    /// Peek the raw messages matching the given ids, alongside with
+    /// their id and their flags, in the order of the given ids.
+    ///
+    /// Backends do not return messages in the requested order, so
+    /// messages are paired with their envelope by Message-ID.
+    /// Messages without a Message-ID unique among the given ids are
+    /// peeked one by one instead.
+    pub async fn peek_messages_with_flags(
+        &self,
+        folder: &str,
+        ids: &[usize],
+    ) -> Result<Vec<(usize, Flags, Vec<u8>)>> {
+        let backend_kind = self.toml_account_config.get_messages_kind();
+        let id_mapper = self.build_id_mapper(folder, backend_kind)?;
+
+        let mut seen = HashSet::new();
+        let ids: Vec<usize> = ids.iter().copied().filter(|id| seen.insert(*id)).collect();
+        let backend_ids = id_mapper.get_ids(&ids)?;
+
+        let mut envelopes: HashMap<String, RawEnvelope> = self
+            .get_raw_envelopes(backend_kind, folder, &backend_ids)
+            .await?
+            .into_iter()
+            .map(|envelope| (envelope.id.clone(), envelope))
+            .collect();
+
+        // positions of envelopes by Message-ID, `None` when shared
+        let mut positions: HashMap<String, Option<usize>> = HashMap::new();
+        for (pos, id) in backend_ids.iter().enumerate() {
+            let message_id = envelopes
+                .get(id)
+                .and_then(|envelope| parse_message_id(&envelope.headers));
+            if let Some(message_id) = message_id {
+                positions
+                    .entry(message_id)
+                    .and_modify(|pos| *pos = None)
+                    .or_insert(Some(pos));
+            }
+        }
+
+        let mut raws: Vec<Option<Vec<u8>>> = vec![None; backend_ids.len()];
+        let messages = self
+            .backend
+            .peek_messages(folder, &Id::multiple(backend_ids.clone()))
+            .await?;
+
+        for message in messages.to_vec() {
+            let raw = message.raw()?;
+            let pos = parse_message_id(raw).and_then(|id| positions.get(&id).copied().flatten());
+            if let Some(pos) = pos {
+                raws[pos] = Some(raw.to_vec());
+            }
+        }
+
+        let mut peeked = Vec::with_capacity(ids.len());
+
+        for ((id, backend_id), raw) in ids.into_iter().zip(&backend_ids).zip(raws) {
+            let raw = match raw {
+                Some(raw) => raw,
+                None => {
+                    let backend_id = Id::single(backend_id.clone());
+                    let messages = self.backend.peek_messages(folder, &backend_id).await?;
+                    let message = messages
+                        .first()
+                        .ok_or_else(|| anyhow!("cannot find message {id} in folder {folder}"))?;
+                    message.raw()?.to_vec()
+                }
+            };
+
+            let flags = envelopes
+                .remove(backend_id)
+                .map(|envelope| envelope.flags)
+                .unwrap_or_default();
+
+            peeked.push((id, flags, raw));
+        }
+
+        Ok(peeked)
+    }
+
     pub async fn get_messages(&self, folder: &str, ids: &[usize]) -> Result<Messages> {
         let backend_kind = self.toml_account_config.get_messages_kind();
         let id_mapper = self.build_id_mapper(folder, backend_kind)?;
@@ -724,12 +1297,27 @@ impl Backend {
         to_folder: &str,
         ids: &[usize],
     ) -> Result<()> {
-        let backend_kind = self.toml_account_config.move_messages_kind();
+        let backend_kind = self.toml_account_config.copy_messages_kind();
         let id_mapper = self.build_id_mapper(from_folder, backend_kind)?;
-        let ids = Id::multiple(id_mapper.get_ids(ids)?);
-        self.backend
-            .copy_messages(from_folder, to_folder, &ids)
-            .await
+        let ids = id_mapper.get_ids(ids)?;
+        let snapshot = self
+            .snapshot_journal_envelopes(backend_kind, from_folder, &ids, Some(to_folder))
+            .await;
+        let (done, res) = apply_by_batches(&ids, |id| async move {
+            self.backend
+                .copy_messages(from_folder, to_folder, &id)
+                .await
+        })
+        .await;
+        self.record_journal_operation(snapshot, backend_kind, Some(to_folder), done, |envelopes| {
+            Operation::Copy {
+                from: from_folder.to_owned(),
+                to: to_folder.to_owned(),
+                envelopes,
+            }
+        })
+        .await;
+        res
     }
 
     pub async fn move_messages(
@@ -740,18 +1328,487 @@ impl Backend {
     ) -> Result<()> {
         let backend_kind = self.toml_account_config.move_messages_kind();
         let id_mapper = self.build_id_mapper(from_folder, backend_kind)?;
-        let ids = Id::multiple(id_mapper.get_ids(ids)?);
-        self.backend
-            .move_messages(from_folder, to_folder, &ids)
-            .await
+        let ids = id_mapper.get_ids(ids)?;
+        let snapshot = self
+            .snapshot_journal_envelopes(backend_kind, from_folder, &ids, Some(to_folder))
+            .await;
+        let (done, res) = apply_by_batches(&ids, |id| async move {
+            self.backend
+                .move_messages(from_folder, to_folder, &id)
+                .await
+        })
+        .await;
+        self.record_journal_operation(snapshot, backend_kind, Some(to_folder), done, |envelopes| {
+            Operation::Move {
+                from: from_folder.to_owned(),
+                to: to_folder.to_owned(),
+                envelopes,
+            }
+        })
+        .await;
+        res
     }
 
     pub async fn delete_messages(&self, folder: &str, ids: &[usize]) -> Result<()> {
         let backend_kind = self.toml_account_config.delete_messages_kind();
         let id_mapper = self.build_id_mapper(folder, backend_kind)?;
-        let ids = Id::multiple(id_mapper.get_ids(ids)?);
-        self.backend.delete_messages(folder, &ids).await
+        let ids = id_mapper.get_ids(ids)?;
+
+        // messages are moved to the trash, unless they already are
+        // in the trash
+        let trash = self.account_config.get_trash_folder_alias();
+        let trash =
+            Some(trash).filter(|trash| self.account_config.get_folder_alias(folder) != *trash);
+
+        let snapshot = self
+            .snapshot_journal_envelopes(backend_kind, folder, &ids, trash.as_deref())
+            .await;
+        let (done, res) = apply_by_batches(&ids, |id| async move {
+            self.backend.delete_messages(folder, &id).await
+        })
+        .await;
+        self.record_journal_operation(
+            snapshot,
+            backend_kind,
+            trash.as_deref(),
+            done,
+            |envelopes| Operation::Delete {
+                folder: folder.to_owned(),
+                trash: trash.clone(),
+                envelopes,
+            },
+        )
+        .await;
+        res
+    }
+
+    /// Redirect the given raw message to the given addresses, based
+    /// on the send message backend.
+    ///
+    /// The message is expected to contain the resent fields already.
+    pub async fn redirect_message(&self, msg: &[u8], to: &[String]) -> Result<()> {
+        let backend_kind = self.toml_account_config.send_message_kind();
+        #[allow(unused)]
+        let ctx = &self.backend.context;
+
+        match backend_kind {
+            #[cfg(feature = "smtp")]
+            Some(BackendKind::Smtp) => {
+                let ctx = ctx
+                    .smtp
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("cannot find smtp context"))?;
+                redirect_smtp_message(ctx, msg, &self.account_config.email, to).await
+            }
+            #[cfg(feature = "sendmail")]
+            Some(BackendKind::Sendmail) => {
+                let ctx = ctx
+                    .sendmail
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("cannot find sendmail context"))?;
+                redirect_sendmail_message(ctx, msg, to).await
+            }
+            Some(kind) => bail!("cannot redirect message: backend {kind} not supported"),
+            None => bail!("cannot redirect message: backend not defined"),
+        }
+    }
+
+    /// Get the raw envelopes matching the given backend ids from the
+    /// given folder, using the given backend.
+    async fn get_raw_envelopes(
+        &self,
+        backend_kind: Option<&BackendKind>,
+        folder: &str,
+        ids: &[String],
+    ) -> Result<Vec<RawEnvelope>> {
+        #[allow(unused)]
+        let ctx = &self.backend.context;
+
+        let envelopes = match backend_kind {
+            #[cfg(feature = "imap")]
+            Some(BackendKind::Imap) => {
+                let ctx = ctx
+                    .imap
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("cannot find imap context"))?;
+                get_imap_envelopes(ctx, folder, ids).await?
+            }
+            #[cfg(feature = "maildir")]
+            Some(BackendKind::Maildir) => {
+                let ctx = ctx
+                    .maildir
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("cannot find maildir context"))?;
+                get_maildir_envelopes(ctx, folder, ids).await?
+            }
+            #[cfg(feature = "account-sync")]
+            Some(BackendKind::MaildirForSync) => {
+                let ctx = ctx
+                    .maildir_for_sync
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("cannot find maildir context"))?;
+                get_maildir_envelopes(ctx, folder, ids).await?
+            }
+            #[cfg(feature = "notmuch")]
+            Some(BackendKind::Notmuch) => {
+                let ctx = ctx
+                    .notmuch
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("cannot find notmuch context"))?;
+                get_notmuch_envelopes(ctx, folder, ids).await?
+            }
+            Some(kind) => bail!("cannot get envelopes: backend {kind} not supported"),
+            None => bail!("cannot get envelopes: backend not defined"),
+        };
+
+        Ok(envelopes)
+    }
+
+    /// Capture the state of the given envelopes before a mutating
+    /// operation, so that it can be recorded in the journal.
+    ///
+    /// When the operation copies envelopes to a target IMAP folder,
+    /// the next UID of the target folder is captured as well, so that
+    /// only envelopes added by the operation are searched afterwards.
+    ///
+    /// Journaling is best-effort: failures are logged and never
+    /// prevent the operation.
+    async fn snapshot_journal_envelopes(
+        &self,
+        backend_kind: Option<&BackendKind>,
+        folder: &str,
+        ids: &[String],
+        target: Option<&str>,
+    ) -> Option<JournalSnapshot> {
+        let retention = self.toml_account_config.journal_retention();
+        let journal = match Journal::new(&self.account_config, retention) {
+            Ok(journal) if journal.is_enabled() => journal,
+            Ok(_) => return None,
+            Err(err) => {
+                warn!("cannot open journal, skipping it: {err}");
+                debug!("{err:?}");
+                return None;
+            }
+        };
+
+        let snapshot = async {
+            let envelopes =
+                get_by_batches(ids, |ids| self.get_raw_envelopes(backend_kind, folder, ids))
+                    .await?
+                    .iter()
+                    .map(JournalEnvelope::from_raw)
+                    .collect();
+
+            let target_uid_next = match target {
+                Some(target) => self.get_uid_next(backend_kind, target).await?,
+                None => None,
+            };
+
+            Result::<_>::Ok(JournalSnapshot {
+                journal,
+                envelopes,
+                target_uid_next,
+            })
+        };
+
+        match snapshot.await {
+            Ok(snapshot) => Some(snapshot),
+            Err(err) => {
+                warn!("cannot get envelopes for the journal, skipping it: {err}");
+                debug!("{err:?}");
+                None
+            }
+        }
+    }
+
+    /// Record the operation built by the given function in the
+    /// journal, for the envelopes of the given snapshot matching the
+    /// given ids (the ones the operation was applied to).
+    ///
+    /// When the operation copies envelopes to the given target
+    /// folder, the ids of the copies are recorded as well.
+    async fn record_journal_operation(
+        &self,
+        snapshot: Option<JournalSnapshot>,
+        backend_kind: Option<&BackendKind>,
+        target: Option<&str>,
+        ids: &[String],
+        operation: impl FnOnce(Vec<JournalEnvelope>) -> Operation,
+    ) {
+        let Some(JournalSnapshot {
+            journal,
+            mut envelopes,
+            target_uid_next,
+        }) = snapshot
+        else {
+            return;
+        };
+
+        envelopes.retain(|envelope| ids.contains(&envelope.id));
+
+        if envelopes.is_empty() {
+            debug!("no envelope affected, skipping journal record");
+            return;
+        }
+
+        if let Some(target) = target {
+            let res = self
+                .find_journal_targets(backend_kind, target, target_uid_next, &mut envelopes)
+                .await;
+
+            if let Err(err) = res {
+                warn!("cannot find copies of envelopes in {target} for the journal: {err}");
+                debug!("{err:?}");
+            }
+        }
+
+        if let Err(err) = journal.record(operation(envelopes)) {
+            warn!("cannot record operation in journal: {err}");
+            debug!("{err:?}");
+        }
+    }
+
+    /// Set the target id of the given journal envelopes.
+    ///
+    /// Maildir and Notmuch keep ids across folders. IMAP copies are
+    /// searched by Message-ID among the envelopes added to the target
+    /// folder since the given next UID.
+    async fn find_journal_targets(
+        &self,
+        backend_kind: Option<&BackendKind>,
+        target: &str,
+        #[allow(unused)] uid_next: Option<u32>,
+        envelopes: &mut [JournalEnvelope],
+    ) -> Result<()> {
+        #[allow(unused)]
+        let ctx = &self.backend.context;
+
+        match backend_kind {
+            #[cfg(feature = "imap")]
+            Some(BackendKind::Imap) => {
+                let ctx = ctx
+                    .imap
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("cannot find imap context"))?;
+
+                let message_ids: Vec<String> = envelopes
+                    .iter()
+                    .filter(|envelope| !envelope.message_id.is_empty())
+                    .map(|envelope| envelope.message_id.clone())
+                    .collect();
+
+                let uids = get_by_batches(&message_ids, |message_ids| {
+                    search_imap_message_ids(ctx, target, uid_next, message_ids)
+                })
+                .await?;
+
+                let mut candidates =
+                    get_by_batches(&uids, |uids| get_imap_envelopes(ctx, target, uids)).await?;
+
+                for envelope in envelopes {
+                    let pos = candidates
+                        .iter()
+                        .position(|raw| raw.to_envelope().message_id == envelope.message_id);
+                    if let Some(pos) = pos {
+                        envelope.target_id = Some(candidates.swap_remove(pos).id);
+                    }
+                }
+            }
+            Some(_) => {
+                for envelope in envelopes {
+                    envelope.target_id = Some(envelope.id.clone());
+                }
+            }
+            None => bail!("cannot find envelopes copies: backend not defined"),
+        }
+
+        Ok(())
+    }
+
+    /// Get the next UID of the given folder, for IMAP only.
+    async fn get_uid_next(
+        &self,
+        backend_kind: Option<&BackendKind>,
+        #[allow(unused)] folder: &str,
+    ) -> Result<Option<u32>> {
+        #[allow(unused)]
+        let ctx = &self.backend.context;
+
+        let uid_next = match backend_kind {
+            #[cfg(feature = "imap")]
+            Some(BackendKind::Imap) => {
+                let ctx = ctx
+                    .imap
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("cannot find imap context"))?;
+                get_imap_uid_next(ctx, folder).await?
+            }
+            _ => None,
+        };
+
+        Ok(uid_next)
+    }
+
+    /// Replay the inverse of the given journal operation.
+    ///
+    /// Inverse operations are not recorded in the journal. Moved,
+    /// copied and deleted messages are found back in their target
+    /// folder by the ids recorded with the operation.
+    pub async fn undo_operation(&self, operation: &Operation) -> Result<()> {
+        match operation {
+            Operation::AddFlags {
+                folder, envelopes, ..
+            }
+            | Operation::SetFlags {
+                folder, envelopes, ..
+            }
+            | Operation::RemoveFlags {
+                folder, envelopes, ..
+            }
+            | Operation::Delete {
+                folder,
+                trash: None,
+                envelopes,
+            } => self.restore_journal_flags(folder, envelopes).await,
+            Operation::Move {
+                from,
+                to,
+                envelopes,
+            } => {
+                let ids = journal_target_ids(to, envelopes)?;
+                apply_by_batches(&ids, |id| async move {
+                    self.backend.move_messages(to, from, &id).await
+                })
+                .await
+                .1
+            }
+            Operation::Copy { to, envelopes, .. } => {
+                let ids = journal_target_ids(to, envelopes)?;
+                apply_by_batches(&ids, |id| async move {
+                    self.backend.delete_messages(to, &id).await
+                })
+                .await
+                .1
+            }
+            Operation::Delete {
+                folder,
+                trash: Some(trash),
+                envelopes,
+            } => {
+                let ids = journal_target_ids(trash, envelopes)?;
+                apply_by_batches(&ids, |id| async move {
+                    self.backend.move_messages(trash, folder, &id).await
+                })
+                .await
+                .1
+            }
+        }
     }
+
+    /// Restore the flags of the given journal envelopes, grouping
+    /// envelopes sharing the same flags.
+    async fn restore_journal_flags(
+        &self,
+        folder: &str,
+        envelopes: &[JournalEnvelope],
+    ) -> Result<()> {
+        let mut groups: Vec<(Flags, Vec<String>)> = Vec::new();
+
+        for envelope in envelopes {
+            let flags = envelope.flags();
+            match groups.iter_mut().find(|(group, _)| *group == flags) {
+                Some((_, ids)) => ids.push(envelope.id.clone()),
+                None => groups.push((flags, vec![envelope.id.clone()])),
+            }
+        }
+
+        for (flags, ids) in groups {
+            let flags = &flags;
+            apply_by_batches(&ids, |id| async move {
+                self.backend.set_flags(folder, &id, flags).await
+            })
+            .await
+            .1?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The state captured before a journaled operation.
+struct JournalSnapshot {
+    journal: Journal,
+    envelopes: Vec<JournalEnvelope>,
+    /// The next UID of the target IMAP folder of the operation, if
+    /// any.
+    target_uid_next: Option<u32>,
+}
+
+/// Parse the Message-ID of the given raw message or headers.
+fn parse_message_id(raw: &[u8]) -> Option<String> {
+    let headers = MessageParser::new().parse_headers(raw)?;
+    headers.message_id().map(ToOwned::to_owned)
+}
+
+/// Get the ids of the copies of the given journal envelopes in the
+/// given folder.
+fn journal_target_ids(folder: &str, envelopes: &[JournalEnvelope]) -> Result<Vec<String>> {
+    let ids: Vec<_> = envelopes
+        .iter()
+        .filter_map(|envelope| envelope.target_id.clone())
+        .collect();
+
+    if ids.is_empty() {
+        bail!("cannot find messages to restore in folder {folder}");
+    }
+
+    if ids.len() < envelopes.len() {
+        warn!(
+            "cannot find {} message(s) to restore in folder {folder}, skipping them",
+            envelopes.len() - ids.len()
+        );
+    }
+
+    Ok(ids)
+}
+
+/// Apply the given operation to the given ids by batches, so that
+/// large selections do not end up in oversized requests.
+///
+/// Returns the ids the operation was applied to, alongside with the
+/// result of the operation.
+async fn apply_by_batches<F>(ids: &[String], f: impl Fn(Id) -> F) -> (&[String], Result<()>)
+where
+    F: Future<Output = Result<()>>,
+{
+    let mut done = 0;
+
+    for batch in ids.chunks(BATCH_SIZE) {
+        if let Err(err) = f(Id::multiple(batch.to_vec())).await {
+            return (&ids[..done], Err(err));
+        }
+        done += batch.len();
+    }
+
+    (ids, Ok(()))
+}
+
+/// Get items matching the given ids by batches, so that large
+/// selections do not end up in oversized requests.
+async fn get_by_batches<'a, T, F>(
+    ids: &'a [String],
+    f: impl Fn(&'a [String]) -> F,
+) -> Result<Vec<T>>
+where
+    F: Future<Output = Result<Vec<T>>>,
+{
+    let mut items = Vec::with_capacity(ids.len());
+
+    for batch in ids.chunks(BATCH_SIZE) {
+        items.extend(f(batch).await?);
+    }
+
+    Ok(items)
 }
 
 impl Deref for Backend {
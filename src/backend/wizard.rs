@@ -43,7 +43,7 @@ pub(crate) async fn configure(
         .items(DEFAULT_BACKEND_KINDS)
         .default(0)
         .interact_opt()?
-        .and_then(|idx| DEFAULT_BACKEND_KINDS.get(idx).map(Clone::clone));
+        .and_then(|idx| DEFAULT_BACKEND_KINDS.get(idx).cloned());
 
     let config = match kind {
         #[cfg(feature = "imap")]
@@ -76,7 +76,7 @@ pub(crate) async fn configure_sender(
         .items(SEND_MESSAGE_BACKEND_KINDS)
         .default(0)
         .interact_opt()?
-        .and_then(|idx| SEND_MESSAGE_BACKEND_KINDS.get(idx).map(Clone::clone));
+        .and_then(|idx| SEND_MESSAGE_BACKEND_KINDS.get(idx).cloned());
 
     let config = match kind {
         #[cfg(feature = "smtp")]
@@ -1,12 +1,12 @@
 pub mod arg;
 pub mod args;
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use dirs::data_dir;
 use email::account::config::AccountConfig;
 use log::debug;
 use sled::{Config, Db};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 #[derive(Debug)]
 pub enum IdMapper {
@@ -48,7 +48,7 @@ impl IdMapper {
                     .to_string();
                 debug!("created alias {alias} for id {id}");
 
-                conn.insert(&id, alias.as_bytes())
+                conn.insert(id, alias.as_bytes())
                     .with_context(|| format!("cannot insert alias {alias} for id {id}"))?;
 
                 Ok(alias)
@@ -100,7 +100,7 @@ impl IdMapper {
 
                 let id = conn
                     .iter()
-                    .flat_map(|entry| entry)
+                    .flatten()
                     .find_map(|(entry_id, entry_alias)| {
                         if entry_alias.as_ref() == alias.as_bytes() {
                             let entry_id = String::from_utf8_lossy(entry_id.as_ref());
@@ -117,28 +117,46 @@ impl IdMapper {
         }
     }
 
+    /// Get the ids matching the given aliases, in the same order.
+    ///
+    /// Aliases that cannot be found lead to an error, so that no
+    /// envelope is silently left out.
     pub fn get_ids(&self, aliases: impl IntoIterator<Item = impl ToString>) -> Result<Vec<String>> {
-        let aliases: Vec<String> = aliases.into_iter().map(|alias| alias.to_string()).collect();
+        let mut aliases: Vec<String> = aliases.into_iter().map(|alias| alias.to_string()).collect();
+        let mut seen = HashSet::new();
+        aliases.retain(|alias| seen.insert(alias.clone()));
 
         match self {
             Self::Dummy => Ok(aliases),
             Self::Mapper(conn) => {
-                let aliases: HashSet<&str> = aliases.iter().map(|alias| alias.as_str()).collect();
-                let ids: Vec<String> = conn
+                let mut ids: HashMap<String, String> = conn
                     .iter()
-                    .flat_map(|entry| entry)
+                    .flatten()
                     .filter_map(|(entry_id, entry_alias)| {
                         let alias = String::from_utf8_lossy(entry_alias.as_ref());
-                        if aliases.contains(alias.as_ref()) {
+                        if seen.contains(alias.as_ref()) {
                             let entry_id = String::from_utf8_lossy(entry_id.as_ref());
-                            Some(entry_id.to_string())
+                            Some((alias.to_string(), entry_id.to_string()))
                         } else {
                             None
                         }
                     })
                     .collect();
 
-                Ok(ids)
+                let missing: Vec<_> = aliases
+                    .iter()
+                    .filter(|alias| !ids.contains_key(*alias))
+                    .map(String::as_str)
+                    .collect();
+
+                if !missing.is_empty() {
+                    bail!("cannot get ids from aliases {}", missing.join(", "));
+                }
+
+                Ok(aliases
+                    .iter()
+                    .filter_map(|alias| ids.remove(alias))
+                    .collect())
             }
         }
     }
@@ -9,11 +9,13 @@ use crate::{
     envelope::command::EnvelopeSubcommand,
     flag::command::FlagSubcommand,
     folder::command::FolderSubcommand,
+    journal::command::{history::HistoryCommand, undo::UndoCommand},
     manual::command::ManualGenerateCommand,
     message::{
         attachment::command::AttachmentSubcommand, command::MessageSubcommand,
         template::command::TemplateSubcommand,
     },
+    outbox::command::OutboxSubcommand,
     output::{ColorFmt, OutputFmt},
     printer::Printer,
 };
@@ -73,6 +75,15 @@ pub struct Cli {
     #[arg(long, short = 'C', global = true)]
     #[arg(value_name = "MODE", value_enum, default_value_t = Default::default())]
     pub color: ColorFmt,
+
+    /// Disable the pager
+    ///
+    /// By default, long plain output printed to a terminal is piped
+    /// to a pager when it does not fit in it. The pager command is
+    /// taken from the 'pager' configuration option, then from the
+    /// PAGER environment variable, then defaults to 'less'.
+    #[arg(long, global = true)]
+    pub no_pager: bool,
 }
 
 #[derive(Subcommand, Debug)]
@@ -106,6 +117,14 @@ pub enum HimalayaCommand {
     #[command(alias = "templates", alias = "tpls", alias = "tpl")]
     Template(TemplateSubcommand),
 
+    #[command(subcommand)]
+    #[command(alias = "outboxes")]
+    Outbox(OutboxSubcommand),
+
+    Undo(UndoCommand),
+
+    History(HistoryCommand),
+
     #[command(arg_required_else_help = true)]
     #[command(alias = "manuals", alias = "mans")]
     Manual(ManualGenerateCommand),
@@ -124,31 +143,43 @@ impl HimalayaCommand {
     ) -> Result<()> {
         match self {
             Self::Account(cmd) => {
-                let config = TomlConfig::from_some_path_or_default(config_path).await?;
+                let config = load_config(printer, config_path).await?;
                 cmd.execute(printer, &config).await
             }
             Self::Folder(cmd) => {
-                let config = TomlConfig::from_some_path_or_default(config_path).await?;
+                let config = load_config(printer, config_path).await?;
                 cmd.execute(printer, &config).await
             }
             Self::Envelope(cmd) => {
-                let config = TomlConfig::from_some_path_or_default(config_path).await?;
+                let config = load_config(printer, config_path).await?;
                 cmd.execute(printer, &config).await
             }
             Self::Flag(cmd) => {
-                let config = TomlConfig::from_some_path_or_default(config_path).await?;
+                let config = load_config(printer, config_path).await?;
                 cmd.execute(printer, &config).await
             }
             Self::Message(cmd) => {
-                let config = TomlConfig::from_some_path_or_default(config_path).await?;
+                let config = load_config(printer, config_path).await?;
                 cmd.execute(printer, &config).await
             }
             Self::Attachment(cmd) => {
-                let config = TomlConfig::from_some_path_or_default(config_path).await?;
+                let config = load_config(printer, config_path).await?;
                 cmd.execute(printer, &config).await
             }
             Self::Template(cmd) => {
-                let config = TomlConfig::from_some_path_or_default(config_path).await?;
+                let config = load_config(printer, config_path).await?;
+                cmd.execute(printer, &config).await
+            }
+            Self::Outbox(cmd) => {
+                let config = load_config(printer, config_path).await?;
+                cmd.execute(printer, &config).await
+            }
+            Self::Undo(cmd) => {
+                let config = load_config(printer, config_path).await?;
+                cmd.execute(printer, &config).await
+            }
+            Self::History(cmd) => {
+                let config = load_config(printer, config_path).await?;
                 cmd.execute(printer, &config).await
             }
             Self::Manual(cmd) => cmd.execute(printer).await,
@@ -156,3 +187,14 @@ impl HimalayaCommand {
         }
     }
 }
+
+/// Read the TOML configuration, then pass its pager to the given
+/// printer.
+pub async fn load_config(
+    printer: &mut impl Printer,
+    config_path: Option<&PathBuf>,
+) -> Result<TomlConfig> {
+    let config = TomlConfig::from_some_path_or_default(config_path).await?;
+    printer.set_pager(config.pager.clone());
+    Ok(config)
+}
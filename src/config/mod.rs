@@ -30,6 +30,9 @@ pub struct TomlConfig {
     pub signature: Option<String>,
     pub signature_delim: Option<String>,
     pub downloads_dir: Option<PathBuf>,
+    /// The command used to page long output, takes precedence over
+    /// `$PAGER`. An empty command disables the pager.
+    pub pager: Option<String>,
     pub accounts: HashMap<String, TomlAccountConfig>,
 }
 
@@ -216,7 +219,7 @@ impl TomlConfig {
                                 #[cfg(feature = "account-sync")]
                                 sync: c.sync,
                             }),
-                            flag: config.flag.map(|c| FlagConfig {
+                            flag: config.flag.map(|#[allow(unused)] c| FlagConfig {
                                 #[cfg(feature = "account-sync")]
                                 sync: c.sync,
                             }),
@@ -513,7 +513,7 @@ smtp.oauth2.scopes = []
         );
     }
 
-    #[cfg(feature = "pgp-cmds")]
+    #[cfg(feature = "pgp-commands")]
     #[test]
     fn pretty_serialize_pgp_cmds() {
         use email::account::config::pgp::PgpConfig;
@@ -0,0 +1,18 @@
+use clap::Parser;
+
+use crate::envelope::EnvelopeColumn;
+
+/// The envelope table columns argument parser.
+#[derive(Debug, Default, Parser)]
+pub struct EnvelopeColumnsFlag {
+    /// The columns of the envelopes table.
+    ///
+    /// Columns are separated by a comma, and are displayed in the
+    /// given order. Available columns are id, flags, subject, from,
+    /// to, cc, date, size and message-id. It overrides the columns
+    /// defined in the account configuration (envelope.list.columns).
+    ///
+    /// For example: 'id,flags,to,subject,size,date'.
+    #[arg(long = "columns", value_delimiter = ',', value_name = "COLUMNS")]
+    pub columns: Option<Vec<EnvelopeColumn>>,
+}
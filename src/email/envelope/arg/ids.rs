@@ -1,17 +1,537 @@
+use anyhow::{anyhow, bail, Context, Error, Result};
 use clap::Parser;
+use log::debug;
+use serde_json::Value;
+use std::{
+    collections::BTreeSet,
+    fmt,
+    io::{self, Read},
+    str::FromStr,
+};
+
+use crate::{backend::Backend, envelope::query::Query};
 
 /// The envelope id argument parser.
 #[derive(Debug, Parser)]
 pub struct EnvelopeIdArg {
     /// The envelope id.
+    ///
+    /// The id can be qualified by the name of its account, as shown
+    /// by multi-account listings (ACCOUNT:ID).
     #[arg(value_name = "ID", required = true)]
-    pub id: usize,
+    pub id: EnvelopeId,
+}
+
+impl EnvelopeIdArg {
+    /// Resolve the account targeted by the envelope id, falling
+    /// back to the given account name.
+    pub fn account<'a>(&'a self, account: Option<&'a str>) -> Result<Option<&'a str>> {
+        resolve_account(account, [self.id.account.as_deref()])
+    }
 }
 
 /// The envelopes ids arguments parser.
 #[derive(Debug, Parser)]
 pub struct EnvelopeIdsArgs {
     /// The list of envelopes ids.
-    #[arg(value_name = "ID", required = true)]
-    pub ids: Vec<usize>,
+    ///
+    /// Ids can be given as ranges, either closed (1-50 or 1..50) or
+    /// open (10..). Ids can also be qualified by the name of their
+    /// account, as shown by multi-account listings (ACCOUNT:ID). The
+    /// id - reads ids from the standard input.
+    #[arg(value_name = "ID")]
+    #[arg(required_unless_present_any = ["query", "all", "ids_from_stdin"])]
+    pub ids: Vec<EnvelopeIdsOrStdin>,
+
+    #[command(flatten)]
+    pub selector: EnvelopesSelectorFlags,
+}
+
+impl EnvelopeIdsArgs {
+    /// Collect the envelopes ids, including the ones from the
+    /// standard input if requested.
+    pub fn collect(&self) -> Result<Vec<EnvelopeIds>> {
+        collect_ids(&self.ids, &self.selector)
+    }
+}
+
+/// The envelopes selector flags parser.
+#[derive(Debug, Default, Parser)]
+pub struct EnvelopesSelectorFlags {
+    /// Select envelopes matching the given query, instead of ids.
+    ///
+    /// The query follows the same syntax as the envelope list
+    /// command, for example: 'from:news@example.com'.
+    #[arg(long, short, value_name = "QUERY", conflicts_with = "all")]
+    pub query: Option<Query>,
+
+    /// Select all envelopes of the folder, instead of ids.
+    #[arg(long)]
+    pub all: bool,
+
+    /// Read envelopes ids from the standard input.
+    ///
+    /// The standard input can contain ids separated by whitespaces
+    /// (ranges and qualified ids are accepted), or JSON envelopes as
+    /// printed by the envelope list command with the JSON output,
+    /// for example: envelope list -o json | jq '.[] | select(…)'.
+    /// JSON envelopes from another folder than the targeted one are
+    /// rejected. Giving the id - has the same effect.
+    #[arg(long, conflicts_with = "query", conflicts_with = "all")]
+    pub ids_from_stdin: bool,
+}
+
+impl EnvelopesSelectorFlags {
+    fn is_empty(&self) -> bool {
+        self.query.is_none() && !self.all
+    }
+
+    /// Return `true` if selecting the given ids requires to list the
+    /// envelopes of the folder, which is the case of ranges and
+    /// selector flags (query, all).
+    pub fn needs_listing(&self, ids: &[EnvelopeIds]) -> bool {
+        !self.is_empty() || ids.iter().any(EnvelopeIds::is_range)
+    }
+}
+
+/// The maximum amount of envelopes sent to the backend in a single
+/// call, so that large selections do not end up in oversized
+/// requests.
+pub const BATCH_SIZE: usize = 500;
+
+/// Represents an envelopes ids argument: either a selection of
+/// envelopes ids, or - in order to read them from the standard input.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum EnvelopeIdsOrStdin {
+    Ids(EnvelopeIds),
+    Stdin,
+}
+
+impl FromStr for EnvelopeIdsOrStdin {
+    type Err = Error;
+
+    fn from_str(ids: &str) -> Result<Self> {
+        match ids {
+            "-" => Ok(Self::Stdin),
+            ids => Ok(Self::Ids(ids.parse()?)),
+        }
+    }
+}
+
+/// Represents a selection of envelopes ids, optionally qualified by
+/// the name of its account (ACCOUNT:IDS).
+///
+/// The selection can be a single id (12), a closed range of ids
+/// (1-50 or 1..50) or an open range of ids (10..).
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub struct EnvelopeIds {
+    pub account: Option<String>,
+    pub start: usize,
+    /// The inclusive end of the range, `None` for open ranges.
+    pub end: Option<usize>,
+    /// The folder of the envelopes, only known when ids are read from
+    /// JSON envelopes.
+    pub folder: Option<String>,
+}
+
+impl EnvelopeIds {
+    /// Return `true` if the selection is a range of ids rather than a
+    /// single id.
+    pub fn is_range(&self) -> bool {
+        self.end != Some(self.start)
+    }
+}
+
+impl fmt::Display for EnvelopeIds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(account) = &self.account {
+            write!(f, "{account}:")?;
+        }
+
+        match self.end {
+            Some(end) if end == self.start => write!(f, "{end}"),
+            Some(end) => write!(f, "{}-{end}", self.start),
+            None => write!(f, "{}..", self.start),
+        }
+    }
+}
+
+impl FromStr for EnvelopeIds {
+    type Err = Error;
+
+    fn from_str(ids: &str) -> Result<Self> {
+        let (account, range) = match ids.rsplit_once(':') {
+            Some((account, range)) if !account.is_empty() => (Some(account.to_owned()), range),
+            Some(_) => bail!("cannot parse envelope ids {ids}: missing account name"),
+            None => (None, ids),
+        };
+
+        let parse = |id: &str| {
+            id.parse::<usize>()
+                .map_err(|err| anyhow!("cannot parse envelope ids {ids}: {err}"))
+        };
+
+        let (start, end) = if let Some(start) = range.strip_suffix("..") {
+            (parse(start)?, None)
+        } else if let Some((start, end)) = range.split_once("..").or(range.split_once('-')) {
+            (parse(start)?, Some(parse(end)?))
+        } else {
+            let id = parse(range)?;
+            (id, Some(id))
+        };
+
+        if let Some(end) = end {
+            if start > end {
+                bail!("cannot parse envelope ids {ids}: range start greater than range end");
+            }
+        }
+
+        Ok(Self {
+            account,
+            start,
+            end,
+            folder: None,
+        })
+    }
+}
+
+/// Collect the given envelopes ids, replacing - by the ids read from
+/// the standard input.
+pub fn collect_ids(
+    ids: &[EnvelopeIdsOrStdin],
+    selector: &EnvelopesSelectorFlags,
+) -> Result<Vec<EnvelopeIds>> {
+    let mut read_stdin = selector.ids_from_stdin;
+    let mut collected_ids = Vec::new();
+
+    for ids in ids {
+        match ids {
+            EnvelopeIdsOrStdin::Ids(ids) => collected_ids.push(ids.clone()),
+            EnvelopeIdsOrStdin::Stdin => read_stdin = true,
+        }
+    }
+
+    if read_stdin {
+        let mut input = String::new();
+        io::stdin()
+            .read_to_string(&mut input)
+            .context("cannot read envelopes ids from stdin")?;
+        collected_ids.extend(parse_ids(&input)?);
+
+        if collected_ids.is_empty() {
+            bail!("cannot find envelopes ids from stdin");
+        }
+    }
+
+    Ok(collected_ids)
+}
+
+/// Parse envelopes ids from the given input.
+///
+/// The input is either composed of JSON values (envelopes, ids or
+/// arrays of them), or of raw ids separated by whitespaces.
+fn parse_ids(input: &str) -> Result<Vec<EnvelopeIds>> {
+    let input = input.trim();
+
+    if !input.starts_with(['{', '[', '"']) {
+        return input
+            .split_whitespace()
+            .map(EnvelopeIds::from_str)
+            .collect();
+    }
+
+    let mut ids = Vec::new();
+
+    for value in serde_json::Deserializer::from_str(input).into_iter::<Value>() {
+        let value = value.context("cannot parse envelopes ids from json")?;
+        parse_json_ids(value, &mut ids)?;
+    }
+
+    Ok(ids)
+}
+
+/// Parse envelopes ids from the given JSON value.
+fn parse_json_ids(value: Value, ids: &mut Vec<EnvelopeIds>) -> Result<()> {
+    match value {
+        Value::Array(values) => {
+            for value in values {
+                parse_json_ids(value, ids)?;
+            }
+        }
+        Value::Object(mut envelope) => {
+            let Some(id) = envelope.remove("id") else {
+                bail!("cannot find id of json envelope");
+            };
+
+            let mut id = match id {
+                Value::String(id) => id.parse::<EnvelopeIds>()?,
+                Value::Number(id) => id.to_string().parse()?,
+                id => bail!("cannot parse envelope id {id}"),
+            };
+
+            if id.account.is_none() {
+                if let Some(Value::String(account)) = envelope.remove("account") {
+                    id.account = Some(account);
+                }
+            }
+
+            if let Some(Value::String(folder)) = envelope.remove("folder") {
+                id.folder = Some(folder);
+            }
+
+            ids.push(id);
+        }
+        Value::String(id) => ids.push(id.parse()?),
+        Value::Number(id) => ids.push(id.to_string().parse()?),
+        value => bail!("cannot parse envelope ids from json value {value}"),
+    }
+
+    Ok(())
+}
+
+/// Select envelopes ids from the given folder.
+///
+/// Single ids are selected as is, whereas ranges and selector flags
+/// (query, all) require to list the envelopes of the folder. Ids read
+/// from JSON envelopes of another folder are rejected.
+pub async fn select_ids(
+    backend: &Backend,
+    folder: &str,
+    ids: &[EnvelopeIds],
+    selector: &EnvelopesSelectorFlags,
+) -> Result<Vec<usize>> {
+    if !ids.is_empty() && !selector.is_empty() {
+        bail!("cannot select envelopes from both ids and query");
+    }
+
+    // ids are specific to their folder, so envelopes coming from
+    // another folder cannot be selected
+    let folder_alias = backend.account_config.get_folder_alias(folder);
+    for ids in ids {
+        match &ids.folder {
+            Some(ids_folder)
+                if backend.account_config.get_folder_alias(ids_folder) != folder_alias =>
+            {
+                bail!("cannot select envelopes {ids} from folder {ids_folder} in folder {folder}: use --folder {ids_folder}");
+            }
+            _ => (),
+        }
+    }
+
+    let folder_ids = if selector.needs_listing(ids) {
+        backend
+            .search_envelopes(folder, selector.query.as_ref(), None, 0, 0)
+            .await?
+            .iter()
+            .map(|envelope| {
+                envelope
+                    .id
+                    .parse::<usize>()
+                    .with_context(|| format!("cannot parse envelope id {}", envelope.id))
+            })
+            .collect::<Result<Vec<_>>>()?
+    } else {
+        Vec::new()
+    };
+
+    let ids = if selector.is_empty() {
+        expand_ids(ids, &folder_ids)
+    } else {
+        let mut ids = folder_ids;
+        ids.sort();
+        ids
+    };
+
+    if ids.is_empty() {
+        bail!("cannot find envelopes matching the given selection in {folder}");
+    }
+
+    debug!("selected envelopes ids {ids:?}");
+    Ok(ids)
+}
+
+/// Expand the given envelopes ids selections, in ascending order.
+///
+/// Ranges are expanded using the given ids of the folder, so that
+/// only existing ids are selected.
+fn expand_ids(ids: &[EnvelopeIds], folder_ids: &[usize]) -> Vec<usize> {
+    let mut expanded_ids = BTreeSet::new();
+
+    for ids in ids {
+        if !ids.is_range() {
+            expanded_ids.insert(ids.start);
+            continue;
+        }
+
+        let end = ids.end.unwrap_or(usize::MAX);
+        let folder_ids = folder_ids
+            .iter()
+            .filter(|id| (ids.start..=end).contains(*id));
+        expanded_ids.extend(folder_ids);
+    }
+
+    Vec::from_iter(expanded_ids)
+}
+
+/// Represents an envelope id, optionally qualified by the name of
+/// its account (ACCOUNT:ID).
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub struct EnvelopeId {
+    pub account: Option<String>,
+    pub id: usize,
+}
+
+impl fmt::Display for EnvelopeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.account {
+            Some(account) => write!(f, "{account}:{}", self.id),
+            None => write!(f, "{}", self.id),
+        }
+    }
+}
+
+impl FromStr for EnvelopeId {
+    type Err = Error;
+
+    fn from_str(id: &str) -> Result<Self> {
+        let (account, num) = match id.rsplit_once(':') {
+            Some((account, num)) if !account.is_empty() => (Some(account.to_owned()), num),
+            Some(_) => bail!("cannot parse envelope id {id}: missing account name"),
+            None => (None, id),
+        };
+
+        let id = num
+            .parse()
+            .map_err(|err| anyhow!("cannot parse envelope id {id}: {err}"))?;
+
+        Ok(Self { account, id })
+    }
+}
+
+/// Resolve the account targeted by the given envelopes ids accounts.
+///
+/// Ids without account qualifier target the given account. All
+/// qualified ids need to target the same account, which also needs
+/// to match the given account if any.
+pub fn resolve_account<'a>(
+    account: Option<&'a str>,
+    ids_accounts: impl IntoIterator<Item = Option<&'a str>>,
+) -> Result<Option<&'a str>> {
+    ids_accounts
+        .into_iter()
+        .flatten()
+        .try_fold(account, |account, id_account| match account {
+            Some(account) if account != id_account => {
+                bail!("cannot target envelopes from both accounts {account} and {id_account}")
+            }
+            _ => Ok(Some(id_account)),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expand_ids, parse_ids, resolve_account, EnvelopeId, EnvelopeIds};
+
+    fn accounts(ids: &[EnvelopeId]) -> impl Iterator<Item = Option<&str>> {
+        ids.iter().map(|id| id.account.as_deref())
+    }
+
+    #[test]
+    fn parse() {
+        let id: EnvelopeId = "12".parse().unwrap();
+        assert_eq!(id.account, None);
+        assert_eq!(id.id, 12);
+
+        let id: EnvelopeId = "work:12".parse().unwrap();
+        assert_eq!(id.account.as_deref(), Some("work"));
+        assert_eq!(id.id, 12);
+        assert_eq!(id.to_string(), "work:12");
+
+        assert!(":12".parse::<EnvelopeId>().is_err());
+        assert!("work:abc".parse::<EnvelopeId>().is_err());
+    }
+
+    #[test]
+    fn resolve() {
+        let ids: Vec<EnvelopeId> = ["1", "work:2", "work:3"]
+            .iter()
+            .map(|id| id.parse().unwrap())
+            .collect();
+
+        assert_eq!(resolve_account(None, accounts(&ids)).unwrap(), Some("work"));
+        assert_eq!(
+            resolve_account(Some("work"), accounts(&ids)).unwrap(),
+            Some("work")
+        );
+        assert!(resolve_account(Some("perso"), accounts(&ids)).is_err());
+        assert_eq!(
+            resolve_account(Some("perso"), accounts(&ids[..1])).unwrap(),
+            Some("perso")
+        );
+
+        let ids: Vec<EnvelopeId> = ["perso:1", "work:2"]
+            .iter()
+            .map(|id| id.parse().unwrap())
+            .collect();
+        assert!(resolve_account(None, accounts(&ids)).is_err());
+    }
+
+    #[test]
+    fn parse_ranges() {
+        let ids: EnvelopeIds = "12".parse().unwrap();
+        assert_eq!((ids.start, ids.end), (12, Some(12)));
+
+        let ids: EnvelopeIds = "work:1-50".parse().unwrap();
+        assert_eq!(ids.account.as_deref(), Some("work"));
+        assert_eq!((ids.start, ids.end), (1, Some(50)));
+        assert_eq!(ids.to_string(), "work:1-50");
+
+        let ids: EnvelopeIds = "1..50".parse().unwrap();
+        assert_eq!((ids.start, ids.end), (1, Some(50)));
+
+        let ids: EnvelopeIds = "10..".parse().unwrap();
+        assert_eq!((ids.start, ids.end), (10, None));
+        assert_eq!(ids.to_string(), "10..");
+
+        assert!("50-1".parse::<EnvelopeIds>().is_err());
+        assert!("1-".parse::<EnvelopeIds>().is_err());
+        assert!("seen".parse::<EnvelopeIds>().is_err());
+    }
+
+    #[test]
+    fn expand() {
+        let ids: Vec<EnvelopeIds> = ["3-5", "4", "8..", "1"]
+            .iter()
+            .map(|ids| ids.parse().unwrap())
+            .collect();
+
+        assert_eq!(
+            expand_ids(&ids, &[1, 2, 3, 7, 8, 9, 12]),
+            vec![1, 3, 4, 8, 9, 12]
+        );
+
+        let ids: Vec<EnvelopeIds> = vec!["1-4000000000".parse().unwrap()];
+        assert_eq!(expand_ids(&ids, &[2, 3, 7]), vec![2, 3, 7]);
+    }
+
+    #[test]
+    fn parse_stdin() {
+        let ids = parse_ids("1\n2-4 work:5\n").unwrap();
+        let ids: Vec<_> = ids.iter().map(ToString::to_string).collect();
+        assert_eq!(ids, vec!["1", "2-4", "work:5"]);
+
+        let input = r#"[{"id":"1","subject":"a"},{"id":"work:2","account":"work","folder":"Sent"}]
+            {"id": "3", "account": "perso"}
+            "4"
+            5"#;
+        let ids = parse_ids(input).unwrap();
+        let ids: Vec<_> = ids.iter().map(ToString::to_string).collect();
+        assert_eq!(ids, vec!["1", "work:2", "perso:3", "4", "5"]);
+
+        let ids = parse_ids(input).unwrap();
+        let folders: Vec<_> = ids.iter().map(|ids| ids.folder.as_deref()).collect();
+        assert_eq!(folders, vec![None, Some("Sent"), None, None, None]);
+
+        assert!(parse_ids(r#"{"subject":"a"}"#).is_err());
+    }
 }
@@ -0,0 +1,61 @@
+use anyhow::Result;
+use clap::Parser;
+use email::backend::feature::BackendFeatureSource;
+use log::info;
+
+#[cfg(feature = "account-sync")]
+use crate::cache::arg::disable::CacheDisableFlag;
+use crate::{
+    account::arg::name::AccountNameFlag, backend::Backend, config::TomlConfig,
+    envelope::arg::ids::EnvelopeIdArg, folder::arg::name::FolderNameOptionalFlag, printer::Printer,
+};
+
+/// Get an envelope.
+///
+/// This command allows you to get the full envelope of a message:
+/// all its addresses, its identification headers, its flags, its
+/// size and its date. The message body is not fetched.
+#[derive(Debug, Parser)]
+pub struct GetEnvelopeCommand {
+    #[command(flatten)]
+    pub folder: FolderNameOptionalFlag,
+
+    #[command(flatten)]
+    pub envelope: EnvelopeIdArg,
+
+    #[cfg(feature = "account-sync")]
+    #[command(flatten)]
+    pub cache: CacheDisableFlag,
+
+    #[command(flatten)]
+    pub account: AccountNameFlag,
+}
+
+impl GetEnvelopeCommand {
+    pub async fn execute(self, printer: &mut impl Printer, config: &TomlConfig) -> Result<()> {
+        info!("executing get envelope command");
+
+        let (toml_account_config, account_config) = config.clone().into_account_configs(
+            self.envelope.account(self.account.name.as_deref())?,
+            #[cfg(feature = "account-sync")]
+            self.cache.disable,
+        )?;
+
+        let folder = &self.folder.name;
+        let get_envelope_kind = toml_account_config.get_envelope_kind();
+
+        let backend = Backend::new(
+            toml_account_config.clone(),
+            account_config,
+            get_envelope_kind,
+            |builder| builder.set_get_envelope(BackendFeatureSource::Context),
+        )
+        .await?;
+
+        let envelope = backend
+            .get_full_envelope(folder, self.envelope.id.id)
+            .await?;
+
+        printer.print(envelope)
+    }
+}
@@ -9,6 +9,13 @@ use crate::{
     account::arg::name::AccountNameFlag,
     backend::Backend,
     config::TomlConfig,
+    envelope::{
+        arg::columns::EnvelopeColumnsFlag,
+        query::Query,
+        sort::Sort,
+        unified::{search_all_accounts_envelopes, search_all_folders_envelopes, ALL_FOLDERS},
+        EnvelopeColumn,
+    },
     folder::arg::name::FolderNameOptionalArg,
     printer::{PrintTableOpts, Printer},
     ui::arg::max_width::TableMaxWidthFlag,
@@ -36,6 +43,34 @@ pub struct ListEnvelopesCommand {
     #[arg(long, short = 's', value_name = "NUMBER")]
     pub page_size: Option<usize>,
 
+    /// Filter envelopes matching the given query.
+    ///
+    /// A query is composed of conditions combined with the boolean
+    /// operators and, or and not. Parenthesis can group conditions
+    /// together, and conditions separated by a space are combined
+    /// with and. Available conditions are from:PATTERN, to:PATTERN,
+    /// subject:PATTERN, body:PATTERN, flag:FLAG, before:DATE and
+    /// after:DATE. Dates follow the format YYYY-MM-DD, or can be
+    /// either today or yesterday.
+    ///
+    /// For example: 'from:alice and not flag:seen after:yesterday'.
+    #[arg(long, short, value_name = "QUERY")]
+    pub query: Option<Query>,
+
+    /// Sort envelopes by the given key.
+    ///
+    /// A sort is composed of a key (date, from, subject, size or to)
+    /// optionally followed by a colon and an order (asc or desc,
+    /// which defaults to asc). It overrides the sort defined in the
+    /// account configuration (envelope.list.sort).
+    ///
+    /// For example: 'date:desc' or 'subject'.
+    #[arg(long, short = 'S', value_name = "SORT")]
+    pub sort: Option<Sort>,
+
+    #[command(flatten)]
+    pub columns: EnvelopeColumnsFlag,
+
     #[command(flatten)]
     pub table: TableMaxWidthFlag,
 
@@ -45,6 +80,28 @@ pub struct ListEnvelopesCommand {
 
     #[command(flatten)]
     pub account: AccountNameFlag,
+
+    /// List envelopes of all accounts.
+    ///
+    /// Envelopes of the given folder are fetched from every
+    /// configured account concurrently, then merged together. Ids
+    /// are qualified by their account name (ACCOUNT:ID), so they can
+    /// be given as is to other commands.
+    #[arg(long, conflicts_with = "account_name")]
+    pub all_accounts: bool,
+
+    /// List envelopes of all folders.
+    ///
+    /// Envelopes are fetched from every folder of the account
+    /// concurrently, then merged together. Folders excluded from the
+    /// synchronization (folder.sync.filter) are skipped. Giving the
+    /// folder '*' has the same effect.
+    ///
+    /// Ids are specific to the folder of their envelope, which is
+    /// shown in the FOLDER column: other commands need this folder
+    /// to be given with --folder in order to use them.
+    #[arg(long)]
+    pub all_folders: bool,
 }
 
 impl Default for ListEnvelopesCommand {
@@ -53,10 +110,15 @@ impl Default for ListEnvelopesCommand {
             folder: Default::default(),
             page: 1,
             page_size: Default::default(),
+            query: Default::default(),
+            sort: Default::default(),
+            columns: Default::default(),
             table: Default::default(),
             #[cfg(feature = "account-sync")]
             cache: Default::default(),
             account: Default::default(),
+            all_accounts: Default::default(),
+            all_folders: Default::default(),
         }
     }
 }
@@ -72,22 +134,98 @@ impl ListEnvelopesCommand {
         )?;
 
         let folder = &self.folder.name;
+        let all_folders = self.all_folders || folder == ALL_FOLDERS;
         let page = 1.max(self.page) - 1;
         let page_size = self
             .page_size
             .unwrap_or_else(|| account_config.get_envelope_list_page_size());
 
-        let list_envelopes_kind = toml_account_config.list_envelopes_kind();
-
-        let backend = Backend::new(
-            toml_account_config.clone(),
-            account_config.clone(),
-            list_envelopes_kind,
-            |builder| builder.set_list_envelopes(BackendFeatureSource::Context),
-        )
-        .await?;
-
-        let envelopes = backend.list_envelopes(folder, page_size, page).await?;
+        let sort = self
+            .sort
+            .clone()
+            .or_else(|| toml_account_config.list_envelopes_sort().cloned());
+
+        let columns = self.columns.columns.clone().or_else(|| {
+            toml_account_config
+                .list_envelopes_columns()
+                .map(ToOwned::to_owned)
+        });
+
+        // the list envelopes feature is used when the table does not
+        // need headers it does not expose
+        let listable = columns
+            .as_deref()
+            .unwrap_or(&EnvelopeColumn::DEFAULT)
+            .iter()
+            .all(EnvelopeColumn::is_listable);
+
+        let mut envelopes = if self.all_accounts {
+            search_all_accounts_envelopes(
+                config,
+                (!all_folders).then_some(folder.as_str()),
+                self.query.as_ref(),
+                sort.as_ref(),
+                page_size,
+                page,
+                #[cfg(feature = "account-sync")]
+                self.cache.disable,
+            )
+            .await?
+        } else {
+            let list_envelopes_kind = toml_account_config.list_envelopes_kind();
+            let list_folders_kind = toml_account_config
+                .list_folders_kind()
+                .filter(|_| all_folders);
+
+            let backend = Backend::new(
+                toml_account_config.clone(),
+                account_config.clone(),
+                list_envelopes_kind.into_iter().chain(list_folders_kind),
+                |builder| {
+                    builder.set_list_envelopes(BackendFeatureSource::Context);
+                    if all_folders {
+                        builder.set_list_folders(BackendFeatureSource::Context);
+                    }
+                },
+            )
+            .await?;
+
+            if all_folders {
+                search_all_folders_envelopes(
+                    &backend,
+                    &toml_account_config,
+                    self.query.as_ref(),
+                    sort.as_ref(),
+                    page_size,
+                    page,
+                )
+                .await?
+            } else if self.query.is_none() && sort.is_none() && !printer.is_json() && listable {
+                backend.list_envelopes(folder, page_size, page).await?
+            } else {
+                backend
+                    .search_envelopes(folder, self.query.as_ref(), sort.as_ref(), page_size, page)
+                    .await?
+            }
+        };
+
+        // unified listings show the account and the folder of
+        // envelopes by default
+        let columns = match columns {
+            None if self.all_accounts || all_folders => {
+                let mut columns = EnvelopeColumn::DEFAULT.to_vec();
+                if all_folders {
+                    columns.insert(1, EnvelopeColumn::Folder);
+                }
+                if self.all_accounts {
+                    columns.insert(1, EnvelopeColumn::Account);
+                }
+                Some(columns)
+            }
+            columns => columns,
+        };
+        envelopes.set_columns(columns);
+        envelopes.set_flags_display(toml_account_config.flags_display());
 
         printer.print_table(
             Box::new(envelopes),
@@ -1,4 +1,6 @@
+pub mod get;
 pub mod list;
+pub mod thread;
 pub mod watch;
 
 use anyhow::Result;
@@ -6,7 +8,10 @@ use clap::Subcommand;
 
 use crate::{config::TomlConfig, printer::Printer};
 
-use self::{list::ListEnvelopesCommand, watch::WatchEnvelopesCommand};
+use self::{
+    get::GetEnvelopeCommand, list::ListEnvelopesCommand, thread::ThreadEnvelopesCommand,
+    watch::WatchEnvelopesCommand,
+};
 
 /// Manage envelopes.
 ///
@@ -19,6 +24,12 @@ pub enum EnvelopeSubcommand {
     #[command(alias = "lst")]
     List(ListEnvelopesCommand),
 
+    #[command()]
+    Thread(ThreadEnvelopesCommand),
+
+    #[command()]
+    Get(GetEnvelopeCommand),
+
     #[command()]
     Watch(WatchEnvelopesCommand),
 }
@@ -28,6 +39,8 @@ impl EnvelopeSubcommand {
     pub async fn execute(self, printer: &mut impl Printer, config: &TomlConfig) -> Result<()> {
         match self {
             Self::List(cmd) => cmd.execute(printer, config).await,
+            Self::Thread(cmd) => cmd.execute(printer, config).await,
+            Self::Get(cmd) => cmd.execute(printer, config).await,
             Self::Watch(cmd) => cmd.execute(printer, config).await,
         }
     }
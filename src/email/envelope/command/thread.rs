@@ -0,0 +1,96 @@
+use anyhow::Result;
+use clap::Parser;
+use email::backend::feature::BackendFeatureSource;
+use log::info;
+
+#[cfg(feature = "account-sync")]
+use crate::cache::arg::disable::CacheDisableFlag;
+use crate::{
+    account::arg::name::AccountNameFlag,
+    backend::Backend,
+    config::TomlConfig,
+    envelope::{arg::columns::EnvelopeColumnsFlag, query::Query},
+    folder::arg::name::FolderNameOptionalArg,
+    printer::{PrintTableOpts, Printer},
+    ui::arg::max_width::TableMaxWidthFlag,
+};
+
+/// Thread all envelopes.
+///
+/// This command allows you to group envelopes of the given folder
+/// into conversations, based on the Message-ID, In-Reply-To and
+/// References headers. Replies are displayed as a tree below the
+/// envelope they answer to.
+#[derive(Debug, Parser)]
+pub struct ThreadEnvelopesCommand {
+    #[command(flatten)]
+    pub folder: FolderNameOptionalArg,
+
+    /// Thread only envelopes matching the given query.
+    ///
+    /// The query follows the same syntax as the one of the list
+    /// envelopes command. Replies that do not match the query are
+    /// left out of their thread.
+    ///
+    /// For example: 'from:alice and not flag:seen after:yesterday'.
+    #[arg(long, short, value_name = "QUERY")]
+    pub query: Option<Query>,
+
+    #[command(flatten)]
+    pub columns: EnvelopeColumnsFlag,
+
+    #[command(flatten)]
+    pub table: TableMaxWidthFlag,
+
+    #[cfg(feature = "account-sync")]
+    #[command(flatten)]
+    pub cache: CacheDisableFlag,
+
+    #[command(flatten)]
+    pub account: AccountNameFlag,
+}
+
+impl ThreadEnvelopesCommand {
+    pub async fn execute(self, printer: &mut impl Printer, config: &TomlConfig) -> Result<()> {
+        info!("executing thread envelopes command");
+
+        let (toml_account_config, account_config) = config.clone().into_account_configs(
+            self.account.name.as_deref(),
+            #[cfg(feature = "account-sync")]
+            self.cache.disable,
+        )?;
+
+        let folder = &self.folder.name;
+        let list_envelopes_kind = toml_account_config.list_envelopes_kind();
+
+        let backend = Backend::new(
+            toml_account_config.clone(),
+            account_config.clone(),
+            list_envelopes_kind,
+            |builder| builder.set_list_envelopes(BackendFeatureSource::Context),
+        )
+        .await?;
+
+        let columns = self.columns.columns.clone().or_else(|| {
+            toml_account_config
+                .list_envelopes_columns()
+                .map(ToOwned::to_owned)
+        });
+
+        let mut threads = backend
+            .thread_envelopes(folder, self.query.as_ref())
+            .await?;
+        threads.set_columns(columns);
+        threads.set_flags_display(toml_account_config.flags_display());
+
+        printer.print_table(
+            Box::new(threads),
+            PrintTableOpts {
+                format: &account_config.get_message_read_format(),
+                max_width: self.table.max_width,
+            },
+        )?;
+
+        Ok(())
+    }
+}
@@ -1,23 +1,52 @@
-use anyhow::Result;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
 use clap::Parser;
-use email::{backend::feature::BackendFeatureSource, envelope::watch::WatchEnvelopes};
-use log::info;
+use email::{
+    account::config::AccountConfig, backend::feature::BackendFeatureSource,
+    envelope::watch::WatchEnvelopes, folder::INBOX,
+};
+use futures::{
+    channel::mpsc::{self, UnboundedSender},
+    StreamExt,
+};
+use log::{debug, info, warn};
+use tokio::time::MissedTickBehavior;
 
 #[cfg(feature = "account-sync")]
 use crate::cache::arg::disable::CacheDisableFlag;
 use crate::{
-    account::arg::name::AccountNameFlag, backend::Backend, config::TomlConfig,
-    folder::arg::name::FolderNameOptionalFlag, printer::Printer,
+    account::{arg::name::AccountNameFlag, config::TomlAccountConfig},
+    backend::Backend,
+    config::TomlConfig,
+    envelope::watch::{has_envelopes_events, spawn_watch, watch_folder_envelopes, WatchEvent},
+    outbox::{flush_outbox_or_warn, FLUSH_INTERVAL},
+    printer::Printer,
 };
 
 /// Watch envelopes for changes.
 ///
-/// This command allows you to watch a folder and execute hooks when
-/// changes occur on envelopes.
+/// This command allows you to watch folders and execute hooks when
+/// changes occur on envelopes. Every change (received, seen, unseen,
+/// flagged, unflagged, deleted or moved) is printed alongside with
+/// its account and its folder. When the JSON output is enabled, each
+/// change is printed as a JSON object on its own line.
+///
+/// Watchers run concurrently, each one on its own thread, and
+/// reconnect by themselves when their connection drops, following the
+/// policy defined in envelope.watch.reconnect. A failing watcher does
+/// not stop the other ones. The due messages of the outbox of watched
+/// accounts are sent meanwhile.
 #[derive(Debug, Parser)]
 pub struct WatchEnvelopesCommand {
-    #[command(flatten)]
-    pub folder: FolderNameOptionalFlag,
+    /// The names of the folders to watch.
+    ///
+    /// This flag can be repeated in order to watch several folders
+    /// at once. Defaults to the folders defined in the account
+    /// configuration (envelope.watch.folders), or to the inbox.
+    #[arg(long = "folder", short = 'f')]
+    #[arg(name = "folder_names", value_name = "NAME")]
+    pub folders: Vec<String>,
 
     #[cfg(feature = "account-sync")]
     #[command(flatten)]
@@ -25,33 +54,202 @@ pub struct WatchEnvelopesCommand {
 
     #[command(flatten)]
     pub account: AccountNameFlag,
+
+    /// Watch envelopes of all accounts.
+    ///
+    /// Folders of every configured account are watched concurrently
+    /// within the same process.
+    #[arg(long, conflicts_with = "account_name")]
+    pub all_accounts: bool,
 }
 
 impl WatchEnvelopesCommand {
     pub async fn execute(self, printer: &mut impl Printer, config: &TomlConfig) -> Result<()> {
         info!("executing watch envelopes command");
 
-        let folder = &self.folder.name;
-        let (toml_account_config, account_config) = config.clone().into_account_configs(
-            self.account.name.as_deref(),
-            #[cfg(feature = "account-sync")]
-            self.cache.disable,
-        )?;
+        let names: Vec<Option<&str>> = if self.all_accounts {
+            let mut names: Vec<_> = config
+                .accounts
+                .keys()
+                .map(|name| Some(name.as_str()))
+                .collect();
+            names.sort();
+            names
+        } else {
+            vec![self.account.name.as_deref()]
+        };
+
+        let json = printer.is_json();
+        let (tx, mut rx) = mpsc::unbounded();
+        let mut watchers = 0;
+
+        for name in names {
+            let (toml_account_config, account_config) = config.clone().into_account_configs(
+                name,
+                #[cfg(feature = "account-sync")]
+                self.cache.disable,
+            )?;
+
+            let folders = if !self.folders.is_empty() {
+                self.folders.clone()
+            } else if let Some(folders) = toml_account_config.watch_envelopes_folders() {
+                folders.to_vec()
+            } else {
+                vec![INBOX.to_owned()]
+            };
 
-        let watch_envelopes_kind = toml_account_config.watch_envelopes_kind();
+            let outbox = (
+                tx.clone(),
+                toml_account_config.clone(),
+                account_config.clone(),
+            );
+            spawn_watch(move || flush_outbox_periodically(outbox.0, outbox.1, outbox.2));
 
-        let backend = Backend::new(
+            for folder in folders {
+                let tx = tx.clone();
+                let toml_account_config = toml_account_config.clone();
+                let account_config = account_config.clone();
+
+                let target = format!("folder {folder} of account {}", account_config.name);
+                let watcher = spawn_watch({
+                    let tx = tx.clone();
+                    move || async move {
+                        watch_folder(toml_account_config, account_config, &folder, json, &tx).await
+                    }
+                });
+
+                // the end of the watcher is reported once its thread
+                // finished, so that a panicking watcher is reported
+                // as well
+                tokio::task::spawn_blocking(move || {
+                    let res = match watcher.join() {
+                        Ok(res) => res,
+                        Err(_) => Err(anyhow!("watcher panicked")),
+                    };
+                    let res = res.map_err(|err| err.context(format!("cannot watch {target}")));
+                    let _ = tx.unbounded_send(WatchMessage::Done(res));
+                });
+
+                watchers += 1;
+            }
+        }
+
+        drop(tx);
+
+        let mut errors = 0;
+        let mut last_err = None;
+
+        while watchers > 0 {
+            match rx.next().await {
+                Some(WatchMessage::Log(log)) => printer.print_log(log)?,
+                Some(WatchMessage::Event(event)) => printer.print_stream(*event)?,
+                Some(WatchMessage::Done(Ok(()))) => watchers -= 1,
+                Some(WatchMessage::Done(Err(err))) => {
+                    warn!("{err:#}");
+                    errors += 1;
+                    watchers -= 1;
+                    last_err = Some(err);
+                }
+                None => break,
+            }
+        }
+
+        match last_err {
+            Some(err) if errors == 1 => Err(err),
+            Some(err) => Err(err.context(format!("cannot watch {errors} folders"))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A message sent by watchers to the printer.
+enum WatchMessage {
+    Log(String),
+    Event(Box<WatchEvent>),
+    /// The watcher ended, either because it was stopped or because
+    /// it failed. It is sent once the thread of the watcher finished,
+    /// even if the watcher panicked.
+    Done(Result<()>),
+}
+
+/// Send the given message to the printer.
+fn send(tx: &UnboundedSender<WatchMessage>, msg: WatchMessage) -> Result<()> {
+    tx.unbounded_send(msg)
+        .map_err(|_| anyhow!("cannot send watch message: printer stopped"))
+}
+
+/// Watch the given folder of the given account, sending its events
+/// to the printer.
+///
+/// Backends that do not support envelopes events fall back to the
+/// watcher of the backend when printing plain text, which executes
+/// hooks only.
+async fn watch_folder(
+    toml_account_config: Arc<TomlAccountConfig>,
+    account_config: Arc<AccountConfig>,
+    folder: &str,
+    json: bool,
+    tx: &UnboundedSender<WatchMessage>,
+) -> Result<()> {
+    let account = account_config.name.clone();
+    let watch_envelopes_kind = toml_account_config.watch_envelopes_kind();
+
+    // each folder needs its own backend, since a watch monopolizes
+    // the backend connection
+    let connect = || async {
+        Backend::new(
             toml_account_config.clone(),
-            account_config,
+            account_config.clone(),
             watch_envelopes_kind,
             |builder| builder.set_watch_envelopes(BackendFeatureSource::Context),
         )
-        .await?;
+        .await
+    };
+
+    send(
+        tx,
+        WatchMessage::Log(format!(
+            "Start watching folder {folder} of account {account} for envelopes changes…"
+        )),
+    )?;
+
+    if !json && !has_envelopes_events(watch_envelopes_kind) {
+        debug!("envelopes events not supported, using the backend watcher");
+        return connect().await?.watch_envelopes(folder).await;
+    }
+
+    let policy = toml_account_config.watch_envelopes_reconnect();
+
+    watch_folder_envelopes(&account, folder, &policy, connect, |event| {
+        send(tx, WatchMessage::Event(Box::new(event)))
+    })
+    .await
+}
+
+/// Send the due messages of the outbox of the given account at a
+/// regular interval, until the printer stops.
+async fn flush_outbox_periodically(
+    tx: UnboundedSender<WatchMessage>,
+    toml_account_config: Arc<TomlAccountConfig>,
+    account_config: Arc<AccountConfig>,
+) {
+    let account = account_config.name.clone();
+    let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        interval.tick().await;
 
-        printer.print_log(format!(
-            "Start watching folder {folder} for envelopes changes…"
-        ))?;
+        if tx.is_closed() {
+            break;
+        }
 
-        backend.watch_envelopes(folder).await
+        let sent = flush_outbox_or_warn(toml_account_config.clone(), account_config.clone()).await;
+        if sent > 0 {
+            let log = format!("Sent {sent} outbox message(s) of account {account}");
+            if send(&tx, WatchMessage::Log(log)).is_err() {
+                break;
+            }
+        }
     }
 }
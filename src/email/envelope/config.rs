@@ -1,9 +1,12 @@
 #[cfg(feature = "account-sync")]
 use email::envelope::sync::config::EnvelopeSyncConfig;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::{collections::HashSet, time::Duration};
 
-use crate::backend::BackendKind;
+use crate::{
+    backend::BackendKind,
+    envelope::{sort::Sort, EnvelopeColumn},
+};
 
 #[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
 pub struct EnvelopeConfig {
@@ -37,6 +40,8 @@ impl EnvelopeConfig {
 #[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
 pub struct ListEnvelopesConfig {
     pub backend: Option<BackendKind>,
+    pub sort: Option<Sort>,
+    pub columns: Option<Vec<EnvelopeColumn>>,
 
     #[serde(flatten)]
     pub remote: email::envelope::list::config::EnvelopeListConfig,
@@ -57,6 +62,8 @@ impl ListEnvelopesConfig {
 #[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
 pub struct WatchEnvelopesConfig {
     pub backend: Option<BackendKind>,
+    pub folders: Option<Vec<String>>,
+    pub reconnect: Option<WatchReconnectConfig>,
 
     #[serde(flatten)]
     pub remote: email::envelope::watch::config::WatchEnvelopeConfig,
@@ -74,6 +81,48 @@ impl WatchEnvelopesConfig {
     }
 }
 
+/// The reconnection policy of envelope watchers.
+///
+/// When a watcher loses its connection, it tries to reconnect after
+/// a delay that doubles at each failed attempt, starting from the
+/// initial delay and capped by the maximum delay.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct WatchReconnectConfig {
+    /// The delay before the first reconnection attempt, in seconds.
+    pub initial_delay: Option<u64>,
+
+    /// The maximum delay between two reconnection attempts, in
+    /// seconds.
+    pub max_delay: Option<u64>,
+
+    /// The maximum amount of consecutive reconnection attempts
+    /// before giving up. Unlimited when omitted.
+    pub max_attempts: Option<u32>,
+}
+
+impl WatchReconnectConfig {
+    pub const DEFAULT_INITIAL_DELAY: u64 = 1;
+    pub const DEFAULT_MAX_DELAY: u64 = 300;
+
+    /// Compute the delay before the given reconnection attempt
+    /// (starting from 1), or `None` if no attempt remains.
+    pub fn find_delay(&self, attempt: u32) -> Option<Duration> {
+        if let Some(max_attempts) = self.max_attempts {
+            if attempt > max_attempts {
+                return None;
+            }
+        }
+
+        let initial_delay = self.initial_delay.unwrap_or(Self::DEFAULT_INITIAL_DELAY);
+        let max_delay = self.max_delay.unwrap_or(Self::DEFAULT_MAX_DELAY);
+        let factor = 2u64.saturating_pow(attempt.saturating_sub(1));
+        let delay = initial_delay.saturating_mul(factor).min(max_delay);
+
+        Some(Duration::from_secs(delay))
+    }
+}
+
 #[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
 pub struct GetEnvelopeConfig {
     pub backend: Option<BackendKind>,
@@ -90,3 +139,36 @@ impl GetEnvelopeConfig {
         kinds
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::WatchReconnectConfig;
+
+    #[test]
+    fn reconnect_delay() {
+        let config = WatchReconnectConfig {
+            initial_delay: Some(2),
+            max_delay: Some(10),
+            max_attempts: Some(4),
+        };
+
+        let delays: Vec<_> = (1..=5).map(|attempt| config.find_delay(attempt)).collect();
+
+        assert_eq!(
+            delays,
+            vec![
+                Some(Duration::from_secs(2)),
+                Some(Duration::from_secs(4)),
+                Some(Duration::from_secs(8)),
+                Some(Duration::from_secs(10)),
+                None,
+            ]
+        );
+
+        let config = WatchReconnectConfig::default();
+        assert_eq!(config.find_delay(1), Some(Duration::from_secs(1)));
+        assert_eq!(config.find_delay(100), Some(Duration::from_secs(300)));
+    }
+}
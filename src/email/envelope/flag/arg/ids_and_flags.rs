@@ -2,41 +2,74 @@ use clap::Parser;
 use email::flag::{Flag, Flags};
 use log::debug;
 
+use crate::{
+    config::TomlConfig,
+    envelope::arg::ids::{EnvelopeIdsOrStdin, EnvelopesSelectorFlags},
+};
+
 /// The ids and/or flags arguments parser.
 #[derive(Debug, Parser)]
 pub struct IdsAndFlagsArgs {
     /// The list of ids and/or flags.
     ///
-    /// Every argument that can be parsed as an integer is considered
-    /// an id, otherwise it is considered as a flag.
+    /// Every argument that can be parsed as an integer or as a range
+    /// of integers (1-50, 1..50 or 10..), optionally qualified by the
+    /// name of a configured account (ACCOUNT:ID), is considered an
+    /// id, otherwise it is considered as a flag. The id - reads ids from the standard
+    /// input.
     #[arg(value_name = "ID-OR-FLAG", required = true)]
     pub ids_and_flags: Vec<IdOrFlag>,
+
+    #[command(flatten)]
+    pub selector: EnvelopesSelectorFlags,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
 pub enum IdOrFlag {
-    Id(usize),
+    /// The parsed ids, alongside with the raw argument so that it can
+    /// still be considered as a flag.
+    Id(EnvelopeIdsOrStdin, String),
     Flag(Flag),
 }
 
 impl From<&str> for IdOrFlag {
     fn from(value: &str) -> Self {
-        value.parse::<usize>().map(Self::Id).unwrap_or_else(|err| {
-            let flag = Flag::from(value);
-            debug!("cannot parse {value} as usize, parsing it as flag {flag}");
-            debug!("{err:?}");
-            Self::Flag(flag)
-        })
+        value
+            .parse::<EnvelopeIdsOrStdin>()
+            .map(|ids| Self::Id(ids, value.to_owned()))
+            .unwrap_or_else(|err| {
+                let flag = Flag::from(value);
+                debug!("cannot parse {value} as envelope id, parsing it as flag {flag}");
+                debug!("{err:?}");
+                Self::Flag(flag)
+            })
     }
 }
 
-pub fn into_tuple(ids_and_flags: &[IdOrFlag]) -> (Vec<usize>, Flags) {
+/// Split the given ids and flags.
+///
+/// Ids qualified by an unknown account name are considered as flags,
+/// so that custom flags containing a colon are not shadowed.
+pub fn into_tuple(
+    ids_and_flags: &[IdOrFlag],
+    config: &TomlConfig,
+) -> (Vec<EnvelopeIdsOrStdin>, Flags) {
     ids_and_flags.iter().fold(
         (Vec::default(), Flags::default()),
         |(mut ids, mut flags), arg| {
             match arg {
-                IdOrFlag::Id(id) => {
-                    ids.push(*id);
+                IdOrFlag::Id(EnvelopeIdsOrStdin::Ids(id), raw)
+                    if id
+                        .account
+                        .as_ref()
+                        .is_some_and(|account| !config.accounts.contains_key(account)) =>
+                {
+                    let flag = Flag::from(raw.as_str());
+                    debug!("unknown account in {raw}, parsing it as flag {flag}");
+                    flags.insert(flag);
+                }
+                IdOrFlag::Id(id, _) => {
+                    ids.push(id.clone());
                 }
                 IdOrFlag::Flag(flag) => {
                     flags.insert(flag.to_owned());
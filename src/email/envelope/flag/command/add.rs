@@ -9,6 +9,7 @@ use crate::{
     account::arg::name::AccountNameFlag,
     backend::Backend,
     config::TomlConfig,
+    envelope::arg::ids::{collect_ids, resolve_account, select_ids},
     flag::arg::ids_and_flags::{into_tuple, IdsAndFlagsArgs},
     folder::arg::name::FolderNameOptionalFlag,
     printer::Printer,
@@ -39,24 +40,40 @@ impl FlagAddCommand {
         info!("executing add flag(s) command");
 
         let folder = &self.folder.name;
-        let (ids, flags) = into_tuple(&self.args.ids_and_flags);
-        let (toml_account_config, account_config) = config.clone().into_account_configs(
+        let (ids, flags) = into_tuple(&self.args.ids_and_flags, config);
+        let ids = collect_ids(&ids, &self.args.selector)?;
+        let account = resolve_account(
             self.account.name.as_deref(),
+            ids.iter().map(|ids| ids.account.as_deref()),
+        )?;
+        let (toml_account_config, account_config) = config.clone().into_account_configs(
+            account,
             #[cfg(feature = "account-sync")]
             self.cache.disable,
         )?;
 
         let add_flags_kind = toml_account_config.add_flags_kind();
+        let needs_listing = self.args.selector.needs_listing(&ids);
+        let list_envelopes_kind = toml_account_config
+            .list_envelopes_kind()
+            .filter(|_| needs_listing);
 
         let backend = Backend::new(
             toml_account_config.clone(),
             account_config,
-            add_flags_kind,
-            |builder| builder.set_add_flags(BackendFeatureSource::Context),
+            add_flags_kind.into_iter().chain(list_envelopes_kind),
+            |builder| {
+                builder.set_add_flags(BackendFeatureSource::Context);
+                if needs_listing {
+                    builder.set_list_envelopes(BackendFeatureSource::Context);
+                }
+            },
         )
         .await?;
 
-        backend.add_flags(folder, &ids, &flags).await?;
+        let ids = &select_ids(&backend, folder, &ids, &self.args.selector).await?;
+
+        backend.add_flags(folder, ids, &flags).await?;
 
         printer.print(format!("Flag(s) {flags} successfully added!"))
     }
@@ -1,7 +1,9 @@
 #[cfg(feature = "account-sync")]
 use email::flag::sync::config::FlagSyncConfig;
+use log::debug;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use termcolor::Color;
 
 use crate::backend::BackendKind;
 
@@ -10,6 +12,7 @@ pub struct FlagConfig {
     pub add: Option<FlagAddConfig>,
     pub set: Option<FlagSetConfig>,
     pub remove: Option<FlagRemoveConfig>,
+    pub display: Option<HashMap<String, FlagDisplayConfig>>,
     #[cfg(feature = "account-sync")]
     pub sync: Option<FlagSyncConfig>,
 }
@@ -84,3 +87,33 @@ impl FlagRemoveConfig {
         kinds
     }
 }
+
+/// Represents how a custom flag (keyword) is rendered in the
+/// envelopes table.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct FlagDisplayConfig {
+    /// The short label of the keyword, displayed when no glyph is
+    /// defined. Defaults to the keyword itself.
+    pub label: Option<String>,
+
+    /// The glyph of the keyword. An empty glyph hides the keyword.
+    pub glyph: Option<String>,
+
+    /// The color of the keyword: either a color name (black, blue,
+    /// green, red, cyan, magenta, yellow, white), an ANSI 256 color
+    /// code or an RGB triple (r,g,b).
+    pub color: Option<String>,
+}
+
+impl FlagDisplayConfig {
+    pub fn color(&self) -> Option<Color> {
+        let color = self.color.as_ref()?;
+        match color.parse() {
+            Ok(color) => Some(color),
+            Err(err) => {
+                debug!("cannot parse flag color {color}, skipping it: {err}");
+                None
+            }
+        }
+    }
+}
@@ -3,7 +3,14 @@ pub mod command;
 pub mod config;
 
 use serde::Serialize;
-use std::{collections::HashSet, ops};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, ops,
+};
+
+use crate::ui::Cell;
+
+use self::config::FlagDisplayConfig;
 
 /// Represents the flag variants.
 #[derive(Clone, Debug, Eq, Hash, PartialEq, Ord, PartialOrd, Serialize)]
@@ -16,6 +23,19 @@ pub enum Flag {
     Custom(String),
 }
 
+impl fmt::Display for Flag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Seen => write!(f, "seen"),
+            Self::Answered => write!(f, "answered"),
+            Self::Flagged => write!(f, "flagged"),
+            Self::Deleted => write!(f, "deleted"),
+            Self::Draft => write!(f, "draft"),
+            Self::Custom(flag) => write!(f, "{flag}"),
+        }
+    }
+}
+
 impl From<&email::flag::Flag> for Flag {
     fn from(flag: &email::flag::Flag) -> Self {
         use email::flag::Flag::*;
@@ -41,6 +61,71 @@ impl ops::Deref for Flags {
     }
 }
 
+impl Flags {
+    /// Build the table cell of the flags.
+    ///
+    /// Standard flags are rendered as glyphs at fixed positions
+    /// (unseen, answered, flagged, deleted and draft), followed by
+    /// custom flags rendered according to the given display
+    /// configuration.
+    pub fn to_cell(&self, display: &HashMap<String, FlagDisplayConfig>) -> Cell {
+        let glyph = |flag: &Flag, glyph: &'static str| {
+            if self.contains(flag) {
+                glyph
+            } else {
+                " "
+            }
+        };
+
+        let mut flags = String::new();
+        flags.push_str(if self.contains(&Flag::Seen) {
+            " "
+        } else {
+            "✷"
+        });
+        flags.push_str(glyph(&Flag::Answered, "↵"));
+        flags.push_str(glyph(&Flag::Flagged, "⚑"));
+        flags.push_str(glyph(&Flag::Deleted, "✗"));
+        flags.push_str(glyph(&Flag::Draft, "✎"));
+
+        let mut keywords: Vec<&str> = self
+            .iter()
+            .filter_map(|flag| match flag {
+                Flag::Custom(keyword) => Some(keyword.as_str()),
+                _ => None,
+            })
+            .collect();
+        keywords.sort_unstable();
+
+        let mut cell = Cell::new(flags).white();
+
+        for keyword in keywords {
+            let config = display
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(keyword))
+                .map(|(_, config)| config);
+
+            let label = match config {
+                Some(FlagDisplayConfig {
+                    glyph: Some(glyph), ..
+                }) => glyph.as_str(),
+                Some(FlagDisplayConfig {
+                    label: Some(label), ..
+                }) => label.as_str(),
+                _ => keyword,
+            };
+
+            if !label.is_empty() {
+                cell = cell
+                    .push(" ", None)
+                    .push(label, config.and_then(FlagDisplayConfig::color));
+            }
+        }
+
+        cell
+    }
+}
+
 impl From<email::flag::Flags> for Flags {
     fn from(flags: email::flag::Flags) -> Self {
         Flags(flags.iter().map(Flag::from).collect())
@@ -0,0 +1,108 @@
+//! Module dedicated to IMAP full envelopes.
+
+use anyhow::{anyhow, Result};
+use email::imap::ImapContextSync;
+use log::{debug, info};
+use utf7_imap::encode_utf7_imap as encode_utf7;
+
+use crate::envelope::{
+    query::imap::{quote, search_imap_uids},
+    raw::{imap::fetch_raw_imap_envelopes, RawEnvelope},
+};
+
+/// Get the raw envelopes matching the given ids from the given IMAP
+/// folder. Envelopes that cannot be found are omitted.
+pub async fn get_imap_envelopes(
+    ctx: &ImapContextSync,
+    folder: &str,
+    ids: &[String],
+) -> Result<Vec<RawEnvelope>> {
+    info!("getting imap envelopes {ids:?} from folder {folder}");
+
+    let mut ctx = ctx.lock().await;
+
+    let folder = ctx.account_config.get_folder_alias(folder);
+    let folder_encoded = encode_utf7(folder.clone());
+    debug!("utf7 encoded folder: {folder_encoded}");
+
+    ctx.exec(
+        |session| session.select(&folder_encoded),
+        |err| anyhow!(err).context(format!("cannot select imap folder {folder}")),
+    )
+    .await?;
+
+    fetch_raw_imap_envelopes(&mut ctx, &ids.join(",")).await
+}
+
+/// Get the UID the next envelope added to the given IMAP folder
+/// will get, if announced by the server.
+///
+/// The folder is examined rather than selected, so that the recent
+/// flag of envelopes is kept untouched.
+pub async fn get_imap_uid_next(ctx: &ImapContextSync, folder: &str) -> Result<Option<u32>> {
+    info!("getting next imap uid of folder {folder}");
+
+    let mut ctx = ctx.lock().await;
+
+    let folder = ctx.account_config.get_folder_alias(folder);
+    let folder_encoded = encode_utf7(folder.clone());
+    debug!("utf7 encoded folder: {folder_encoded}");
+
+    let mailbox = ctx
+        .exec(
+            |session| session.examine(&folder_encoded),
+            |err| anyhow!(err).context(format!("cannot examine imap folder {folder}")),
+        )
+        .await?;
+
+    Ok(mailbox.uid_next)
+}
+
+/// Search the UIDs of the envelopes of the given IMAP folder having
+/// one of the given Message-IDs.
+///
+/// When the next UID of the folder was captured beforehand, only the
+/// envelopes added since then are searched.
+pub async fn search_imap_message_ids(
+    ctx: &ImapContextSync,
+    folder: &str,
+    uid_next: Option<u32>,
+    message_ids: &[String],
+) -> Result<Vec<String>> {
+    info!("searching imap envelopes {message_ids:?} in folder {folder}");
+
+    let mut criteria = match uid_next {
+        Some(uid_next) => format!("UID {uid_next}:*"),
+        None => String::new(),
+    };
+
+    criteria.push_str(&" OR".repeat(message_ids.len().saturating_sub(1)));
+
+    for message_id in message_ids {
+        criteria.push_str(" HEADER Message-ID ");
+        criteria.push_str(&quote(message_id)?);
+    }
+
+    let mut ctx = ctx.lock().await;
+
+    let folder = ctx.account_config.get_folder_alias(folder);
+    let folder_encoded = encode_utf7(folder.clone());
+    debug!("utf7 encoded folder: {folder_encoded}");
+
+    ctx.exec(
+        |session| session.examine(&folder_encoded),
+        |err| anyhow!(err).context(format!("cannot examine imap folder {folder}")),
+    )
+    .await?;
+
+    // a range ending with * always contains the greatest UID of the
+    // folder, even when it is lower than the start of the range
+    let uids = search_imap_uids(&mut ctx, criteria.trim_start())
+        .await?
+        .into_iter()
+        .filter(|uid| uid_next.is_none_or(|uid_next| *uid >= uid_next))
+        .map(|uid| uid.to_string())
+        .collect();
+
+    Ok(uids)
+}
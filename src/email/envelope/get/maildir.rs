@@ -0,0 +1,28 @@
+//! Module dedicated to Maildir full envelopes.
+
+use anyhow::Result;
+use email::maildir::MaildirContextSync;
+use log::info;
+
+use crate::envelope::raw::RawEnvelope;
+
+/// Get the raw envelopes matching the given ids from the given
+/// Maildir folder. Envelopes that cannot be found are omitted.
+pub async fn get_maildir_envelopes(
+    ctx: &MaildirContextSync,
+    folder: &str,
+    ids: &[String],
+) -> Result<Vec<RawEnvelope>> {
+    info!("getting maildir envelopes {ids:?} from folder {folder}");
+
+    let ctx = ctx.lock().await;
+    let mdir = ctx.get_maildir_from_folder_name(folder)?;
+
+    let envelopes = ids
+        .iter()
+        .filter_map(|id| mdir.find(id))
+        .map(|entry| RawEnvelope::from_mdir_entry(&entry))
+        .collect();
+
+    Ok(envelopes)
+}
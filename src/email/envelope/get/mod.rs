@@ -0,0 +1,114 @@
+//! Module dedicated to full envelopes.
+//!
+//! A full envelope contains all the addresses and identification
+//! headers of a message, as well as its size, without fetching the
+//! message body.
+
+#[cfg(feature = "imap")]
+pub mod imap;
+#[cfg(any(feature = "maildir", feature = "account-sync"))]
+pub mod maildir;
+#[cfg(feature = "notmuch")]
+pub mod notmuch;
+
+use anyhow::{Context, Result};
+use email::{account::config::AccountConfig, message::Message};
+use serde::Serialize;
+
+use crate::{
+    cache::IdMapper,
+    flag::Flags,
+    printer::{Print, WriteColor},
+};
+
+use super::{format_size, mailboxes, message_ids, raw::RawEnvelope, Mailbox};
+
+/// Represents the full envelope of a message.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct FullEnvelope {
+    pub id: String,
+    pub flags: Flags,
+    pub message_id: Option<String>,
+    pub in_reply_to: Vec<String>,
+    pub references: Vec<String>,
+    pub subject: String,
+    pub from: Vec<Mailbox>,
+    pub sender: Vec<Mailbox>,
+    pub reply_to: Vec<Mailbox>,
+    pub to: Vec<Mailbox>,
+    pub cc: Vec<Mailbox>,
+    pub bcc: Vec<Mailbox>,
+    pub date: String,
+    pub size: u64,
+}
+
+impl FullEnvelope {
+    /// Build a full envelope from the given raw envelope, which holds
+    /// the flags, the headers and the size of the message.
+    pub fn from_raw(
+        config: &AccountConfig,
+        id_mapper: &IdMapper,
+        raw: &RawEnvelope,
+    ) -> Result<Self> {
+        let msg = Message::from(raw.headers.as_slice());
+        let parsed = msg.parsed().context("cannot parse envelope headers")?;
+        let envelope = raw.to_envelope();
+
+        Ok(FullEnvelope {
+            id: id_mapper.get_or_create_alias(&raw.id)?,
+            flags: raw.flags.clone().into(),
+            message_id: parsed.message_id().map(|id| format!("<{id}>")),
+            in_reply_to: message_ids(parsed.in_reply_to()),
+            references: message_ids(parsed.references()),
+            subject: envelope.subject.clone(),
+            from: mailboxes(parsed.from()),
+            sender: mailboxes(parsed.sender()),
+            reply_to: mailboxes(parsed.reply_to()),
+            to: mailboxes(parsed.to()),
+            cc: mailboxes(parsed.cc()),
+            bcc: mailboxes(parsed.bcc()),
+            date: envelope.format_date(config),
+            size: raw.size,
+        })
+    }
+}
+
+impl Print for FullEnvelope {
+    fn print(&self, writer: &mut dyn WriteColor) -> Result<()> {
+        let mut flags: Vec<_> = self.flags.iter().map(ToString::to_string).collect();
+        flags.sort();
+
+        let fields = [
+            ("ID", self.id.clone()),
+            ("Flags", flags.join(", ")),
+            ("Date", self.date.clone()),
+            ("Size", format_size(self.size)),
+            ("Message-ID", self.message_id.clone().unwrap_or_default()),
+            ("In-Reply-To", self.in_reply_to.join(" ")),
+            ("References", self.references.join(" ")),
+            ("From", join_mailboxes(&self.from)),
+            ("Sender", join_mailboxes(&self.sender)),
+            ("Reply-To", join_mailboxes(&self.reply_to)),
+            ("To", join_mailboxes(&self.to)),
+            ("Cc", join_mailboxes(&self.cc)),
+            ("Bcc", join_mailboxes(&self.bcc)),
+            ("Subject", self.subject.clone()),
+        ];
+
+        for (key, val) in fields {
+            if !val.is_empty() {
+                writeln!(writer, "{key}: {val}")?;
+            }
+        }
+
+        Ok(writer.reset()?)
+    }
+}
+
+fn join_mailboxes(mailboxes: &[Mailbox]) -> String {
+    mailboxes
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
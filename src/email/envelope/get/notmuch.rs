@@ -0,0 +1,31 @@
+//! Module dedicated to Notmuch full envelopes.
+
+use anyhow::Result;
+use email::notmuch::NotmuchContextSync;
+use log::info;
+
+use crate::envelope::raw::RawEnvelope;
+
+/// Get the raw envelopes matching the given ids from the Notmuch
+/// database. Envelopes that cannot be found are omitted.
+pub async fn get_notmuch_envelopes(
+    ctx: &NotmuchContextSync,
+    folder: &str,
+    ids: &[String],
+) -> Result<Vec<RawEnvelope>> {
+    info!("getting notmuch envelopes {ids:?} from folder {folder}");
+
+    let ctx = ctx.lock().await;
+    let db = ctx.open_db()?;
+
+    let mut envelopes = Vec::with_capacity(ids.len());
+    for id in ids {
+        if let Some(msg) = db.find_message(id)? {
+            envelopes.push(RawEnvelope::from_notmuch_msg(&msg)?);
+        }
+    }
+
+    db.close()?;
+
+    Ok(envelopes)
+}
@@ -2,82 +2,255 @@ pub mod arg;
 pub mod command;
 pub mod config;
 pub mod flag;
+pub mod get;
+pub mod query;
+pub mod raw;
+pub mod sort;
+pub mod thread;
+pub mod unified;
+pub mod watch;
 
-use anyhow::Result;
-use email::account::config::AccountConfig;
-use serde::Serialize;
-use std::ops;
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use email::{account::config::AccountConfig, message::Message};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt, ops};
 
 use crate::{
     cache::IdMapper,
-    flag::{Flag, Flags},
+    flag::{config::FlagDisplayConfig, Flag, Flags},
     printer::{PrintTable, PrintTableOpts, WriteColor},
-    ui::{Cell, Row, Table},
+    ui::{print_rows, Cell, Row, Table},
 };
 
+use self::raw::RawEnvelope;
+
 #[derive(Clone, Debug, Default, Serialize)]
 pub struct Mailbox {
     pub name: Option<String>,
     pub addr: String,
 }
 
+impl fmt::Display for Mailbox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{name} <{}>", self.addr),
+            None => write!(f, "{}", self.addr),
+        }
+    }
+}
+
+/// Represents the envelope table columns.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum EnvelopeColumn {
+    Id,
+    Account,
+    Folder,
+    Flags,
+    Subject,
+    From,
+    To,
+    Cc,
+    Date,
+    Size,
+    MessageId,
+}
+
+impl EnvelopeColumn {
+    /// The columns displayed when none are configured.
+    pub const DEFAULT: [EnvelopeColumn; 5] = [
+        EnvelopeColumn::Id,
+        EnvelopeColumn::Flags,
+        EnvelopeColumn::Subject,
+        EnvelopeColumn::From,
+        EnvelopeColumn::Date,
+    ];
+
+    fn head(&self) -> Cell {
+        let cell = match self {
+            Self::Id => Cell::new("ID"),
+            Self::Account => Cell::new("ACCOUNT"),
+            Self::Folder => Cell::new("FOLDER"),
+            Self::Flags => Cell::new("FLAGS"),
+            Self::Subject => Cell::new("SUBJECT").shrinkable(),
+            Self::From => Cell::new("FROM"),
+            Self::To => Cell::new("TO"),
+            Self::Cc => Cell::new("CC"),
+            Self::Date => Cell::new("DATE"),
+            Self::Size => Cell::new("SIZE"),
+            Self::MessageId => Cell::new("MESSAGE-ID"),
+        };
+
+        cell.bold().underline().white()
+    }
+
+    /// Whether the column can be filled from the envelopes returned
+    /// by the list envelopes feature, which only exposes the first
+    /// sender and the first recipient of messages.
+    pub fn is_listable(&self) -> bool {
+        matches!(
+            self,
+            Self::Id
+                | Self::Account
+                | Self::Folder
+                | Self::Flags
+                | Self::Subject
+                | Self::From
+                | Self::Date
+        )
+    }
+}
+
 #[derive(Clone, Debug, Default, Serialize)]
 pub struct Envelope {
     pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
     pub flags: Flags,
     pub subject: String,
     pub from: Mailbox,
+    pub to: Vec<Mailbox>,
+    pub cc: Vec<Mailbox>,
     pub date: String,
+    pub size: u64,
+    pub message_id: Option<String>,
+}
+
+impl Envelope {
+    /// Build an envelope from the given envelope returned by the list
+    /// envelopes feature.
+    ///
+    /// Only the first recipient is known, and the size is left
+    /// empty. Message-IDs generated by the backend are omitted.
+    pub fn from_backend(
+        config: &AccountConfig,
+        id_mapper: &IdMapper,
+        envelope: &email::envelope::Envelope,
+    ) -> Result<Self> {
+        let mailbox = |addr: &email::envelope::Address| Mailbox {
+            name: addr.name.clone(),
+            addr: addr.addr.clone(),
+        };
+
+        Ok(Envelope {
+            id: id_mapper.get_or_create_alias(&envelope.id)?,
+            account: None,
+            folder: None,
+            flags: envelope.flags.clone().into(),
+            subject: envelope.subject.clone(),
+            from: mailbox(&envelope.from),
+            to: Some(&envelope.to)
+                .filter(|addr| !addr.addr.is_empty())
+                .map(mailbox)
+                .into_iter()
+                .collect(),
+            cc: Vec::new(),
+            date: envelope.format_date(config),
+            size: 0,
+            message_id: Some(envelope.message_id.clone())
+                .filter(|id| !id.is_empty() && !id.ends_with("@generated>")),
+        })
+    }
+
+    pub fn from_raw(
+        config: &AccountConfig,
+        id_mapper: &IdMapper,
+        raw: &RawEnvelope,
+    ) -> Result<Self> {
+        let msg = Message::from(raw.headers.as_slice());
+        let parsed = msg.parsed().context("cannot parse envelope headers")?;
+        Self::from_parsed(config, id_mapper, raw, parsed)
+    }
+
+    /// Build an envelope from the given raw envelope, whose headers
+    /// are already parsed.
+    fn from_parsed(
+        config: &AccountConfig,
+        id_mapper: &IdMapper,
+        raw: &RawEnvelope,
+        parsed: &mail_parser::Message,
+    ) -> Result<Self> {
+        Ok(Envelope {
+            id: id_mapper.get_or_create_alias(&raw.id)?,
+            account: None,
+            folder: None,
+            flags: raw.flags.clone().into(),
+            subject: parsed.subject().unwrap_or_default().to_owned(),
+            from: mailboxes(parsed.from())
+                .into_iter()
+                .next()
+                .unwrap_or_default(),
+            to: mailboxes(parsed.to()),
+            cc: mailboxes(parsed.cc()),
+            date: raw.to_envelope().format_date(config),
+            size: raw.size,
+            message_id: parsed.message_id().map(|id| format!("<{id}>")),
+        })
+    }
+
+    fn head_with(columns: &[EnvelopeColumn]) -> Row {
+        columns
+            .iter()
+            .fold(Row::new(), |row, column| row.cell(column.head()))
+    }
+
+    fn row_with(
+        &self,
+        columns: &[EnvelopeColumn],
+        flags_display: &HashMap<String, FlagDisplayConfig>,
+    ) -> Row {
+        let unseen = !self.flags.contains(&Flag::Seen);
+
+        columns.iter().fold(Row::new(), |row, column| {
+            let cell = match column {
+                EnvelopeColumn::Id => Cell::new(&self.id).red(),
+                EnvelopeColumn::Account => {
+                    Cell::new(self.account.as_deref().unwrap_or_default()).magenta()
+                }
+                EnvelopeColumn::Folder => {
+                    Cell::new(self.folder.as_deref().unwrap_or_default()).magenta()
+                }
+                EnvelopeColumn::Flags => self.flags.to_cell(flags_display),
+                EnvelopeColumn::Subject => Cell::new(&self.subject).shrinkable().green(),
+                EnvelopeColumn::From => Cell::new(mailbox_name(&self.from)).blue(),
+                EnvelopeColumn::To => Cell::new(mailboxes_names(&self.to)).blue(),
+                EnvelopeColumn::Cc => Cell::new(mailboxes_names(&self.cc)).blue(),
+                EnvelopeColumn::Date => Cell::new(&self.date).yellow(),
+                EnvelopeColumn::Size => Cell::new(format_size(self.size)).white(),
+                EnvelopeColumn::MessageId => {
+                    Cell::new(self.message_id.as_deref().unwrap_or_default()).white()
+                }
+            };
+
+            row.cell(cell.bold_if(unseen))
+        })
+    }
 }
 
 impl Table for Envelope {
     fn head() -> Row {
-        Row::new()
-            .cell(Cell::new("ID").bold().underline().white())
-            .cell(Cell::new("FLAGS").bold().underline().white())
-            .cell(Cell::new("SUBJECT").shrinkable().bold().underline().white())
-            .cell(Cell::new("FROM").bold().underline().white())
-            .cell(Cell::new("DATE").bold().underline().white())
+        Self::head_with(&EnvelopeColumn::DEFAULT)
     }
 
     fn row(&self) -> Row {
-        let id = self.id.to_string();
-        let unseen = !self.flags.contains(&Flag::Seen);
-        let flags = {
-            let mut flags = String::new();
-            flags.push_str(if !unseen { " " } else { "✷" });
-            flags.push_str(if self.flags.contains(&Flag::Answered) {
-                "↵"
-            } else {
-                " "
-            });
-            flags.push_str(if self.flags.contains(&Flag::Flagged) {
-                "⚑"
-            } else {
-                " "
-            });
-            flags
-        };
-        let subject = &self.subject;
-        let sender = if let Some(name) = &self.from.name {
-            name
-        } else {
-            &self.from.addr
-        };
-        let date = &self.date;
-
-        Row::new()
-            .cell(Cell::new(id).bold_if(unseen).red())
-            .cell(Cell::new(flags).bold_if(unseen).white())
-            .cell(Cell::new(subject).shrinkable().bold_if(unseen).green())
-            .cell(Cell::new(sender).bold_if(unseen).blue())
-            .cell(Cell::new(date).bold_if(unseen).yellow())
+        self.row_with(&EnvelopeColumn::DEFAULT, &HashMap::new())
     }
 }
 
 /// Represents the list of envelopes.
 #[derive(Clone, Debug, Default, Serialize)]
-pub struct Envelopes(Vec<Envelope>);
+#[serde(transparent)]
+pub struct Envelopes {
+    envelopes: Vec<Envelope>,
+    #[serde(skip)]
+    columns: Option<Vec<EnvelopeColumn>>,
+    #[serde(skip)]
+    flags_display: HashMap<String, FlagDisplayConfig>,
+}
 
 impl Envelopes {
     pub fn from_backend(
@@ -85,23 +258,87 @@ impl Envelopes {
         id_mapper: &IdMapper,
         envelopes: email::envelope::Envelopes,
     ) -> Result<Envelopes> {
-        let envelopes = envelopes
+        envelopes
             .iter()
-            .map(|envelope| {
-                Ok(Envelope {
-                    id: id_mapper.get_or_create_alias(&envelope.id)?,
-                    flags: envelope.flags.clone().into(),
-                    subject: envelope.subject.clone(),
-                    from: Mailbox {
-                        name: envelope.from.name.clone(),
-                        addr: envelope.from.addr.clone(),
-                    },
-                    date: envelope.format_date(config),
-                })
-            })
-            .collect::<Result<Vec<_>>>()?;
+            .map(|envelope| Envelope::from_backend(config, id_mapper, envelope))
+            .collect()
+    }
+
+    pub fn from_raw(
+        config: &AccountConfig,
+        id_mapper: &IdMapper,
+        envelopes: &[RawEnvelope],
+    ) -> Result<Envelopes> {
+        let envelopes = Self::pair_from_raw(config, id_mapper, envelopes)?
+            .into_iter()
+            .map(|(_, envelope)| envelope)
+            .collect();
+
+        Ok(Envelopes {
+            envelopes,
+            ..Default::default()
+        })
+    }
+
+    /// Build envelopes from the given raw envelopes, alongside with
+    /// the raw envelope each of them was built from.
+    ///
+    /// Raw envelopes with invalid headers are skipped.
+    pub fn pair_from_raw<'a>(
+        config: &AccountConfig,
+        id_mapper: &IdMapper,
+        envelopes: &'a [RawEnvelope],
+    ) -> Result<Vec<(&'a RawEnvelope, Envelope)>> {
+        let mut parsed_envelopes = Vec::with_capacity(envelopes.len());
+
+        // a message with invalid headers should not prevent the
+        // others from being listed
+        for raw in envelopes {
+            let msg = Message::from(raw.headers.as_slice());
+            match msg.parsed() {
+                Ok(parsed) => {
+                    let envelope = Envelope::from_parsed(config, id_mapper, raw, parsed)?;
+                    parsed_envelopes.push((raw, envelope));
+                }
+                Err(err) => {
+                    warn!(
+                        "cannot parse envelope {} headers, skipping it: {err}",
+                        raw.id
+                    );
+                    debug!("{err:?}");
+                }
+            }
+        }
+
+        Ok(parsed_envelopes)
+    }
+
+    /// Define the columns of the table output.
+    pub fn set_columns(&mut self, columns: Option<Vec<EnvelopeColumn>>) {
+        self.columns = columns;
+    }
+
+    /// Define how custom flags are rendered in the table output.
+    pub fn set_flags_display(&mut self, flags_display: HashMap<String, FlagDisplayConfig>) {
+        self.flags_display = flags_display;
+    }
+}
+
+impl FromIterator<Envelope> for Envelopes {
+    fn from_iter<T: IntoIterator<Item = Envelope>>(iter: T) -> Self {
+        Envelopes {
+            envelopes: iter.into_iter().collect(),
+            ..Default::default()
+        }
+    }
+}
 
-        Ok(Envelopes(envelopes))
+impl IntoIterator for Envelopes {
+    type Item = Envelope;
+    type IntoIter = std::vec::IntoIter<Envelope>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.envelopes.into_iter()
     }
 }
 
@@ -109,15 +346,149 @@ impl ops::Deref for Envelopes {
     type Target = Vec<Envelope>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.envelopes
     }
 }
 
 impl PrintTable for Envelopes {
     fn print_table(&self, writer: &mut dyn WriteColor, opts: PrintTableOpts) -> Result<()> {
+        let columns = self.columns.as_deref().unwrap_or(&EnvelopeColumn::DEFAULT);
+        let head = Envelope::head_with(columns);
+        let rows = self
+            .iter()
+            .map(|envelope| envelope.row_with(columns, &self.flags_display));
+
         writeln!(writer)?;
-        Table::print(writer, self, opts)?;
+        print_rows(writer, head, rows, opts)?;
         writeln!(writer)?;
         Ok(())
     }
 }
+
+/// Extract the given page from the given list of items.
+///
+/// A page size of 0 disables the pagination and returns all items.
+pub(crate) fn paginate<T>(
+    mut items: Vec<T>,
+    folder: &str,
+    page_size: usize,
+    page: usize,
+) -> Result<Vec<T>> {
+    if page_size == 0 {
+        return Ok(items);
+    }
+
+    let page_begin = page * page_size;
+    if page_begin > items.len() {
+        bail!(
+            "cannot list envelopes from {folder}: page {} out of bounds",
+            page + 1
+        );
+    }
+
+    let page_end = items.len().min(page_begin + page_size);
+    items.truncate(page_end);
+    Ok(items.split_off(page_begin))
+}
+
+/// Format the given size in bytes into a human-readable size.
+pub(crate) fn format_size(size: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    if size < 1024 {
+        return format!("{size} B");
+    }
+
+    let mut size = size as f64 / 1024.0;
+    let mut unit = UNITS[0];
+
+    for next_unit in &UNITS[1..] {
+        if size < 1024.0 {
+            break;
+        }
+        size /= 1024.0;
+        unit = next_unit;
+    }
+
+    format!("{size:.1} {unit}")
+}
+
+/// Extract message identifiers from the given header value.
+pub(crate) fn message_ids(header: &mail_parser::HeaderValue) -> Vec<String> {
+    header
+        .as_text_list()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|id| format!("<{id}>"))
+        .collect()
+}
+
+/// Extract mailboxes from the given address header.
+pub(crate) fn mailboxes(addr: Option<&mail_parser::Address>) -> Vec<Mailbox> {
+    let mailbox = |addr: &mail_parser::Addr| {
+        Some(Mailbox {
+            name: addr.name.as_ref().map(ToString::to_string),
+            addr: addr.address.as_ref()?.to_string(),
+        })
+    };
+
+    match addr {
+        Some(mail_parser::Address::List(addrs)) => addrs.iter().filter_map(mailbox).collect(),
+        Some(mail_parser::Address::Group(groups)) => groups
+            .iter()
+            .flat_map(|group| group.addresses.iter())
+            .filter_map(mailbox)
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Get the display name of the given mailbox, which is its name if
+/// any, otherwise its address.
+fn mailbox_name(mailbox: &Mailbox) -> &str {
+    mailbox.name.as_deref().unwrap_or(&mailbox.addr)
+}
+
+fn mailboxes_names(mailboxes: &[Mailbox]) -> String {
+    mailboxes
+        .iter()
+        .map(mailbox_name)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use email::{account::config::AccountConfig, envelope::Flags};
+
+    use crate::cache::IdMapper;
+
+    use super::{raw::RawEnvelope, Envelopes};
+
+    fn raw(id: &str, headers: &str) -> RawEnvelope {
+        RawEnvelope {
+            id: id.into(),
+            flags: Flags::default(),
+            headers: headers.as_bytes().to_vec(),
+            size: headers.len() as u64,
+        }
+    }
+
+    #[test]
+    fn pair_from_raw() {
+        let raws = [
+            raw("1", "Subject: one\r\n\r\n"),
+            raw("2", ""),
+            raw("3", "Subject: three\r\n\r\n"),
+        ];
+
+        let pairs =
+            Envelopes::pair_from_raw(&AccountConfig::default(), &IdMapper::Dummy, &raws).unwrap();
+
+        assert_eq!(pairs.len(), 2);
+        for (raw, envelope) in pairs {
+            assert_eq!(raw.id, envelope.id);
+            assert_eq!(raw.to_envelope().subject, envelope.subject);
+        }
+    }
+}
@@ -0,0 +1,276 @@
+//! Module dedicated to IMAP envelope queries.
+//!
+//! This module translates queries into IMAP SEARCH criteria, as
+//! defined in the [RFC 3501](https://www.rfc-editor.org/rfc/rfc3501#section-6.4.4).
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{Duration, NaiveDate};
+use email::{
+    envelope::Envelope,
+    flag::Flag,
+    imap::{ImapContext, ImapContextSync},
+};
+use imap::extensions::sort::SortCharset;
+use log::{debug, info};
+use utf7_imap::encode_utf7_imap as encode_utf7;
+
+use crate::envelope::{
+    paginate,
+    raw::{imap::fetch_ordered_raw_imap_envelopes, RawEnvelope},
+    sort::Sort,
+};
+
+use super::Query;
+
+impl Query {
+    /// Translate the query into IMAP SEARCH criteria.
+    pub fn to_imap_search(&self) -> Result<String> {
+        let criteria = match self {
+            Self::And(left, right) => {
+                format!("({} {})", left.to_imap_search()?, right.to_imap_search()?)
+            }
+            Self::Or(left, right) => {
+                format!("OR {} {}", left.to_imap_search()?, right.to_imap_search()?)
+            }
+            Self::Not(query) => format!("NOT {}", query.to_imap_search()?),
+            Self::From(pattern) => format!("FROM {}", quote(pattern)?),
+            Self::To(pattern) => format!("TO {}", quote(pattern)?),
+            Self::Subject(pattern) => format!("SUBJECT {}", quote(pattern)?),
+            Self::Body(pattern) => format!("BODY {}", quote(pattern)?),
+            Self::Flag(flag) => match flag {
+                Flag::Seen => String::from("SEEN"),
+                Flag::Answered => String::from("ANSWERED"),
+                Flag::Flagged => String::from("FLAGGED"),
+                Flag::Deleted => String::from("DELETED"),
+                Flag::Draft => String::from("DRAFT"),
+                Flag::Custom(flag) => format!("KEYWORD {}", atom(flag)?),
+            },
+            Self::Before(date) => format!("SENTBEFORE {}", format_date(date)),
+            // SENTSINCE is inclusive, whereas after is exclusive
+            Self::After(date) => format!("SENTSINCE {}", format_date(&(*date + Duration::days(1)))),
+        };
+
+        Ok(criteria)
+    }
+}
+
+/// The IMAP fetch query used to sort envelopes client-side.
+const SORT_ENVELOPES_QUERY: &str = "(UID FLAGS ENVELOPE RFC822.SIZE)";
+
+/// Search envelopes matching the given query from the given IMAP
+/// folder.
+///
+/// Without query, all envelopes of the folder are matched. Without
+/// sort, matching envelopes are ordered by descending UID, which
+/// corresponds to the arrival order. The sort is delegated to the
+/// server when it supports the SORT extension, otherwise envelopes
+/// are sorted client-side.
+pub async fn search_imap_envelopes(
+    ctx: &ImapContextSync,
+    folder: &str,
+    query: Option<&Query>,
+    sort: Option<&Sort>,
+    page_size: usize,
+    page: usize,
+) -> Result<Vec<RawEnvelope>> {
+    info!("searching imap envelopes from folder {folder}");
+
+    let mut ctx = ctx.lock().await;
+
+    let folder = ctx.account_config.get_folder_alias(folder);
+    let folder_encoded = encode_utf7(folder.clone());
+    debug!("utf7 encoded folder: {folder_encoded}");
+
+    ctx.exec(
+        |session| session.select(&folder_encoded),
+        |err| anyhow!(err).context(format!("cannot select imap folder {folder}")),
+    )
+    .await?;
+
+    let criteria = match query {
+        Some(query) => query.to_imap_search()?,
+        None => String::from("ALL"),
+    };
+    debug!("imap search criteria: {criteria}");
+
+    let uids = match sort {
+        None => {
+            let mut uids = search_imap_uids(&mut ctx, &criteria).await?;
+            uids.sort_by(|a, b| b.cmp(a));
+            uids
+        }
+        Some(sort) => {
+            let capabilities = ctx
+                .exec(
+                    |session| session.capabilities(),
+                    |err| anyhow!(err).context("cannot get imap capabilities"),
+                )
+                .await?;
+
+            if capabilities.has_str("SORT") {
+                let sort_criteria = sort.to_imap_sort_criteria();
+                debug!("imap sort criteria: {sort_criteria:?}");
+
+                ctx.exec(
+                    |session| session.uid_sort(&sort_criteria, SortCharset::Utf8, &criteria),
+                    |err| anyhow!(err).context(format!("cannot sort imap envelopes {criteria}")),
+                )
+                .await?
+            } else {
+                debug!("imap server does not support sort, sorting envelopes client-side");
+                sort_imap_uids(&mut ctx, &criteria, sort).await?
+            }
+        }
+    };
+    debug!("found {} imap envelopes matching the query", uids.len());
+
+    let uids = paginate(uids, folder.as_str(), page_size, page)?;
+    fetch_ordered_raw_imap_envelopes(&mut ctx, &uids).await
+}
+
+/// Search UIDs matching the given IMAP search criteria.
+///
+/// The UTF-8 charset is announced when the criteria contain non-ASCII
+/// characters, since the default charset of SEARCH is US-ASCII.
+pub(crate) async fn search_imap_uids(ctx: &mut ImapContext, criteria: &str) -> Result<Vec<u32>> {
+    let query = if criteria.is_ascii() {
+        criteria.to_owned()
+    } else {
+        format!("CHARSET UTF-8 {criteria}")
+    };
+
+    let uids = ctx
+        .exec(
+            |session| session.uid_search(&query),
+            |err| anyhow!(err).context(format!("cannot search imap envelopes {criteria}")),
+        )
+        .await?
+        .into_iter()
+        .collect();
+
+    Ok(uids)
+}
+
+/// Sort client-side UIDs matching the given IMAP search criteria.
+///
+/// This is the fallback used when the server does not support the
+/// SORT extension: envelopes of all matching UIDs need to be
+/// fetched, so that pagination follows the sort order.
+async fn sort_imap_uids(ctx: &mut ImapContext, criteria: &str, sort: &Sort) -> Result<Vec<u32>> {
+    let uids = search_imap_uids(ctx, criteria).await?;
+
+    if uids.is_empty() {
+        return Ok(uids);
+    }
+
+    let uids = uids
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let fetches = ctx
+        .exec(
+            |session| session.uid_fetch(&uids, SORT_ENVELOPES_QUERY),
+            |err| anyhow!(err).context(format!("cannot fetch imap envelopes {uids}")),
+        )
+        .await?;
+
+    let mut envelopes = fetches
+        .iter()
+        .filter_map(|fetch| match Envelope::from_imap_fetch(fetch) {
+            Ok(envelope) => Some((envelope, fetch.size.unwrap_or_default().into())),
+            Err(err) => {
+                debug!("cannot build imap envelope, skipping it: {err}");
+                debug!("{err:?}");
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+
+    sort.sort(&mut envelopes);
+
+    let uids = envelopes
+        .into_iter()
+        .filter_map(|(envelope, _)| envelope.id.parse().ok())
+        .collect();
+
+    Ok(uids)
+}
+
+/// Quote the given string so it can be used as an IMAP string
+/// argument.
+///
+/// Quoted strings cannot contain line breaks, which would otherwise
+/// end the command.
+pub(crate) fn quote(s: &str) -> Result<String> {
+    if s.contains(['\r', '\n']) {
+        bail!("cannot quote imap string {s:?}: invalid line break");
+    }
+
+    Ok(format!(
+        "\"{}\"",
+        s.replace('\\', "\\\\").replace('"', "\\\"")
+    ))
+}
+
+/// Check that the given string is a valid IMAP atom, so it can be
+/// used as a keyword.
+///
+/// Atoms cannot be quoted, hence they cannot contain spaces, control
+/// characters nor any of the atom specials `(){%*"\]`.
+fn atom(s: &str) -> Result<&str> {
+    let is_atom_char = |c: char| {
+        c.is_ascii_graphic() && !matches!(c, '(' | ')' | '{' | '%' | '*' | '"' | '\\' | ']')
+    };
+
+    if s.is_empty() || !s.chars().all(is_atom_char) {
+        bail!("cannot search imap envelopes: invalid keyword {s:?}");
+    }
+
+    Ok(s)
+}
+
+/// Format the given date following the IMAP date format
+/// (`1-Jan-2024`).
+fn format_date(date: &NaiveDate) -> String {
+    date.format("%-d-%b-%Y").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Query;
+
+    #[test]
+    fn to_imap_search() {
+        let query: Query = "from:alice (subject:\"say \\\"hi\\\"\" or not flag:seen)"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            query.to_imap_search().unwrap(),
+            "(FROM \"alice\" OR SUBJECT \"say \\\"hi\\\"\" NOT SEEN)"
+        );
+
+        let query: Query = "after:2024-01-31 before:2024-03-01 flag:$Label1"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            query.to_imap_search().unwrap(),
+            "((SENTSINCE 1-Feb-2024 SENTBEFORE 1-Mar-2024) KEYWORD $Label1)"
+        );
+
+        let query: Query = "subject:\"line\nbreak\"".parse().unwrap();
+        assert!(query.to_imap_search().is_err());
+
+        for keyword in [
+            "\"my label\"",
+            "\"la(bel\"",
+            "label]",
+            "\"la\\\"bel\"",
+            "\"la\rbel\"",
+        ] {
+            let query: Query = format!("flag:{keyword}").parse().unwrap();
+            assert!(query.to_imap_search().is_err(), "{keyword}");
+        }
+    }
+}
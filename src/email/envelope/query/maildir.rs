@@ -0,0 +1,92 @@
+//! Module dedicated to Maildir envelope queries.
+//!
+//! Maildir has no search capability, so queries are evaluated
+//! locally against every envelope of the folder.
+
+use anyhow::Result;
+use email::{maildir::MaildirContextSync, message::Message};
+use log::{debug, info};
+use std::{cmp::Reverse, fs};
+
+use crate::envelope::{paginate, raw::RawEnvelope, sort::Sort};
+
+use super::Query;
+
+/// Search envelopes matching the given query from the given Maildir
+/// folder.
+///
+/// Without query, all envelopes of the folder are matched. Without
+/// sort, matching envelopes are ordered by descending date.
+pub async fn search_maildir_envelopes(
+    ctx: &MaildirContextSync,
+    folder: &str,
+    query: Option<&Query>,
+    sort: Option<&Sort>,
+    page_size: usize,
+    page: usize,
+) -> Result<Vec<RawEnvelope>> {
+    info!("searching maildir envelopes from folder {folder}");
+
+    let ctx = ctx.lock().await;
+    let mdir = ctx.get_maildir_from_folder_name(folder)?;
+    let needs_body = query.map(Query::needs_body).unwrap_or_default();
+
+    let mut envelopes: Vec<_> = mdir
+        .list_cur()
+        .filter_map(|entry| match entry {
+            Ok(entry) => Some(entry),
+            Err(err) => {
+                debug!("cannot parse maildir entry, skipping it: {err}");
+                debug!("{err:?}");
+                None
+            }
+        })
+        .filter_map(|entry| {
+            let body = if needs_body {
+                read_text_body(entry.path())
+            } else {
+                None
+            };
+
+            let raw = RawEnvelope::from_mdir_entry(&entry);
+            let envelope = raw.to_envelope();
+
+            match query {
+                Some(query) if !query.matches(&envelope, body.as_deref()) => None,
+                _ => Some((envelope, raw)),
+            }
+        })
+        .collect();
+    debug!(
+        "found {} maildir envelopes matching the query",
+        envelopes.len()
+    );
+
+    match sort {
+        Some(sort) => {
+            envelopes.sort_by(|(a, a_raw), (b, b_raw)| sort.compare(a, a_raw.size, b, b_raw.size))
+        }
+        None => envelopes.sort_by_key(|(envelope, _)| Reverse(envelope.date)),
+    }
+
+    let envelopes = paginate(envelopes, folder, page_size, page)?;
+
+    Ok(envelopes.into_iter().map(|(_, raw)| raw).collect())
+}
+
+/// Read the first text body of the message located at the given
+/// path.
+pub(crate) fn read_text_body(path: &std::path::Path) -> Option<String> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            debug!("cannot read maildir entry at {path:?}, skipping its body: {err}");
+            return None;
+        }
+    };
+
+    let msg = Message::from(bytes);
+    let body = msg.parsed().ok()?.body_text(0)?.to_string();
+
+    Some(body)
+}
@@ -0,0 +1,419 @@
+//! Module dedicated to envelope queries.
+//!
+//! A query is a small filter expression used to narrow down the list
+//! of envelopes. It is composed of conditions (`from:alice`,
+//! `flag:seen`, `after:2024-01-01`…) combined with the boolean
+//! operators `and`, `or` and `not`. Parenthesis can be used to group
+//! conditions together. Two conditions separated by a space are
+//! implicitly combined with `and`.
+//!
+//! The query is then either translated into a backend-specific query
+//! (IMAP SEARCH, Notmuch query) or evaluated locally (Maildir).
+
+#[cfg(feature = "imap")]
+pub mod imap;
+#[cfg(any(feature = "maildir", feature = "account-sync"))]
+pub mod maildir;
+#[cfg(feature = "notmuch")]
+pub mod notmuch;
+
+use anyhow::{anyhow, bail, Error, Result};
+use chrono::{Duration, Local, NaiveDate};
+use email::{envelope::Envelope, flag::Flag};
+use std::{fmt, str::FromStr};
+
+/// The envelope query.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Query {
+    /// Both queries must match.
+    And(Box<Query>, Box<Query>),
+    /// At least one of the queries must match.
+    Or(Box<Query>, Box<Query>),
+    /// The query must not match.
+    Not(Box<Query>),
+    /// The sender name or address contains the given pattern.
+    From(String),
+    /// The recipient name or address contains the given pattern.
+    To(String),
+    /// The subject contains the given pattern.
+    Subject(String),
+    /// The text body contains the given pattern.
+    Body(String),
+    /// The envelope has the given flag.
+    Flag(Flag),
+    /// The envelope date is strictly before the given date.
+    Before(NaiveDate),
+    /// The envelope date is strictly after the given date.
+    After(NaiveDate),
+}
+
+impl Query {
+    /// Return `true` if the query needs the message body to be
+    /// evaluated.
+    pub fn needs_body(&self) -> bool {
+        match self {
+            Self::And(left, right) | Self::Or(left, right) => {
+                left.needs_body() || right.needs_body()
+            }
+            Self::Not(query) => query.needs_body(),
+            Self::Body(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Evaluate the query against the given envelope.
+    ///
+    /// The body is only used by body conditions. When missing, body
+    /// conditions never match.
+    pub fn matches(&self, envelope: &Envelope, body: Option<&str>) -> bool {
+        match self {
+            Self::And(left, right) => left.matches(envelope, body) && right.matches(envelope, body),
+            Self::Or(left, right) => left.matches(envelope, body) || right.matches(envelope, body),
+            Self::Not(query) => !query.matches(envelope, body),
+            Self::From(pattern) => {
+                let name = envelope.from.name.as_deref().unwrap_or_default();
+                contains(name, pattern) || contains(&envelope.from.addr, pattern)
+            }
+            Self::To(pattern) => {
+                let name = envelope.to.name.as_deref().unwrap_or_default();
+                contains(name, pattern) || contains(&envelope.to.addr, pattern)
+            }
+            Self::Subject(pattern) => contains(&envelope.subject, pattern),
+            Self::Body(pattern) => body.map(|body| contains(body, pattern)).unwrap_or(false),
+            Self::Flag(flag) => envelope.flags.contains(flag),
+            Self::Before(date) => envelope.date.date_naive() < *date,
+            Self::After(date) => envelope.date.date_naive() > *date,
+        }
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::And(left, right) => write!(f, "({left} and {right})"),
+            Self::Or(left, right) => write!(f, "({left} or {right})"),
+            Self::Not(query) => write!(f, "not {query}"),
+            Self::From(pattern) => write!(f, "from:{pattern:?}"),
+            Self::To(pattern) => write!(f, "to:{pattern:?}"),
+            Self::Subject(pattern) => write!(f, "subject:{pattern:?}"),
+            Self::Body(pattern) => write!(f, "body:{pattern:?}"),
+            Self::Flag(flag) => write!(f, "flag:{flag}"),
+            Self::Before(date) => write!(f, "before:{date}"),
+            Self::After(date) => write!(f, "after:{date}"),
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = Error;
+
+    fn from_str(query: &str) -> Result<Self> {
+        let tokens = tokenize(query)?;
+        let mut parser = Parser { tokens, cursor: 0 };
+        let parsed_query = parser.parse_or()?;
+
+        match parser.peek() {
+            None => Ok(parsed_query),
+            Some(token) => bail!("cannot parse query {query:?}: unexpected token {token}"),
+        }
+    }
+}
+
+/// Case-insensitive version of [`str::contains`].
+fn contains(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(&needle.to_lowercase())
+}
+
+/// Parse a query date.
+///
+/// Dates can be either absolute (`YYYY-MM-DD`) or relative to the
+/// current day (`today`, `yesterday`).
+fn parse_date(date: &str) -> Result<NaiveDate> {
+    let today = Local::now().date_naive();
+
+    match date.to_lowercase().as_str() {
+        "today" => Ok(today),
+        "yesterday" => Ok(today - Duration::days(1)),
+        date => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|err| anyhow!(err).context(format!("cannot parse query date {date:?}"))),
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Token {
+    OpenParen,
+    CloseParen,
+    Word(String),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::OpenParen => write!(f, "("),
+            Self::CloseParen => write!(f, ")"),
+            Self::Word(word) => write!(f, "{word:?}"),
+        }
+    }
+}
+
+/// Split the given query into tokens.
+///
+/// Words are separated by white spaces. Double quotes can be used to
+/// include white spaces or parenthesis inside a word.
+fn tokenize(query: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => continue,
+            '(' => tokens.push(Token::OpenParen),
+            ')' => tokens.push(Token::CloseParen),
+            c => {
+                let mut word = String::new();
+                let mut quoted = c == '"';
+
+                if !quoted {
+                    word.push(c);
+                }
+
+                while let Some(&c) = chars.peek() {
+                    if !quoted && (c.is_whitespace() || c == '(' || c == ')') {
+                        break;
+                    }
+
+                    chars.next();
+
+                    match c {
+                        '"' => quoted = !quoted,
+                        '\\' if quoted => word.extend(chars.next()),
+                        c => word.push(c),
+                    }
+                }
+
+                if quoted {
+                    bail!("cannot parse query {query:?}: missing closing quote");
+                }
+
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent parser of the query grammar:
+///
+/// ```text
+/// or        = and ("or" and)*
+/// and       = not ("and"? not)*
+/// not       = "not" not | primary
+/// primary   = "(" or ")" | condition
+/// condition = key ":" value
+/// ```
+struct Parser {
+    tokens: Vec<Token>,
+    cursor: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.cursor)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.cursor).cloned();
+        self.cursor += 1;
+        token
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn parse_or(&mut self) -> Result<Query> {
+        let mut query = self.parse_and()?;
+
+        while self.peek_keyword("or") {
+            self.next();
+            let right = self.parse_and()?;
+            query = Query::Or(Box::new(query), Box::new(right));
+        }
+
+        Ok(query)
+    }
+
+    fn parse_and(&mut self) -> Result<Query> {
+        let mut query = self.parse_not()?;
+
+        loop {
+            if self.peek_keyword("and") {
+                self.next();
+            } else if self.peek().is_none()
+                || self.peek_keyword("or")
+                || self.peek() == Some(&Token::CloseParen)
+            {
+                break;
+            }
+
+            let right = self.parse_not()?;
+            query = Query::And(Box::new(query), Box::new(right));
+        }
+
+        Ok(query)
+    }
+
+    fn parse_not(&mut self) -> Result<Query> {
+        if self.peek_keyword("not") {
+            self.next();
+            let query = self.parse_not()?;
+            Ok(Query::Not(Box::new(query)))
+        } else {
+            self.parse_primary()
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Query> {
+        match self.next() {
+            None => bail!("cannot parse query: unexpected end of query"),
+            Some(Token::CloseParen) => bail!("cannot parse query: unexpected token )"),
+            Some(Token::OpenParen) => {
+                let query = self.parse_or()?;
+                match self.next() {
+                    Some(Token::CloseParen) => Ok(query),
+                    _ => bail!("cannot parse query: missing closing parenthesis"),
+                }
+            }
+            Some(Token::Word(word)) => parse_condition(&word),
+        }
+    }
+}
+
+fn parse_condition(condition: &str) -> Result<Query> {
+    let (key, val) = condition
+        .split_once(':')
+        .ok_or_else(|| anyhow!("cannot parse query condition {condition:?}: missing key"))?;
+
+    if val.is_empty() {
+        bail!("cannot parse query condition {condition:?}: missing value");
+    }
+
+    match key.to_lowercase().as_str() {
+        "from" => Ok(Query::From(val.to_owned())),
+        "to" => Ok(Query::To(val.to_owned())),
+        "subject" => Ok(Query::Subject(val.to_owned())),
+        "body" => Ok(Query::Body(val.to_owned())),
+        "flag" => Ok(Query::Flag(Flag::from(val))),
+        "before" => Ok(Query::Before(parse_date(val)?)),
+        "after" => Ok(Query::After(parse_date(val)?)),
+        key => bail!("cannot parse query condition {condition:?}: unknown key {key}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use email::{
+        envelope::{Address, Envelope},
+        flag::{Flag, Flags},
+    };
+
+    use super::Query;
+
+    fn and(left: Query, right: Query) -> Query {
+        Query::And(Box::new(left), Box::new(right))
+    }
+
+    fn or(left: Query, right: Query) -> Query {
+        Query::Or(Box::new(left), Box::new(right))
+    }
+
+    fn not(query: Query) -> Query {
+        Query::Not(Box::new(query))
+    }
+
+    #[test]
+    fn parse_condition() {
+        assert_eq!(
+            "from:alice".parse::<Query>().unwrap(),
+            Query::From("alice".into())
+        );
+        assert_eq!(
+            "subject:\"hello world\"".parse::<Query>().unwrap(),
+            Query::Subject("hello world".into())
+        );
+        assert_eq!(
+            "flag:seen".parse::<Query>().unwrap(),
+            Query::Flag(Flag::Seen)
+        );
+        assert_eq!(
+            "after:2024-01-31".parse::<Query>().unwrap(),
+            Query::After(NaiveDate::from_ymd_opt(2024, 1, 31).unwrap())
+        );
+    }
+
+    #[test]
+    fn parse_operators_precedence() {
+        assert_eq!(
+            "from:alice to:bob or not flag:seen"
+                .parse::<Query>()
+                .unwrap(),
+            or(
+                and(Query::From("alice".into()), Query::To("bob".into())),
+                not(Query::Flag(Flag::Seen)),
+            )
+        );
+
+        assert_eq!(
+            "from:alice and (to:bob or to:carol)"
+                .parse::<Query>()
+                .unwrap(),
+            and(
+                Query::From("alice".into()),
+                or(Query::To("bob".into()), Query::To("carol".into())),
+            )
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!("alice".parse::<Query>().is_err());
+        assert!("unknown:alice".parse::<Query>().is_err());
+        assert!("from:".parse::<Query>().is_err());
+        assert!("(from:alice".parse::<Query>().is_err());
+        assert!("from:alice)".parse::<Query>().is_err());
+        assert!("subject:\"hello".parse::<Query>().is_err());
+        assert!("before:yesteryear".parse::<Query>().is_err());
+        assert!("from:alice or".parse::<Query>().is_err());
+    }
+
+    #[test]
+    fn matches() {
+        let envelope = Envelope {
+            from: Address::new(Some("Alice"), "alice@localhost"),
+            to: Address::new_nameless("bob@localhost"),
+            subject: "Hello, world!".into(),
+            flags: Flags::from_iter([Flag::Seen]),
+            date: "2024-01-15T10:00:00+01:00".parse().unwrap(),
+            ..Default::default()
+        };
+
+        let query: Query = "from:alice subject:HELLO".parse().unwrap();
+        assert!(query.matches(&envelope, None));
+
+        let query: Query = "not flag:seen or to:carol".parse().unwrap();
+        assert!(!query.matches(&envelope, None));
+
+        let query: Query = "after:2024-01-14 before:2024-01-16".parse().unwrap();
+        assert!(query.matches(&envelope, None));
+
+        let query: Query = "after:2024-01-15".parse().unwrap();
+        assert!(!query.matches(&envelope, None));
+
+        let query: Query = "body:invoice".parse().unwrap();
+        assert!(query.needs_body());
+        assert!(!query.matches(&envelope, None));
+        assert!(query.matches(&envelope, Some("Please find the Invoice attached.")));
+    }
+}
@@ -0,0 +1,143 @@
+//! Module dedicated to Notmuch envelope queries.
+//!
+//! This module translates queries into Notmuch search terms, as
+//! defined in the [notmuch-search-terms(7)](https://notmuchmail.org/doc/latest/man7/notmuch-search-terms.html)
+//! manual.
+
+use anyhow::{anyhow, Result};
+use chrono::{Duration, NaiveDate};
+use email::{
+    account::config::AccountConfig, envelope::Envelope, flag::Flag, folder::FolderKind,
+    notmuch::NotmuchContextSync,
+};
+use log::{debug, info};
+use std::fs;
+
+use crate::envelope::{paginate, raw::RawEnvelope, sort::Sort};
+
+use super::Query;
+
+impl Query {
+    /// Translate the query into Notmuch search terms.
+    pub fn to_notmuch_query(&self) -> String {
+        match self {
+            Self::And(left, right) => {
+                format!(
+                    "({} and {})",
+                    left.to_notmuch_query(),
+                    right.to_notmuch_query()
+                )
+            }
+            Self::Or(left, right) => {
+                format!(
+                    "({} or {})",
+                    left.to_notmuch_query(),
+                    right.to_notmuch_query()
+                )
+            }
+            Self::Not(query) => format!("(not {})", query.to_notmuch_query()),
+            Self::From(pattern) => format!("from:{}", quote(pattern)),
+            Self::To(pattern) => format!("to:{}", quote(pattern)),
+            Self::Subject(pattern) => format!("subject:{}", quote(pattern)),
+            Self::Body(pattern) => format!("body:{}", quote(pattern)),
+            Self::Flag(flag) => match flag {
+                Flag::Seen => String::from("(not tag:unread)"),
+                Flag::Answered => String::from("tag:replied"),
+                Flag::Flagged => String::from("tag:flagged"),
+                Flag::Deleted => String::from("tag:deleted"),
+                Flag::Draft => String::from("tag:draft"),
+                Flag::Custom(tag) => format!("tag:{}", quote(tag)),
+            },
+            // Notmuch date ranges are inclusive
+            Self::Before(date) => format!("date:..{}", format_date(&(*date - Duration::days(1)))),
+            Self::After(date) => format!("date:{}..", format_date(&(*date + Duration::days(1)))),
+        }
+    }
+}
+
+/// Search envelopes matching the given query from the given Notmuch
+/// folder.
+///
+/// Without query, all envelopes of the folder are matched. Without
+/// sort, matching envelopes are ordered by descending date.
+pub async fn search_notmuch_envelopes(
+    ctx: &NotmuchContextSync,
+    folder: &str,
+    query: Option<&Query>,
+    sort: Option<&Sort>,
+    page_size: usize,
+    page: usize,
+) -> Result<Vec<RawEnvelope>> {
+    info!("searching notmuch envelopes from folder {folder}");
+
+    let ctx = ctx.lock().await;
+    let config = &ctx.account_config;
+    let db = ctx.open_db()?;
+
+    let folder_query = to_notmuch_folder_query(config, folder);
+
+    let query = match query {
+        Some(query) => format!("{folder_query} and {}", query.to_notmuch_query()),
+        None => folder_query,
+    };
+    debug!("notmuch query: {query}");
+
+    let query_builder = db.create_query(&query)?;
+    let mut envelopes: Vec<_> = query_builder
+        .search_messages()?
+        .map(|msg| {
+            let size = fs::metadata(msg.filename())
+                .map(|metadata| metadata.len())
+                .unwrap_or_default();
+
+            (Envelope::from_notmuch_msg(msg), size)
+        })
+        .collect();
+    debug!(
+        "found {} notmuch envelopes matching the query",
+        envelopes.len()
+    );
+
+    match sort {
+        Some(sort) => sort.sort(&mut envelopes),
+        None => envelopes.sort_by(|(a, _), (b, _)| b.date.cmp(&a.date)),
+    }
+
+    let envelopes = paginate(envelopes, folder, page_size, page)?
+        .into_iter()
+        .map(|(envelope, _)| {
+            let msg = db
+                .find_message(&envelope.id)?
+                .ok_or_else(|| anyhow!("cannot find notmuch envelope {}", envelope.id))?;
+            RawEnvelope::from_notmuch_msg(&msg)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    db.close()?;
+
+    Ok(envelopes)
+}
+
+/// Build the Notmuch search term matching messages of the given
+/// folder.
+///
+/// The inbox matches the root of the Maildir, other folders are
+/// resolved from their alias.
+pub fn to_notmuch_folder_query(config: &AccountConfig, folder: &str) -> String {
+    if FolderKind::matches_inbox(folder) {
+        String::from("folder:\"\"")
+    } else {
+        format!("folder:{}", quote(&config.get_folder_alias(folder)))
+    }
+}
+
+/// Quote the given string so it can be used as a Notmuch search term
+/// value.
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "\"\""))
+}
+
+/// Format the given date following the Notmuch absolute date format.
+fn format_date(date: &NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
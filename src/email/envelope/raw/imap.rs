@@ -0,0 +1,119 @@
+//! Module dedicated to IMAP raw envelopes.
+
+use anyhow::{anyhow, Result};
+use email::{envelope::Flags, imap::ImapContext};
+use imap::types::Fetch;
+use log::debug;
+
+use super::RawEnvelope;
+
+/// The IMAP fetch query used to get raw envelopes.
+pub const RAW_ENVELOPES_QUERY: &str = "(UID FLAGS RFC822.SIZE BODY.PEEK[HEADER])";
+
+impl RawEnvelope {
+    pub fn from_imap_fetch(fetch: &Fetch) -> Option<Self> {
+        Some(Self {
+            id: fetch.uid?.to_string(),
+            flags: Flags::from_imap_fetch(fetch),
+            headers: fetch.header()?.to_vec(),
+            size: fetch.size.unwrap_or_default().into(),
+        })
+    }
+}
+
+/// Fetch raw envelopes matching the given UID set from the selected
+/// IMAP folder.
+///
+/// Envelopes are returned in the order given by the server.
+pub async fn fetch_raw_imap_envelopes(
+    ctx: &mut ImapContext,
+    uids: &str,
+) -> Result<Vec<RawEnvelope>> {
+    let fetches = ctx
+        .exec(
+            |session| session.uid_fetch(uids, RAW_ENVELOPES_QUERY),
+            |err| anyhow!(err).context(format!("cannot fetch imap envelopes {uids}")),
+        )
+        .await?;
+
+    let envelopes = fetches
+        .iter()
+        .filter_map(|fetch| {
+            let envelope = RawEnvelope::from_imap_fetch(fetch);
+            if envelope.is_none() {
+                debug!(
+                    "cannot build imap envelope from fetch {}, skipping it",
+                    fetch.message
+                );
+            }
+            envelope
+        })
+        .collect();
+
+    Ok(envelopes)
+}
+
+/// Fetch raw envelopes matching the given UIDs from the selected IMAP
+/// folder.
+///
+/// Envelopes are returned in the same order as the given UIDs.
+pub async fn fetch_ordered_raw_imap_envelopes(
+    ctx: &mut ImapContext,
+    uids: &[u32],
+) -> Result<Vec<RawEnvelope>> {
+    if uids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let uids_set = uids
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let mut envelopes = fetch_raw_imap_envelopes(ctx, &uids_set).await?;
+    envelopes.sort_by_key(|envelope| {
+        let uid = envelope.id.parse::<u32>().unwrap_or_default();
+        uids.iter().position(|u| *u == uid)
+    });
+
+    Ok(envelopes)
+}
+
+/// Build the IMAP sequence set of the given UIDs, where consecutive
+/// UIDs are collapsed into ranges (`1:3,5`).
+pub fn to_uid_set(mut uids: Vec<u32>) -> String {
+    uids.sort_unstable();
+    uids.dedup();
+
+    let mut ranges: Vec<(u32, u32)> = Vec::new();
+    for uid in uids {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == uid => *end = uid,
+            _ => ranges.push((uid, uid)),
+        }
+    }
+
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            if start == end {
+                start.to_string()
+            } else {
+                format!("{start}:{end}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::to_uid_set;
+
+    #[test]
+    fn uid_set() {
+        assert_eq!(to_uid_set(vec![5, 1, 2, 3, 9, 8, 2]), "1:3,5,8:9");
+        assert_eq!(to_uid_set(vec![4]), "4");
+    }
+}
@@ -0,0 +1,22 @@
+//! Module dedicated to Maildir raw envelopes.
+
+use email::envelope::Flags;
+use maildirpp::MailEntry;
+use std::fs;
+
+use super::RawEnvelope;
+
+impl RawEnvelope {
+    pub fn from_mdir_entry(entry: &MailEntry) -> Self {
+        let size = fs::metadata(entry.path())
+            .map(|metadata| metadata.len())
+            .unwrap_or_default();
+
+        Self {
+            id: entry.id().to_owned(),
+            flags: Flags::from_mdir_entry(entry),
+            headers: entry.headers().to_vec(),
+            size,
+        }
+    }
+}
@@ -0,0 +1,35 @@
+//! Module dedicated to raw envelopes.
+//!
+//! A raw envelope is composed of the headers, the flags and the size
+//! of a message, as fetched from the backend. It contains everything
+//! needed to build envelopes, without fetching message bodies.
+
+#[cfg(feature = "imap")]
+pub mod imap;
+#[cfg(any(feature = "maildir", feature = "account-sync"))]
+pub mod maildir;
+#[cfg(feature = "notmuch")]
+pub mod notmuch;
+
+use email::{
+    envelope::{Envelope, Flags},
+    message::Message,
+};
+
+/// Represents a raw envelope, as fetched from the backend.
+#[derive(Clone, Debug)]
+pub struct RawEnvelope {
+    pub id: String,
+    pub flags: Flags,
+    pub headers: Vec<u8>,
+    pub size: u64,
+}
+
+impl RawEnvelope {
+    /// Build the backend envelope, used to filter, sort and thread
+    /// envelopes.
+    pub fn to_envelope(&self) -> Envelope {
+        let msg = Message::from(self.headers.as_slice());
+        Envelope::from_msg(&self.id, self.flags.clone(), msg)
+    }
+}
@@ -0,0 +1,34 @@
+//! Module dedicated to Notmuch raw envelopes.
+
+use anyhow::Result;
+use email::envelope::Flags;
+use std::fs;
+
+use super::RawEnvelope;
+
+impl RawEnvelope {
+    /// Build a raw envelope from the given Notmuch message.
+    ///
+    /// Notmuch does not expose raw headers, so they are read from the
+    /// message file.
+    pub fn from_notmuch_msg(msg: &notmuch::Message) -> Result<Self> {
+        let bytes = fs::read(msg.filename())?;
+        let size = bytes.len() as u64;
+
+        // keep only the header part of the message
+        let headers = match bytes.windows(4).position(|w| w == b"\r\n\r\n") {
+            Some(pos) => bytes[..pos + 4].to_vec(),
+            None => match bytes.windows(2).position(|w| w == b"\n\n") {
+                Some(pos) => bytes[..pos + 2].to_vec(),
+                None => bytes,
+            },
+        };
+
+        Ok(Self {
+            id: msg.id().to_string(),
+            flags: Flags::from_notmuch_msg(msg),
+            headers,
+            size,
+        })
+    }
+}
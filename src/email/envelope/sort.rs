@@ -0,0 +1,325 @@
+//! Module dedicated to envelopes sorting.
+//!
+//! A sort is composed of a key (date, from, subject, size or to) and
+//! an optional order (asc or desc), separated by a colon. For
+//! example: `date:desc`, `subject` or `size:asc`.
+
+use anyhow::{anyhow, bail, Error, Result};
+use email::envelope::Envelope;
+use serde::{Deserialize, Serialize};
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+/// The envelope property to sort by.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SortKey {
+    Date,
+    From,
+    Subject,
+    Size,
+    To,
+}
+
+impl fmt::Display for SortKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Date => write!(f, "date"),
+            Self::From => write!(f, "from"),
+            Self::Subject => write!(f, "subject"),
+            Self::Size => write!(f, "size"),
+            Self::To => write!(f, "to"),
+        }
+    }
+}
+
+impl FromStr for SortKey {
+    type Err = Error;
+
+    fn from_str(key: &str) -> Result<Self> {
+        match key.trim().to_lowercase().as_str() {
+            "date" => Ok(Self::Date),
+            "from" => Ok(Self::From),
+            "subject" => Ok(Self::Subject),
+            "size" => Ok(Self::Size),
+            "to" => Ok(Self::To),
+            key => bail!("cannot parse sort key {key}: expected date, from, subject, size or to"),
+        }
+    }
+}
+
+/// The sort order.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl fmt::Display for SortOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Asc => write!(f, "asc"),
+            Self::Desc => write!(f, "desc"),
+        }
+    }
+}
+
+impl FromStr for SortOrder {
+    type Err = Error;
+
+    fn from_str(order: &str) -> Result<Self> {
+        match order.trim().to_lowercase().as_str() {
+            "asc" => Ok(Self::Asc),
+            "desc" => Ok(Self::Desc),
+            order => bail!("cannot parse sort order {order}: expected asc or desc"),
+        }
+    }
+}
+
+/// The envelopes sort, composed of a key and an order.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Sort {
+    pub key: SortKey,
+    pub order: SortOrder,
+}
+
+impl Sort {
+    /// Compare the given envelopes, alongside with their size in
+    /// bytes, according to the sort.
+    ///
+    /// Envelopes sharing the same key are ordered by descending date
+    /// then by descending id, so that pagination stays stable.
+    pub fn compare(&self, a: &Envelope, a_size: u64, b: &Envelope, b_size: u64) -> Ordering {
+        let ordering = match self.key {
+            SortKey::Date => a.date.cmp(&b.date),
+            SortKey::From => address_key(&a.from.addr).cmp(&address_key(&b.from.addr)),
+            SortKey::Subject => base_subject(&a.subject).cmp(&base_subject(&b.subject)),
+            SortKey::Size => a_size.cmp(&b_size),
+            SortKey::To => address_key(&a.to.addr).cmp(&address_key(&b.to.addr)),
+        };
+
+        let ordering = match self.order {
+            SortOrder::Asc => ordering,
+            SortOrder::Desc => ordering.reverse(),
+        };
+
+        ordering
+            .then_with(|| b.date.cmp(&a.date))
+            .then_with(|| compare_ids(&b.id, &a.id))
+    }
+
+    /// Sort the given envelopes, alongside with their size in bytes.
+    pub fn sort(&self, envelopes: &mut [(Envelope, u64)]) {
+        envelopes.sort_by(|(a, a_size), (b, b_size)| self.compare(a, *a_size, b, *b_size))
+    }
+
+    /// Build the equivalent IMAP SORT criteria, as defined in the
+    /// [RFC 5256](https://www.rfc-editor.org/rfc/rfc5256#section-3).
+    ///
+    /// Like [`Sort::compare`], envelopes sharing the same key are
+    /// ordered by descending date. Remaining ties are ordered by
+    /// descending arrival date, which matches the descending id order
+    /// as long as UIDs are assigned in arrival order.
+    #[cfg(feature = "imap")]
+    pub fn to_imap_sort_criteria(&self) -> Vec<imap::extensions::sort::SortCriterion<'static>> {
+        use imap::extensions::sort::SortCriterion;
+
+        let criterion = match self.key {
+            SortKey::Date => &SortCriterion::Date,
+            SortKey::From => &SortCriterion::From,
+            SortKey::Subject => &SortCriterion::Subject,
+            SortKey::Size => &SortCriterion::Size,
+            SortKey::To => &SortCriterion::To,
+        };
+
+        let mut criteria = vec![match self.order {
+            SortOrder::Asc => *criterion,
+            SortOrder::Desc => SortCriterion::Reverse(criterion),
+        }];
+
+        if self.key != SortKey::Date {
+            criteria.push(SortCriterion::Reverse(&SortCriterion::Date));
+        }
+
+        criteria.push(SortCriterion::Reverse(&SortCriterion::Arrival));
+        criteria
+    }
+}
+
+impl fmt::Display for Sort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.key, self.order)
+    }
+}
+
+impl FromStr for Sort {
+    type Err = Error;
+
+    fn from_str(sort: &str) -> Result<Self> {
+        let (key, order) = match sort.split_once(':') {
+            Some((key, order)) => (key, Some(order)),
+            None => (sort, None),
+        };
+
+        let key = key
+            .parse()
+            .map_err(|err| anyhow!("cannot parse sort {sort}: {err}"))?;
+        let order = match order {
+            Some(order) => order
+                .parse()
+                .map_err(|err| anyhow!("cannot parse sort {sort}: {err}"))?,
+            None => SortOrder::default(),
+        };
+
+        Ok(Self { key, order })
+    }
+}
+
+impl TryFrom<String> for Sort {
+    type Error = Error;
+
+    fn try_from(sort: String) -> Result<Self> {
+        sort.parse()
+    }
+}
+
+impl From<Sort> for String {
+    fn from(sort: Sort) -> Self {
+        sort.to_string()
+    }
+}
+
+/// Build the sort key of the given email address, which is its
+/// mailbox local-part, as described in the [RFC
+/// 5256](https://www.rfc-editor.org/rfc/rfc5256#section-3) for the
+/// FROM and TO sort criteria.
+fn address_key(addr: &str) -> String {
+    let addr = addr.trim();
+    let mailbox = addr.rsplit_once('@').map_or(addr, |(mailbox, _)| mailbox);
+    mailbox.to_lowercase()
+}
+
+/// Extract the base subject of the given subject, which is the
+/// subject stripped from its reply and forward prefixes, as described
+/// in the [RFC 5256](https://www.rfc-editor.org/rfc/rfc5256#section-2.1).
+fn base_subject(subject: &str) -> String {
+    let mut subject = subject.trim().to_lowercase();
+
+    loop {
+        let stripped = ["re:", "fwd:", "fw:"]
+            .iter()
+            .find_map(|prefix| subject.strip_prefix(prefix))
+            .map(|s| s.trim_start().to_owned());
+
+        match stripped {
+            Some(stripped) => subject = stripped,
+            None => break subject,
+        }
+    }
+}
+
+/// Compare the given envelope ids, numerically if possible.
+fn compare_ids(a: &str, b: &str) -> Ordering {
+    match (a.parse::<u64>(), b.parse::<u64>()) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        _ => a.cmp(b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+    use email::envelope::{Address, Envelope};
+
+    use super::{address_key, base_subject, Sort, SortKey, SortOrder};
+
+    fn envelope(id: &str, subject: &str, date: &str) -> Envelope {
+        Envelope {
+            id: id.into(),
+            subject: subject.into(),
+            from: Address::new_nameless("alice@localhost"),
+            date: DateTime::parse_from_rfc3339(date).unwrap(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let sort: Sort = "date".parse().unwrap();
+        assert_eq!(sort.key, SortKey::Date);
+        assert_eq!(sort.order, SortOrder::Asc);
+
+        let sort: Sort = "Subject:DESC".parse().unwrap();
+        assert_eq!(sort.key, SortKey::Subject);
+        assert_eq!(sort.order, SortOrder::Desc);
+        assert_eq!(sort.to_string(), "subject:desc");
+
+        assert!("cc".parse::<Sort>().is_err());
+        assert!("date:up".parse::<Sort>().is_err());
+    }
+
+    #[test]
+    fn address_mailbox_key() {
+        assert_eq!(address_key("Alice@Localhost"), "alice");
+        assert_eq!(address_key("alice"), "alice");
+
+        let mut a = envelope("1", "a", "2024-01-01T00:00:00Z");
+        a.from = Address::new_nameless("bob@a.localhost");
+        let mut b = envelope("2", "a", "2024-01-01T00:00:00Z");
+        b.from = Address::new_nameless("alice@z.localhost");
+
+        let sort: Sort = "from".parse().unwrap();
+        assert!(sort.compare(&b, 0, &a, 0).is_lt());
+    }
+
+    #[test]
+    fn strip_base_subject() {
+        assert_eq!(base_subject("Hello"), "hello");
+        assert_eq!(base_subject("Re: RE: Fwd:  hello"), "hello");
+        assert_eq!(base_subject("Reply"), "reply");
+    }
+
+    #[test]
+    fn sort() {
+        let mut envelopes = vec![
+            (envelope("1", "b", "2024-01-01T00:00:00Z"), 30),
+            (envelope("2", "Re: a", "2024-01-03T00:00:00Z"), 10),
+            (envelope("3", "c", "2024-01-02T00:00:00Z"), 20),
+            (envelope("4", "a", "2024-01-03T00:00:00Z"), 20),
+        ];
+
+        let ids = |envelopes: &[(Envelope, u64)]| {
+            envelopes
+                .iter()
+                .map(|(e, _)| e.id.clone())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+
+        "date:desc".parse::<Sort>().unwrap().sort(&mut envelopes);
+        assert_eq!(ids(&envelopes), "4,2,3,1");
+
+        "subject".parse::<Sort>().unwrap().sort(&mut envelopes);
+        assert_eq!(ids(&envelopes), "4,2,1,3");
+
+        "size:asc".parse::<Sort>().unwrap().sort(&mut envelopes);
+        assert_eq!(ids(&envelopes), "2,4,3,1");
+    }
+
+    #[cfg(feature = "imap")]
+    #[test]
+    fn imap_sort_criteria() {
+        let criteria = |sort: &str| {
+            sort.parse::<Sort>()
+                .unwrap()
+                .to_imap_sort_criteria()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        assert_eq!(criteria("date:desc"), "REVERSE DATE REVERSE ARRIVAL");
+        assert_eq!(criteria("subject"), "SUBJECT REVERSE DATE REVERSE ARRIVAL");
+    }
+}
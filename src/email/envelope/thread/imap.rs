@@ -0,0 +1,266 @@
+//! Module dedicated to IMAP envelope threads.
+//!
+//! Threads are built by the server when it supports the THREAD
+//! extension, as defined in the [RFC 5256](https://www.rfc-editor.org/rfc/rfc5256#section-4),
+//! otherwise they are built client-side from message headers.
+
+use anyhow::{anyhow, bail, Result};
+use email::imap::{ImapContext, ImapContextSync};
+use log::{debug, info};
+use std::collections::HashMap;
+use utf7_imap::encode_utf7_imap as encode_utf7;
+
+use crate::envelope::{
+    query::{imap::search_imap_uids, Query},
+    raw::imap::{fetch_raw_imap_envelopes, to_uid_set},
+};
+
+use super::{build_threads, sort_threads, Thread, ThreadableEnvelope};
+
+/// Build envelope threads of the given IMAP folder.
+///
+/// Only envelopes matching the given query are threaded. Headers are
+/// fetched for those envelopes only.
+pub async fn thread_imap_envelopes(
+    ctx: &ImapContextSync,
+    folder: &str,
+    query: Option<&Query>,
+) -> Result<Vec<Thread<ThreadableEnvelope>>> {
+    info!("threading imap envelopes from folder {folder}");
+
+    let mut ctx = ctx.lock().await;
+
+    let folder = ctx.account_config.get_folder_alias(folder);
+    let folder_encoded = encode_utf7(folder.clone());
+    debug!("utf7 encoded folder: {folder_encoded}");
+
+    let mailbox = ctx
+        .exec(
+            |session| session.select(&folder_encoded),
+            |err| anyhow!(err).context(format!("cannot select imap folder {folder}")),
+        )
+        .await?;
+
+    if mailbox.exists == 0 {
+        return Ok(Vec::new());
+    }
+
+    let criteria = match query {
+        Some(query) => query.to_imap_search()?,
+        None => String::from("ALL"),
+    };
+    debug!("imap search criteria: {criteria}");
+
+    let capabilities = ctx
+        .exec(
+            |session| session.capabilities(),
+            |err| anyhow!(err).context("cannot get imap capabilities"),
+        )
+        .await?;
+
+    if !capabilities.has_str("THREAD=REFERENCES") {
+        debug!("imap server does not support thread, threading envelopes client-side");
+
+        let uids = search_imap_uids(&mut ctx, &criteria).await?;
+        let envelopes = fetch_threadable_envelopes(&mut ctx, uids).await?;
+
+        return Ok(build_threads(envelopes));
+    }
+
+    let cmd = format!("UID THREAD REFERENCES UTF-8 {criteria}");
+    let res = ctx
+        .exec(
+            |session| session.run_command_and_read_response(&cmd),
+            |err| anyhow!(err).context(format!("cannot thread imap envelopes from {folder}")),
+        )
+        .await?;
+
+    let threads = parse_threads(&res)?;
+    debug!("found {} imap threads", threads.len());
+
+    let mut uids = Vec::new();
+    threads
+        .iter()
+        .for_each(|thread| collect_uids(thread, &mut uids));
+
+    let mut envelopes: HashMap<u32, ThreadableEnvelope> =
+        fetch_threadable_envelopes(&mut ctx, uids)
+            .await?
+            .into_iter()
+            .filter_map(|envelope| Some((envelope.raw.id.parse().ok()?, envelope)))
+            .collect();
+
+    let mut threads: Vec<_> = threads
+        .into_iter()
+        .flat_map(|thread| resolve_thread(thread, &mut envelopes))
+        .collect();
+
+    sort_threads(&mut threads);
+
+    Ok(threads)
+}
+
+/// Fetch threadable envelopes matching the given UIDs from the
+/// selected IMAP folder.
+async fn fetch_threadable_envelopes(
+    ctx: &mut ImapContext,
+    uids: Vec<u32>,
+) -> Result<Vec<ThreadableEnvelope>> {
+    if uids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let envelopes = fetch_raw_imap_envelopes(ctx, &to_uid_set(uids))
+        .await?
+        .into_iter()
+        .map(ThreadableEnvelope::from_raw)
+        .collect();
+
+    Ok(envelopes)
+}
+
+/// Collect UIDs of the given thread, replies included.
+fn collect_uids(thread: &Thread<u32>, uids: &mut Vec<u32>) {
+    uids.push(thread.envelope);
+    thread
+        .children
+        .iter()
+        .for_each(|thread| collect_uids(thread, uids));
+}
+
+/// Replace UIDs of the given thread by their envelope.
+///
+/// Envelopes that cannot be found are pruned, their replies being
+/// promoted to their parent.
+fn resolve_thread(
+    thread: Thread<u32>,
+    envelopes: &mut HashMap<u32, ThreadableEnvelope>,
+) -> Vec<Thread<ThreadableEnvelope>> {
+    let children = thread
+        .children
+        .into_iter()
+        .flat_map(|thread| resolve_thread(thread, envelopes))
+        .collect();
+
+    match envelopes.remove(&thread.envelope) {
+        Some(envelope) => vec![Thread { envelope, children }],
+        None => children,
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Uid(u32),
+}
+
+/// Parse the UIDs threads from the given THREAD response.
+///
+/// A thread is a parenthesized list of UIDs, each UID being the
+/// parent of the next one. The list may end with nested lists,
+/// which are replies of the last UID (`(1 2 (3)(4 5))`).
+fn parse_threads(res: &[u8]) -> Result<Vec<Thread<u32>>> {
+    let res = String::from_utf8_lossy(res);
+
+    let threads = res
+        .lines()
+        .find_map(|line| line.strip_prefix("* THREAD"))
+        .ok_or_else(|| anyhow!("cannot find imap thread response"))?;
+
+    let mut tokens = tokenize(threads)?.into_iter();
+    let mut threads = Vec::new();
+
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Open => threads.extend(parse_thread(&mut tokens)?),
+            token => bail!("cannot parse imap thread response: unexpected token {token:?}"),
+        }
+    }
+
+    Ok(threads)
+}
+
+/// Parse a thread, once its opening parenthesis is consumed.
+fn parse_thread(tokens: &mut impl Iterator<Item = Token>) -> Result<Vec<Thread<u32>>> {
+    let mut uids = Vec::new();
+    let mut children = Vec::new();
+
+    loop {
+        match tokens.next() {
+            Some(Token::Uid(_)) if !children.is_empty() => {
+                bail!("cannot parse imap thread response: unexpected uid after nested thread")
+            }
+            Some(Token::Uid(uid)) => uids.push(uid),
+            Some(Token::Open) => children.extend(parse_thread(tokens)?),
+            Some(Token::Close) => break,
+            None => bail!("cannot parse imap thread response: unclosed thread"),
+        }
+    }
+
+    // a thread without UID (dummy parent) promotes its children
+    for uid in uids.into_iter().rev() {
+        children = vec![Thread {
+            envelope: uid,
+            children,
+        }];
+    }
+
+    Ok(children)
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.trim().chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            c if c.is_whitespace() => continue,
+            c if c.is_ascii_digit() => {
+                let mut uid = String::from(c);
+                while let Some(c) = chars.next_if(char::is_ascii_digit) {
+                    uid.push(c);
+                }
+                tokens.push(Token::Uid(uid.parse()?));
+            }
+            c => bail!("cannot parse imap thread response: unexpected character {c}"),
+        }
+    }
+
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_threads, Thread};
+
+    fn ids(threads: &[Thread<u32>]) -> String {
+        threads
+            .iter()
+            .map(|thread| {
+                if thread.children.is_empty() {
+                    thread.envelope.to_string()
+                } else {
+                    format!("{}({})", thread.envelope, ids(&thread.children))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn parse() {
+        let res = b"* THREAD (2)(3 6 (4 23)(44 7 96))((11)(12 13))\r\n";
+        let threads = parse_threads(res).unwrap();
+        assert_eq!(ids(&threads), "2 3(6(4(23) 44(7(96)))) 11 12(13)");
+
+        let res = b"* THREAD\r\n";
+        assert!(parse_threads(res).unwrap().is_empty());
+
+        assert!(parse_threads(b"* THREAD (1 (2)\r\n").is_err());
+        assert!(parse_threads(b"* THREAD (1 (2) 3)\r\n").is_err());
+        assert!(parse_threads(b"* OK\r\n").is_err());
+    }
+}
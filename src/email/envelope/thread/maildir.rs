@@ -0,0 +1,58 @@
+//! Module dedicated to Maildir envelope threads.
+
+use anyhow::Result;
+use email::maildir::MaildirContextSync;
+use log::{debug, info};
+
+use crate::envelope::{
+    query::{maildir::read_text_body, Query},
+    raw::RawEnvelope,
+};
+
+use super::{build_threads, Thread, ThreadableEnvelope};
+
+/// Build envelope threads of the given Maildir folder.
+///
+/// Only envelopes matching the given query are threaded.
+pub async fn thread_maildir_envelopes(
+    ctx: &MaildirContextSync,
+    folder: &str,
+    query: Option<&Query>,
+) -> Result<Vec<Thread<ThreadableEnvelope>>> {
+    info!("threading maildir envelopes from folder {folder}");
+
+    let ctx = ctx.lock().await;
+    let mdir = ctx.get_maildir_from_folder_name(folder)?;
+
+    let envelopes = mdir
+        .list_cur()
+        .filter_map(|entry| match entry {
+            Ok(entry) => Some(entry),
+            Err(err) => {
+                debug!("cannot parse maildir entry, skipping it: {err}");
+                debug!("{err:?}");
+                None
+            }
+        })
+        .filter_map(|entry| {
+            let envelope = ThreadableEnvelope::from_raw(RawEnvelope::from_mdir_entry(&entry));
+
+            match query {
+                Some(query) => {
+                    let body = if query.needs_body() {
+                        read_text_body(entry.path())
+                    } else {
+                        None
+                    };
+
+                    query
+                        .matches(&envelope.envelope, body.as_deref())
+                        .then_some(envelope)
+                }
+                None => Some(envelope),
+            }
+        })
+        .collect();
+
+    Ok(build_threads(envelopes))
+}
@@ -0,0 +1,497 @@
+//! Module dedicated to envelope threads.
+//!
+//! Envelopes are grouped into conversation threads following the
+//! [JWZ algorithm](https://www.jwz.org/doc/threading.html), based on
+//! the Message-ID, In-Reply-To and References headers.
+
+#[cfg(feature = "imap")]
+pub mod imap;
+#[cfg(any(feature = "maildir", feature = "account-sync"))]
+pub mod maildir;
+#[cfg(feature = "notmuch")]
+pub mod notmuch;
+
+use anyhow::Result;
+use chrono::{DateTime, FixedOffset};
+use email::{account::config::AccountConfig, message::Message};
+use log::{debug, warn};
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::{
+    cache::IdMapper,
+    flag::config::FlagDisplayConfig,
+    printer::{PrintTable, PrintTableOpts, WriteColor},
+    ui::{print_rows, Row},
+};
+
+use super::{raw::RawEnvelope, Envelope, EnvelopeColumn};
+
+/// An envelope alongside with its replies.
+#[derive(Clone, Debug, Serialize)]
+pub struct Thread<T> {
+    #[serde(flatten)]
+    pub envelope: T,
+    pub children: Vec<Thread<T>>,
+}
+
+impl<T> Thread<T> {
+    pub fn new(envelope: T) -> Self {
+        Self {
+            envelope,
+            children: Vec::new(),
+        }
+    }
+}
+
+impl Thread<ThreadableEnvelope> {
+    /// Get the date of the most recent envelope of the thread.
+    fn latest_date(&self) -> DateTime<FixedOffset> {
+        self.children
+            .iter()
+            .map(Thread::latest_date)
+            .fold(self.envelope.envelope.date, |a, b| a.max(b))
+    }
+}
+
+/// Sort the given threads.
+///
+/// Threads are ordered by descending date of their most recent
+/// envelope, whereas replies are ordered by ascending date.
+pub fn sort_threads(threads: &mut [Thread<ThreadableEnvelope>]) {
+    fn sort_replies(thread: &mut Thread<ThreadableEnvelope>) {
+        thread
+            .children
+            .sort_by_key(|thread| thread.envelope.envelope.date);
+        thread.children.iter_mut().for_each(sort_replies);
+    }
+
+    threads.iter_mut().for_each(sort_replies);
+    threads.sort_by_cached_key(|thread| std::cmp::Reverse(thread.latest_date()));
+}
+
+/// An envelope alongside with the identifiers of the messages it
+/// refers to, used to build threads.
+#[derive(Clone, Debug)]
+pub struct ThreadableEnvelope {
+    pub raw: RawEnvelope,
+    pub envelope: email::envelope::Envelope,
+    pub message_id: Option<String>,
+    pub references: Vec<String>,
+}
+
+impl ThreadableEnvelope {
+    /// Build a threadable envelope from the given raw envelope.
+    pub fn from_raw(raw: RawEnvelope) -> Self {
+        let msg = Message::from(raw.headers.as_slice());
+
+        let (message_id, references) = match msg.parsed() {
+            Ok(parsed) => {
+                let message_id = parsed.message_id().map(ToOwned::to_owned);
+
+                let mut references: Vec<String> = parsed
+                    .references()
+                    .as_text_list()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(ToOwned::to_owned)
+                    .collect();
+
+                // In-Reply-To usually duplicates the last reference,
+                // but some clients only set this one
+                if let Some(in_reply_to) = parsed.in_reply_to().as_text() {
+                    if references.last().map(String::as_str) != Some(in_reply_to) {
+                        references.push(in_reply_to.to_owned());
+                    }
+                }
+
+                (message_id, references)
+            }
+            Err(_) => (None, Vec::new()),
+        };
+        drop(msg);
+
+        Self {
+            envelope: raw.to_envelope(),
+            raw,
+            message_id,
+            references,
+        }
+    }
+}
+
+/// A node of the threading algorithm. A container without envelope
+/// represents a message that is referenced but not present.
+#[derive(Default)]
+struct Container {
+    envelope: Option<ThreadableEnvelope>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+}
+
+/// Build threads from the given envelopes.
+///
+/// Messages referenced but not present are pruned, their replies
+/// being promoted to their parent (or to the root set).
+pub fn build_threads(envelopes: Vec<ThreadableEnvelope>) -> Vec<Thread<ThreadableEnvelope>> {
+    let mut containers: Vec<Container> = Vec::new();
+    let mut ids: HashMap<String, usize> = HashMap::new();
+
+    let mut get_or_insert = |containers: &mut Vec<Container>, id: &str| -> usize {
+        *ids.entry(id.to_owned()).or_insert_with(|| {
+            containers.push(Container::default());
+            containers.len() - 1
+        })
+    };
+
+    for threadable in envelopes {
+        // envelopes without Message-ID or sharing the Message-ID of
+        // another envelope get their own container
+        let idx = match &threadable.message_id {
+            Some(id) => {
+                let idx = get_or_insert(&mut containers, id);
+                if containers[idx].envelope.is_some() {
+                    containers.push(Container::default());
+                    containers.len() - 1
+                } else {
+                    idx
+                }
+            }
+            None => {
+                containers.push(Container::default());
+                containers.len() - 1
+            }
+        };
+
+        let references = threadable.references.clone();
+        containers[idx].envelope = Some(threadable);
+
+        // link references together, without breaking existing links
+        let mut prev: Option<usize> = None;
+        for reference in &references {
+            let ref_idx = get_or_insert(&mut containers, reference);
+
+            if let Some(prev) = prev {
+                if containers[ref_idx].parent.is_none()
+                    && prev != ref_idx
+                    && !is_ancestor(&containers, ref_idx, prev)
+                {
+                    link(&mut containers, prev, ref_idx);
+                }
+            }
+
+            prev = Some(ref_idx);
+        }
+
+        // the last reference is the parent of the envelope
+        if let Some(parent) = prev {
+            if parent != idx && !is_ancestor(&containers, idx, parent) {
+                unlink(&mut containers, idx);
+                link(&mut containers, parent, idx);
+            }
+        }
+    }
+
+    let roots: Vec<usize> = (0..containers.len())
+        .filter(|idx| containers[*idx].parent.is_none())
+        .collect();
+
+    let mut threads: Vec<_> = roots
+        .into_iter()
+        .flat_map(|idx| collect_thread(&mut containers, idx))
+        .collect();
+
+    sort_threads(&mut threads);
+    threads
+}
+
+/// Check if the given ancestor container is an ancestor of the given
+/// container.
+fn is_ancestor(containers: &[Container], ancestor: usize, mut idx: usize) -> bool {
+    while let Some(parent) = containers[idx].parent {
+        if parent == ancestor {
+            return true;
+        }
+        idx = parent;
+    }
+
+    false
+}
+
+fn link(containers: &mut [Container], parent: usize, child: usize) {
+    containers[child].parent = Some(parent);
+    containers[parent].children.push(child);
+}
+
+fn unlink(containers: &mut [Container], child: usize) {
+    if let Some(parent) = containers[child].parent.take() {
+        containers[parent].children.retain(|idx| *idx != child);
+    }
+}
+
+/// Collect the thread starting at the given container, pruning empty
+/// containers on the way.
+fn collect_thread(containers: &mut [Container], idx: usize) -> Vec<Thread<ThreadableEnvelope>> {
+    let children: Vec<_> = containers[idx]
+        .children
+        .clone()
+        .into_iter()
+        .flat_map(|child| collect_thread(containers, child))
+        .collect();
+
+    match containers[idx].envelope.take() {
+        Some(envelope) => vec![Thread { envelope, children }],
+        None => children,
+    }
+}
+
+/// Represents the list of envelope threads.
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(transparent)]
+pub struct EnvelopeThreads {
+    threads: Vec<Thread<Envelope>>,
+    #[serde(skip)]
+    columns: Option<Vec<EnvelopeColumn>>,
+    #[serde(skip)]
+    flags_display: HashMap<String, FlagDisplayConfig>,
+}
+
+impl EnvelopeThreads {
+    /// Build envelope threads from the given threadable envelopes.
+    ///
+    /// Envelopes with invalid headers are skipped, their replies
+    /// being promoted to their parent.
+    pub fn from_raw(
+        config: &AccountConfig,
+        id_mapper: &IdMapper,
+        threads: Vec<Thread<ThreadableEnvelope>>,
+    ) -> Result<EnvelopeThreads> {
+        fn map(
+            config: &AccountConfig,
+            id_mapper: &IdMapper,
+            thread: Thread<ThreadableEnvelope>,
+        ) -> Result<Vec<Thread<Envelope>>> {
+            let raw = &thread.envelope.raw;
+
+            let mut children = Vec::new();
+            for thread in thread.children {
+                children.extend(map(config, id_mapper, thread)?);
+            }
+
+            let msg = Message::from(raw.headers.as_slice());
+            match msg.parsed() {
+                Ok(parsed) => {
+                    let envelope = Envelope::from_parsed(config, id_mapper, raw, parsed)?;
+                    Ok(vec![Thread { envelope, children }])
+                }
+                Err(err) => {
+                    warn!(
+                        "cannot parse envelope {} headers, skipping it: {err}",
+                        raw.id
+                    );
+                    debug!("{err:?}");
+                    Ok(children)
+                }
+            }
+        }
+
+        let mut envelope_threads = Vec::new();
+        for thread in threads {
+            envelope_threads.extend(map(config, id_mapper, thread)?);
+        }
+
+        Ok(EnvelopeThreads {
+            threads: envelope_threads,
+            columns: None,
+            flags_display: HashMap::new(),
+        })
+    }
+
+    /// Set the columns of the table, defaults to
+    /// [`EnvelopeColumn::DEFAULT`].
+    pub fn set_columns(&mut self, columns: Option<Vec<EnvelopeColumn>>) {
+        self.columns = columns;
+    }
+
+    /// Define how custom flags are rendered in the table output.
+    pub fn set_flags_display(&mut self, flags_display: HashMap<String, FlagDisplayConfig>) {
+        self.flags_display = flags_display;
+    }
+}
+
+impl PrintTable for EnvelopeThreads {
+    fn print_table(&self, writer: &mut dyn WriteColor, opts: PrintTableOpts) -> Result<()> {
+        let columns = self.columns.as_deref().unwrap_or(&EnvelopeColumn::DEFAULT);
+        let mut rows = Vec::new();
+
+        for thread in &self.threads {
+            flatten_thread(thread, "", "", &mut rows);
+        }
+
+        let rows = rows
+            .iter()
+            .map(|row| row.row_with(columns, &self.flags_display));
+
+        writeln!(writer)?;
+        print_rows(writer, Envelope::head_with(columns), rows, opts)?;
+        writeln!(writer)?;
+        Ok(())
+    }
+}
+
+/// Represents an envelope of a flattened thread, prefixed by its
+/// position in the tree.
+struct ThreadRow<'a> {
+    prefix: String,
+    envelope: &'a Envelope,
+}
+
+impl ThreadRow<'_> {
+    fn row_with(
+        &self,
+        columns: &[EnvelopeColumn],
+        flags_display: &HashMap<String, FlagDisplayConfig>,
+    ) -> Row {
+        let envelope = Envelope {
+            subject: format!("{}{}", self.prefix, self.envelope.subject),
+            ..self.envelope.clone()
+        };
+
+        envelope.row_with(columns, flags_display)
+    }
+}
+
+/// Flatten the given thread into table rows, drawing the tree in
+/// front of the subject.
+fn flatten_thread<'a>(
+    thread: &'a Thread<Envelope>,
+    prefix: &str,
+    indent: &str,
+    rows: &mut Vec<ThreadRow<'a>>,
+) {
+    rows.push(ThreadRow {
+        prefix: prefix.to_owned(),
+        envelope: &thread.envelope,
+    });
+
+    let len = thread.children.len();
+    for (i, child) in thread.children.iter().enumerate() {
+        let (branch, next_indent) = if i + 1 == len {
+            ("└─ ", "   ")
+        } else {
+            ("├─ ", "│  ")
+        };
+
+        flatten_thread(
+            child,
+            &format!("{indent}{branch}"),
+            &format!("{indent}{next_indent}"),
+            rows,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use email::{account::config::AccountConfig, envelope::Flags};
+
+    use crate::cache::IdMapper;
+
+    use super::{build_threads, EnvelopeThreads, RawEnvelope, Thread, ThreadableEnvelope};
+
+    fn threadable(id: &str, headers: &str) -> ThreadableEnvelope {
+        ThreadableEnvelope::from_raw(RawEnvelope {
+            id: id.into(),
+            flags: Flags::default(),
+            headers: headers.as_bytes().to_vec(),
+            size: headers.len() as u64,
+        })
+    }
+
+    fn ids(threads: &[Thread<ThreadableEnvelope>]) -> String {
+        threads
+            .iter()
+            .map(|thread| {
+                if thread.children.is_empty() {
+                    thread.envelope.raw.id.clone()
+                } else {
+                    format!("{}({})", thread.envelope.raw.id, ids(&thread.children))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn build() {
+        let threads = build_threads(vec![
+            threadable(
+                "4",
+                "Message-ID: <4@x>\r\nReferences: <1@x> <2@x>\r\nDate: Thu, 4 Jan 2024 00:00:00 +0000\r\n\r\n",
+            ),
+            threadable(
+                "1",
+                "Message-ID: <1@x>\r\nDate: Mon, 1 Jan 2024 00:00:00 +0000\r\n\r\n",
+            ),
+            threadable(
+                "2",
+                "Message-ID: <2@x>\r\nIn-Reply-To: <1@x>\r\nDate: Tue, 2 Jan 2024 00:00:00 +0000\r\n\r\n",
+            ),
+            threadable(
+                "3",
+                "Message-ID: <3@x>\r\nIn-Reply-To: <1@x>\r\nDate: Wed, 3 Jan 2024 00:00:00 +0000\r\n\r\n",
+            ),
+            threadable(
+                "5",
+                "Message-ID: <5@x>\r\nDate: Tue, 2 Jan 2024 00:00:00 +0000\r\n\r\n",
+            ),
+            // replies to a missing message are promoted to the root
+            threadable(
+                "6",
+                "Message-ID: <6@x>\r\nIn-Reply-To: <missing@x>\r\nDate: Fri, 5 Jan 2024 00:00:00 +0000\r\n\r\n",
+            ),
+            threadable(
+                "7",
+                "Message-ID: <7@x>\r\nIn-Reply-To: <6@x>\r\nDate: Sat, 6 Jan 2024 00:00:00 +0000\r\n\r\n",
+            ),
+        ]);
+
+        assert_eq!(ids(&threads), "6(7) 1(2(4) 3) 5");
+    }
+
+    #[test]
+    fn build_with_loop() {
+        let threads = build_threads(vec![
+            threadable(
+                "1",
+                "Message-ID: <1@x>\r\nIn-Reply-To: <2@x>\r\nDate: Mon, 1 Jan 2024 00:00:00 +0000\r\n\r\n",
+            ),
+            threadable(
+                "2",
+                "Message-ID: <2@x>\r\nIn-Reply-To: <1@x>\r\nDate: Tue, 2 Jan 2024 00:00:00 +0000\r\n\r\n",
+            ),
+        ]);
+
+        assert_eq!(ids(&threads), "2(1)");
+    }
+
+    #[test]
+    fn skip_invalid_headers() {
+        let threads = vec![Thread {
+            envelope: threadable("1", "Message-ID: <1@x>\r\n\r\n"),
+            children: vec![Thread {
+                envelope: threadable("2", ""),
+                children: vec![Thread::new(threadable("3", "Message-ID: <3@x>\r\n\r\n"))],
+            }],
+        }];
+
+        let threads =
+            EnvelopeThreads::from_raw(&AccountConfig::default(), &IdMapper::Dummy, threads)
+                .unwrap();
+
+        assert_eq!(threads.threads.len(), 1);
+        assert_eq!(threads.threads[0].envelope.id, "1");
+        assert_eq!(threads.threads[0].children.len(), 1);
+        assert_eq!(threads.threads[0].children[0].envelope.id, "3");
+    }
+}
@@ -0,0 +1,45 @@
+//! Module dedicated to Notmuch envelope threads.
+
+use anyhow::Result;
+use email::notmuch::NotmuchContextSync;
+use log::{debug, info};
+
+use crate::envelope::{
+    query::{notmuch::to_notmuch_folder_query, Query},
+    raw::RawEnvelope,
+};
+
+use super::{build_threads, Thread, ThreadableEnvelope};
+
+/// Build envelope threads of the given Notmuch folder.
+///
+/// Only envelopes matching the given query are threaded.
+pub async fn thread_notmuch_envelopes(
+    ctx: &NotmuchContextSync,
+    folder: &str,
+    query: Option<&Query>,
+) -> Result<Vec<Thread<ThreadableEnvelope>>> {
+    info!("threading notmuch envelopes from folder {folder}");
+
+    let ctx = ctx.lock().await;
+    let config = &ctx.account_config;
+    let db = ctx.open_db()?;
+
+    let folder_query = to_notmuch_folder_query(config, folder);
+
+    let query = match query {
+        Some(query) => format!("{folder_query} and {}", query.to_notmuch_query()),
+        None => folder_query,
+    };
+    debug!("notmuch query: {query}");
+
+    let query_builder = db.create_query(&query)?;
+    let envelopes = query_builder
+        .search_messages()?
+        .map(|msg| RawEnvelope::from_notmuch_msg(&msg).map(ThreadableEnvelope::from_raw))
+        .collect::<Result<_>>()?;
+
+    db.close()?;
+
+    Ok(build_threads(envelopes))
+}
@@ -0,0 +1,274 @@
+//! Module dedicated to unified envelope listings.
+//!
+//! A unified listing spans several folders of an account, or several
+//! accounts. Envelopes are searched concurrently, then merged into a
+//! single list. When spanning accounts, envelope ids are qualified by
+//! their account name (ACCOUNT:ID), so that they can be used by other
+//! commands.
+
+use anyhow::Result;
+use email::{backend::feature::BackendFeatureSource, folder::list::ListFolders};
+use futures::future::join_all;
+use log::{debug, info, warn};
+use std::cmp::Reverse;
+
+use crate::{account::config::TomlAccountConfig, backend::Backend, config::TomlConfig};
+
+use super::{paginate, query::Query, sort::Sort, Envelope, Envelopes};
+
+/// The folder name matching all folders.
+pub const ALL_FOLDERS: &str = "*";
+
+/// An envelope alongside with its backend envelope, used to merge
+/// envelopes coming from different sources.
+type MergeableEnvelope = (email::envelope::Envelope, Envelope);
+
+/// Search envelopes matching the given query from all the folders of
+/// the given backend.
+///
+/// Each folder is asked for enough envelopes to fill the given page,
+/// then envelopes are merged following the given sort (or by
+/// descending date) before being paginated.
+pub async fn search_all_folders_envelopes(
+    backend: &Backend,
+    toml_account_config: &TomlAccountConfig,
+    query: Option<&Query>,
+    sort: Option<&Sort>,
+    page_size: usize,
+    page: usize,
+) -> Result<Envelopes> {
+    let folders = list_folders(backend, toml_account_config).await?;
+    let limit = page_size * (page + 1);
+    let envelopes = search_folders_envelopes(backend, &folders, query, sort, limit).await?;
+    merge_envelopes(envelopes, "all folders", sort, page_size, page)
+}
+
+/// Search envelopes matching the given query from the given folder
+/// of all configured accounts. Without folder, all folders of all
+/// accounts are searched.
+///
+/// Each account is asked for enough envelopes to fill the given
+/// page, then envelopes are merged following the given sort (or by
+/// descending date) before being paginated. Accounts that cannot be
+/// searched are skipped with a warning, unless all of them fail.
+pub async fn search_all_accounts_envelopes(
+    config: &TomlConfig,
+    folder: Option<&str>,
+    query: Option<&Query>,
+    sort: Option<&Sort>,
+    page_size: usize,
+    page: usize,
+    #[cfg(feature = "account-sync")] disable_cache: bool,
+) -> Result<Envelopes> {
+    let mut names: Vec<_> = config.accounts.keys().collect();
+    names.sort();
+    info!("searching envelopes from {} accounts", names.len());
+
+    // every account needs to provide all envelopes up to the
+    // requested page, since the page can be composed of envelopes
+    // from any account
+    let limit = page_size * (page + 1);
+
+    let searches = names.into_iter().map(|name| {
+        search_account_envelopes(
+            config,
+            name,
+            folder,
+            query,
+            sort,
+            limit,
+            #[cfg(feature = "account-sync")]
+            disable_cache,
+        )
+    });
+
+    let mut envelopes = Vec::new();
+    let mut searched = false;
+    let mut last_err = None;
+
+    for result in join_all(searches).await {
+        match result {
+            Ok(account_envelopes) => {
+                searched = true;
+                envelopes.extend(account_envelopes);
+            }
+            Err(err) => {
+                warn!("{err:#}");
+                last_err = Some(err);
+            }
+        }
+    }
+
+    if let Some(err) = last_err.filter(|_| !searched) {
+        return Err(err.context("cannot search envelopes from any account"));
+    }
+
+    merge_envelopes(
+        envelopes,
+        folder.unwrap_or("all folders"),
+        sort,
+        page_size,
+        page,
+    )
+}
+
+/// Search envelopes of the given account, qualifying their id with
+/// the account name.
+async fn search_account_envelopes(
+    config: &TomlConfig,
+    name: &str,
+    folder: Option<&str>,
+    query: Option<&Query>,
+    sort: Option<&Sort>,
+    limit: usize,
+    #[cfg(feature = "account-sync")] disable_cache: bool,
+) -> Result<Vec<MergeableEnvelope>> {
+    let (toml_account_config, account_config) = config.clone().into_account_configs(
+        Some(name),
+        #[cfg(feature = "account-sync")]
+        disable_cache,
+    )?;
+
+    let list_envelopes_kind = toml_account_config.list_envelopes_kind();
+    let list_folders_kind = toml_account_config.list_folders_kind();
+
+    let backend = Backend::new(
+        toml_account_config.clone(),
+        account_config,
+        list_envelopes_kind.into_iter().chain(list_folders_kind),
+        |builder| {
+            builder.set_list_envelopes(BackendFeatureSource::Context);
+            builder.set_list_folders(BackendFeatureSource::Context);
+        },
+    )
+    .await?;
+
+    let folders = match folder {
+        Some(folder) => vec![folder.to_owned()],
+        None => list_folders(&backend, &toml_account_config).await?,
+    };
+
+    let envelopes = search_folders_envelopes(&backend, &folders, query, sort, limit)
+        .await
+        .map_err(|err| err.context(format!("cannot search envelopes of account {name}")))?
+        .into_iter()
+        .map(|(sort_envelope, envelope)| {
+            let envelope = Envelope {
+                id: format!("{name}:{}", envelope.id),
+                account: Some(name.to_owned()),
+                ..envelope
+            };
+            (sort_envelope, envelope)
+        })
+        .collect();
+
+    Ok(envelopes)
+}
+
+/// List the folders of the given backend, following the folder
+/// synchronization filter of the account.
+async fn list_folders(
+    backend: &Backend,
+    toml_account_config: &TomlAccountConfig,
+) -> Result<Vec<String>> {
+    let folders: Vec<_> = backend
+        .list_folders()
+        .await?
+        .iter()
+        .map(|folder| folder.name.clone())
+        .filter(|folder| {
+            toml_account_config
+                .folder
+                .as_ref()
+                .map(|config| config.sync_filter_matches(folder))
+                .unwrap_or(true)
+        })
+        .collect();
+
+    debug!("searching envelopes from folders {folders:?}");
+    Ok(folders)
+}
+
+/// Search envelopes of the given folders concurrently, tagging them
+/// with their folder name.
+///
+/// Folders that cannot be searched are skipped with a warning,
+/// unless all of them fail.
+async fn search_folders_envelopes(
+    backend: &Backend,
+    folders: &[String],
+    query: Option<&Query>,
+    sort: Option<&Sort>,
+    limit: usize,
+) -> Result<Vec<MergeableEnvelope>> {
+    let searches = folders.iter().map(|folder| async move {
+        let raw_envelopes = backend
+            .search_raw_envelopes(folder, query, sort, limit, 0)
+            .await
+            .map_err(|err| err.context(format!("cannot search envelopes from {folder}")))?;
+
+        let envelopes = backend
+            .to_paired_envelopes(folder, &raw_envelopes)?
+            .into_iter()
+            .map(|(raw, envelope)| {
+                let envelope = Envelope {
+                    folder: Some(folder.clone()),
+                    ..envelope
+                };
+                (raw.to_envelope(), envelope)
+            })
+            .collect();
+
+        Result::<Vec<_>>::Ok(envelopes)
+    });
+
+    let mut envelopes = Vec::new();
+    let mut searched = false;
+    let mut last_err = None;
+
+    for result in join_all(searches).await {
+        match result {
+            Ok(folder_envelopes) => {
+                searched = true;
+                envelopes.extend(folder_envelopes);
+            }
+            Err(err) => {
+                warn!("{err:#}");
+                debug!("{err:?}");
+                last_err = Some(err);
+            }
+        }
+    }
+
+    if let Some(err) = last_err.filter(|_| !searched) {
+        return Err(err.context("cannot search envelopes from any folder"));
+    }
+
+    Ok(envelopes)
+}
+
+/// Merge the given envelopes following the given sort (or by
+/// descending date), then extract the given page.
+fn merge_envelopes(
+    mut envelopes: Vec<MergeableEnvelope>,
+    source: &str,
+    sort: Option<&Sort>,
+    page_size: usize,
+    page: usize,
+) -> Result<Envelopes> {
+    debug!("merging {} envelopes from {source}", envelopes.len());
+
+    match sort {
+        Some(sort) => envelopes.sort_by(|(a, a_envelope), (b, b_envelope)| {
+            sort.compare(a, a_envelope.size, b, b_envelope.size)
+        }),
+        None => envelopes.sort_by_key(|(envelope, _)| Reverse(envelope.date)),
+    }
+
+    let envelopes = paginate(envelopes, source, page_size, page)?;
+
+    Ok(envelopes
+        .into_iter()
+        .map(|(_, envelope)| envelope)
+        .collect())
+}
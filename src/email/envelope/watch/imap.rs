@@ -0,0 +1,172 @@
+//! Module dedicated to IMAP envelope watch events.
+
+use anyhow::{anyhow, Result};
+use email::{envelope::Flags, imap::ImapContextSync};
+use imap::{
+    extensions::idle::{stop_on_any, WaitOutcome},
+    types::Mailbox,
+};
+use log::{debug, info};
+use std::time::Duration;
+use utf7_imap::encode_utf7_imap as encode_utf7;
+
+use crate::envelope::raw::{
+    imap::{fetch_raw_imap_envelopes, to_uid_set},
+    RawEnvelope,
+};
+
+use super::Snapshot;
+
+/// The IMAP fetch query used to take snapshots.
+const SNAPSHOT_QUERY: &str = "(UID FLAGS)";
+
+/// Take a snapshot of the envelopes of the given IMAP folder.
+///
+/// Only UIDs and flags are fetched: headers and sizes are taken from
+/// the given previous snapshot, so that they are fetched for new
+/// envelopes only.
+///
+/// The folder is examined rather than selected, so that watching
+/// does not alter the recent flag of envelopes.
+pub async fn snapshot_imap_envelopes(
+    ctx: &ImapContextSync,
+    folder: &str,
+    prev: Option<&Snapshot>,
+) -> Result<Vec<RawEnvelope>> {
+    debug!("taking snapshot of imap folder {folder}");
+
+    let mut ctx = ctx.lock().await;
+
+    let folder = ctx.account_config.get_folder_alias(folder);
+    let folder_encoded = encode_utf7(folder.clone());
+    debug!("utf7 encoded folder: {folder_encoded}");
+
+    let mailbox = ctx
+        .exec(
+            |session| session.examine(&folder_encoded),
+            |err| anyhow!(err).context(format!("cannot examine imap folder {folder}")),
+        )
+        .await?;
+
+    if mailbox.exists == 0 {
+        return Ok(Vec::new());
+    }
+
+    let fetches = ctx
+        .exec(
+            |session| session.uid_fetch("1:*", SNAPSHOT_QUERY),
+            |err| anyhow!(err).context(format!("cannot fetch imap folder {folder} flags")),
+        )
+        .await?;
+
+    let mut envelopes = Vec::with_capacity(fetches.len());
+    let mut new_uids = Vec::new();
+
+    for fetch in fetches.iter() {
+        let Some(uid) = fetch.uid else {
+            debug!("cannot find uid of fetch {}, skipping it", fetch.message);
+            continue;
+        };
+
+        match prev.and_then(|prev| prev.get(&uid.to_string())) {
+            Some(envelope) => envelopes.push(RawEnvelope {
+                flags: Flags::from_imap_fetch(fetch),
+                ..envelope.clone()
+            }),
+            None => new_uids.push(uid),
+        }
+    }
+
+    debug!("fetching headers of {} new imap envelopes", new_uids.len());
+
+    if !new_uids.is_empty() {
+        let new_envelopes = fetch_raw_imap_envelopes(&mut ctx, &to_uid_set(new_uids)).await?;
+        envelopes.extend(new_envelopes);
+    }
+
+    Ok(envelopes)
+}
+
+/// The state of an IMAP folder, as returned when examining it.
+///
+/// A change of state means that envelopes were added or removed, or
+/// that their flags changed when the server supports the CONDSTORE
+/// extension.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct ImapFolderState {
+    exists: u32,
+    uid_next: Option<u32>,
+    uid_validity: Option<u32>,
+    highest_mod_seq: Option<u64>,
+}
+
+impl From<&Mailbox> for ImapFolderState {
+    fn from(mailbox: &Mailbox) -> Self {
+        Self {
+            exists: mailbox.exists,
+            uid_next: mailbox.uid_next,
+            uid_validity: mailbox.uid_validity,
+            highest_mod_seq: mailbox.highest_mod_seq,
+        }
+    }
+}
+
+/// The IMAP watcher.
+///
+/// The watcher keeps the state of the folder from one wait to
+/// another, so that changes occurring between two IDLE commands are
+/// not missed.
+#[derive(Default)]
+pub struct ImapWatcher {
+    state: Option<ImapFolderState>,
+}
+
+impl ImapWatcher {
+    /// Wait for changes in the given IMAP folder, using the IDLE
+    /// extension.
+    ///
+    /// The wait ends after the IMAP watch timeout
+    /// (imap.watch.timeout), even if no change occurred, in which
+    /// case `false` is returned.
+    pub async fn wait(&mut self, ctx: &ImapContextSync, folder: &str) -> Result<bool> {
+        info!("waiting for changes in imap folder {folder}");
+
+        let timeout = ctx.imap_config.find_watch_timeout();
+        let mut ctx = ctx.lock().await;
+
+        let folder = ctx.account_config.get_folder_alias(folder);
+        let folder_encoded = encode_utf7(folder.clone());
+        debug!("utf7 encoded folder: {folder_encoded}");
+
+        let mailbox = ctx
+            .exec(
+                |session| session.examine(&folder_encoded),
+                |err| anyhow!(err).context(format!("cannot examine imap folder {folder}")),
+            )
+            .await?;
+
+        let state = ImapFolderState::from(&mailbox);
+        if self.state.replace(state).is_some_and(|prev| prev != state) {
+            debug!("imap folder {folder} changed since the last wait");
+            return Ok(true);
+        }
+
+        let outcome = ctx
+            .exec(
+                |session| {
+                    let mut idle = session.idle();
+
+                    if let Some(secs) = timeout {
+                        debug!("setting imap idle timeout option at {secs}secs");
+                        idle.timeout(Duration::new(secs, 0));
+                    }
+
+                    idle.wait_while(stop_on_any)
+                },
+                |err| anyhow!(err).context(format!("cannot run imap idle mode on folder {folder}")),
+            )
+            .await?;
+
+        Ok(outcome == WaitOutcome::MailboxChanged)
+    }
+}
@@ -0,0 +1,103 @@
+//! Module dedicated to Maildir envelope watch events.
+
+use anyhow::{Context, Result};
+use email::maildir::MaildirContextSync;
+use futures::{
+    channel::mpsc::{unbounded, UnboundedReceiver},
+    StreamExt,
+};
+use log::{debug, info, trace};
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::envelope::raw::RawEnvelope;
+
+/// Take a snapshot of the envelopes of the given Maildir folder,
+/// including the new ones.
+pub async fn snapshot_maildir_envelopes(
+    ctx: &MaildirContextSync,
+    folder: &str,
+) -> Result<Vec<RawEnvelope>> {
+    debug!("taking snapshot of maildir folder {folder}");
+
+    let ctx = ctx.lock().await;
+    let mdir = ctx.get_maildir_from_folder_name(folder)?;
+
+    let envelopes = mdir
+        .list_new()
+        .chain(mdir.list_cur())
+        .filter_map(|entry| match entry {
+            Ok(entry) => Some(RawEnvelope::from_mdir_entry(&entry)),
+            Err(err) => {
+                debug!("cannot parse maildir entry, skipping it: {err}");
+                debug!("{err:?}");
+                None
+            }
+        })
+        // entries being written are empty, they will be part of the
+        // snapshot following their next change
+        .filter(|envelope: &RawEnvelope| !envelope.headers.is_empty())
+        .collect();
+
+    Ok(envelopes)
+}
+
+/// The Maildir file system watcher.
+///
+/// The watcher lives as long as the folder is watched, so that no
+/// change is missed between two waits.
+pub struct MaildirWatcher {
+    // the watcher stops watching when dropped
+    _watcher: RecommendedWatcher,
+    events: UnboundedReceiver<notify::Result<Event>>,
+}
+
+impl MaildirWatcher {
+    pub async fn new(ctx: &MaildirContextSync, folder: &str) -> Result<Self> {
+        let ctx = ctx.lock().await;
+        let mdir = ctx.get_maildir_from_folder_name(folder)?;
+
+        let (tx, events) = unbounded();
+        let mut watcher = RecommendedWatcher::new(
+            move |evt| {
+                let _ = tx.unbounded_send(evt);
+            },
+            Default::default(),
+        )
+        .context("cannot create maildir watcher")?;
+
+        // envelopes only live in new and cur, and subfolders of
+        // Maildir++ folders are other folders
+        for dir in ["new", "cur"] {
+            watcher
+                .watch(&mdir.path().join(dir), RecursiveMode::NonRecursive)
+                .with_context(|| format!("cannot watch maildir folder {folder} ({dir})"))?;
+        }
+
+        Ok(Self {
+            _watcher: watcher,
+            events,
+        })
+    }
+
+    /// Wait for changes in the watched Maildir folder.
+    ///
+    /// Events received at once are coalesced, since a single change
+    /// usually triggers several file system events. The wait only
+    /// ends on changes, hence it always returns `true`.
+    pub async fn wait(&mut self, folder: &str) -> Result<bool> {
+        info!("waiting for changes in maildir folder {folder}");
+
+        let evt = self
+            .events
+            .next()
+            .await
+            .context("cannot receive maildir event: watcher stopped")?;
+        trace!("received filesystem change event: {evt:?}");
+
+        while let Ok(Some(evt)) = self.events.try_next() {
+            trace!("received filesystem change event: {evt:?}");
+        }
+
+        Ok(true)
+    }
+}
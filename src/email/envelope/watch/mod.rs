@@ -0,0 +1,480 @@
+//! Module dedicated to envelope watch events.
+//!
+//! Watching a folder consists of taking snapshots of its envelopes
+//! each time the backend notifies a change (IMAP IDLE, Maildir file
+//! system events). Events are deduced from the difference between
+//! two consecutive snapshots.
+//!
+//! When a watcher fails, for example because the connection dropped,
+//! it reconnects following the reconnection policy of the account
+//! (envelope.watch.reconnect). The last snapshot taken before the
+//! failure is compared to the first snapshot taken after the
+//! reconnection, so that changes are neither missed nor duplicated.
+//! Only backend errors lead to a reconnection: errors occurring while
+//! handling events, like a closed output, stop the watcher.
+
+#[cfg(feature = "imap")]
+pub mod imap;
+#[cfg(any(feature = "maildir", feature = "account-sync"))]
+pub mod maildir;
+
+use anyhow::Result;
+use email::flag::Flag;
+#[cfg(feature = "imap")]
+use email::imap::ImapContextSync;
+#[cfg(any(feature = "maildir", feature = "account-sync"))]
+use email::maildir::MaildirContextSync;
+use log::{info, warn};
+use serde::Serialize;
+use std::{cmp::Ordering, collections::HashMap, fmt, future::Future, thread};
+use tokio::runtime::Handle;
+
+use crate::{
+    backend::{Backend, BackendKind},
+    printer::{Print, WriteColor},
+};
+
+use super::{config::WatchReconnectConfig, raw::RawEnvelope, Envelope};
+
+/// The kind of change that occurred on an envelope.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WatchEventKind {
+    Received,
+    Seen,
+    Unseen,
+    Flagged,
+    Unflagged,
+    Deleted,
+    Moved,
+}
+
+impl fmt::Display for WatchEventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Received => write!(f, "received"),
+            Self::Seen => write!(f, "seen"),
+            Self::Unseen => write!(f, "unseen"),
+            Self::Flagged => write!(f, "flagged"),
+            Self::Unflagged => write!(f, "unflagged"),
+            Self::Deleted => write!(f, "deleted"),
+            Self::Moved => write!(f, "moved"),
+        }
+    }
+}
+
+/// Represents a change that occurred on an envelope of a watched
+/// folder.
+#[derive(Clone, Debug, Serialize)]
+pub struct WatchEvent {
+    pub event: WatchEventKind,
+    pub account: String,
+    pub folder: String,
+    pub envelope: Envelope,
+}
+
+impl Print for WatchEvent {
+    fn print(&self, writer: &mut dyn WriteColor) -> Result<()> {
+        let from = &self.envelope.from;
+        let from = from.name.as_deref().unwrap_or(&from.addr);
+
+        writeln!(
+            writer,
+            "{} {}/{} {}: {} ({from})",
+            self.event, self.account, self.folder, self.envelope.id, self.envelope.subject,
+        )?;
+
+        Ok(writer.reset()?)
+    }
+}
+
+/// The backend-specific part of a folder watch.
+pub enum EnvelopesWatcher {
+    #[cfg(feature = "imap")]
+    Imap(ImapContextSync, imap::ImapWatcher),
+    #[cfg(any(feature = "maildir", feature = "account-sync"))]
+    Maildir(MaildirContextSync, maildir::MaildirWatcher),
+}
+
+impl EnvelopesWatcher {
+    /// Take a snapshot of the envelopes of the given folder.
+    ///
+    /// The given previous snapshot of the folder, if any, spares
+    /// fetching again what cannot change (IMAP headers).
+    pub async fn snapshot(&self, folder: &str, prev: Option<&Snapshot>) -> Result<Snapshot> {
+        let envelopes = match self {
+            #[cfg(feature = "imap")]
+            Self::Imap(ctx, _) => imap::snapshot_imap_envelopes(ctx, folder, prev).await?,
+            #[cfg(any(feature = "maildir", feature = "account-sync"))]
+            Self::Maildir(ctx, _) => maildir::snapshot_maildir_envelopes(ctx, folder).await?,
+        };
+
+        Ok(snapshot(envelopes))
+    }
+
+    /// Wait for changes in the given folder.
+    ///
+    /// Return `false` when the wait ended without any change, which
+    /// happens when the IMAP watch timeout is reached.
+    pub async fn wait(&mut self, folder: &str) -> Result<bool> {
+        match self {
+            #[cfg(feature = "imap")]
+            Self::Imap(ctx, watcher) => watcher.wait(ctx, folder).await,
+            #[cfg(any(feature = "maildir", feature = "account-sync"))]
+            Self::Maildir(_, watcher) => watcher.wait(folder).await,
+        }
+    }
+}
+
+/// Return `true` if the given backend can be watched for envelopes
+/// events.
+pub fn has_envelopes_events(kind: Option<&BackendKind>) -> bool {
+    match kind {
+        #[cfg(feature = "imap")]
+        Some(BackendKind::Imap) => true,
+        #[cfg(feature = "maildir")]
+        Some(BackendKind::Maildir) => true,
+        #[cfg(feature = "account-sync")]
+        Some(BackendKind::MaildirForSync) => true,
+        _ => false,
+    }
+}
+
+/// Run the given watch on its own thread.
+///
+/// Waiting for changes can block the thread it runs on (IMAP IDLE is
+/// synchronous), so each watch gets its own thread in order not to
+/// starve the other watches. The future is driven by the handle of
+/// the current runtime.
+pub fn spawn_watch<F>(watch: impl FnOnce() -> F + Send + 'static) -> thread::JoinHandle<F::Output>
+where
+    F: Future,
+    F::Output: Send + 'static,
+{
+    let runtime = Handle::current();
+    thread::spawn(move || runtime.block_on(watch()))
+}
+
+/// Watch the given folder for envelopes changes, calling the given
+/// function for every change.
+///
+/// The backend is built by the given connect function, which is
+/// called again in order to reconnect when the backend fails. Failing
+/// to connect the first time is not retried.
+pub async fn watch_folder_envelopes<F>(
+    account: &str,
+    folder: &str,
+    policy: &WatchReconnectConfig,
+    connect: impl Fn() -> F,
+    mut on_event: impl FnMut(WatchEvent) -> Result<()>,
+) -> Result<()>
+where
+    F: Future<Output = Result<Backend>>,
+{
+    let mut envelopes = None;
+    let mut attempt = 0;
+
+    loop {
+        let err = match watch(
+            folder,
+            &connect,
+            &mut envelopes,
+            &mut attempt,
+            &mut on_event,
+        )
+        .await
+        {
+            Ok(()) => return Ok(()),
+            Err(WatchError::Event(err)) => return Err(err),
+            // the watch never started, so the error is unlikely to
+            // be a connection issue
+            Err(WatchError::Backend(err)) if envelopes.is_none() => return Err(err),
+            Err(WatchError::Backend(err)) => err,
+        };
+
+        reconnect(account, folder, policy, &mut attempt, err).await?;
+    }
+}
+
+/// Watch the given folder for changes, calling the given function
+/// every time the folder changed.
+///
+/// Unlike [`watch_folder_envelopes`], changes are not detailed, which
+/// spares taking snapshots of the folder. Since changes may have been
+/// missed while disconnected, the given function is also called
+/// after every reconnection.
+pub async fn watch_folder_changes<F>(
+    account: &str,
+    folder: &str,
+    policy: &WatchReconnectConfig,
+    connect: impl Fn() -> F,
+    mut on_change: impl FnMut() -> Result<()>,
+) -> Result<()>
+where
+    F: Future<Output = Result<Backend>>,
+{
+    let mut started = false;
+    let mut attempt = 0;
+
+    loop {
+        let err = match watch_changes(folder, &connect, &mut started, &mut attempt, &mut on_change)
+            .await
+        {
+            Ok(()) => return Ok(()),
+            Err(WatchError::Event(err)) => return Err(err),
+            // the watch never started, so the error is unlikely to
+            // be a connection issue
+            Err(WatchError::Backend(err)) if !started => return Err(err),
+            Err(WatchError::Backend(err)) => err,
+        };
+
+        reconnect(account, folder, policy, &mut attempt, err).await?;
+    }
+}
+
+/// Wait before reconnecting a failed watch, following the given
+/// reconnection policy.
+///
+/// The given error is returned when the policy gives up.
+async fn reconnect(
+    account: &str,
+    folder: &str,
+    policy: &WatchReconnectConfig,
+    attempt: &mut u32,
+    err: anyhow::Error,
+) -> Result<()> {
+    *attempt += 1;
+
+    let Some(delay) = policy.find_delay(*attempt) else {
+        warn!("account={account} folder={folder} status=failed attempts={attempt}");
+        return Err(err);
+    };
+
+    warn!(
+        "account={account} folder={folder} status=disconnected attempt={attempt} delay={}s error={err:#}",
+        delay.as_secs(),
+    );
+
+    tokio::time::sleep(delay).await;
+    info!("account={account} folder={folder} status=reconnecting attempt={attempt}");
+
+    Ok(())
+}
+
+/// The error of a watch, telling whether it can be retried.
+enum WatchError {
+    /// The backend failed, for example because the connection
+    /// dropped: the watch can be retried.
+    Backend(anyhow::Error),
+    /// The events could not be handled: the watch cannot be retried.
+    Event(anyhow::Error),
+}
+
+/// Connect then watch the given folder until an error occurs.
+///
+/// The given envelopes are the last snapshot taken by a previous
+/// watch, if any. They are compared to the first snapshot in order
+/// to resync changes that occurred while disconnected.
+async fn watch<F>(
+    folder: &str,
+    connect: &impl Fn() -> F,
+    envelopes: &mut Option<Snapshot>,
+    attempt: &mut u32,
+    on_event: &mut impl FnMut(WatchEvent) -> Result<()>,
+) -> Result<(), WatchError>
+where
+    F: Future<Output = Result<Backend>>,
+{
+    let backend = connect().await.map_err(WatchError::Backend)?;
+    let account = &backend.account_config.name;
+    let mut watcher = backend
+        .build_envelopes_watcher(folder)
+        .await
+        .map_err(WatchError::Backend)?;
+    let mut connected = false;
+
+    loop {
+        // a snapshot is taken even when the wait timed out, since
+        // flags changes occurring between two waits are not notified
+        if connected {
+            watcher.wait(folder).await.map_err(WatchError::Backend)?;
+        }
+
+        let next_envelopes = watcher
+            .snapshot(folder, envelopes.as_ref())
+            .await
+            .map_err(WatchError::Backend)?;
+
+        // the snapshot is saved before processing changes, so that
+        // they are not processed twice
+        let prev_envelopes = envelopes.replace(next_envelopes);
+
+        if let (Some(prev_envelopes), Some(next_envelopes)) = (&prev_envelopes, &*envelopes) {
+            backend
+                .process_envelopes_changes(folder, prev_envelopes, next_envelopes, on_event)
+                .await
+                .map_err(WatchError::Event)?;
+        }
+
+        if !connected {
+            info!("account={account} folder={folder} status=connected attempt={attempt}");
+            *attempt = 0;
+            connected = true;
+        }
+    }
+}
+
+/// Connect then watch the given folder for changes until an error
+/// occurs.
+async fn watch_changes<F>(
+    folder: &str,
+    connect: &impl Fn() -> F,
+    started: &mut bool,
+    attempt: &mut u32,
+    on_change: &mut impl FnMut() -> Result<()>,
+) -> Result<(), WatchError>
+where
+    F: Future<Output = Result<Backend>>,
+{
+    let backend = connect().await.map_err(WatchError::Backend)?;
+    let account = &backend.account_config.name;
+    let mut watcher = backend
+        .build_envelopes_watcher(folder)
+        .await
+        .map_err(WatchError::Backend)?;
+
+    info!("account={account} folder={folder} status=connected attempt={attempt}");
+    *attempt = 0;
+
+    // changes that occurred while disconnected are unknown
+    if *started {
+        on_change().map_err(WatchError::Event)?;
+    }
+
+    *started = true;
+
+    loop {
+        if watcher.wait(folder).await.map_err(WatchError::Backend)? {
+            on_change().map_err(WatchError::Event)?;
+        }
+    }
+}
+
+/// A snapshot of the envelopes of a folder, indexed by their
+/// backend id.
+pub type Snapshot = HashMap<String, RawEnvelope>;
+
+/// Build a snapshot from the given raw envelopes.
+fn snapshot(envelopes: Vec<RawEnvelope>) -> Snapshot {
+    envelopes
+        .into_iter()
+        .map(|envelope| (envelope.id.clone(), envelope))
+        .collect()
+}
+
+/// Deduce events from the difference between the given snapshots.
+///
+/// Envelopes that disappeared are considered deleted when they were
+/// flagged as deleted, otherwise they are considered moved (which is
+/// also the case of envelopes deleted to the trash folder).
+pub fn diff_snapshots<'a>(
+    prev: &'a Snapshot,
+    next: &'a Snapshot,
+) -> Vec<(WatchEventKind, &'a RawEnvelope)> {
+    let mut events = Vec::new();
+
+    for (id, envelope) in next {
+        let Some(prev_envelope) = prev.get(id) else {
+            events.push((WatchEventKind::Received, envelope));
+            continue;
+        };
+
+        for (flag, added, removed) in [
+            (Flag::Seen, WatchEventKind::Seen, WatchEventKind::Unseen),
+            (
+                Flag::Flagged,
+                WatchEventKind::Flagged,
+                WatchEventKind::Unflagged,
+            ),
+        ] {
+            match (
+                prev_envelope.flags.contains(&flag),
+                envelope.flags.contains(&flag),
+            ) {
+                (false, true) => events.push((added, envelope)),
+                (true, false) => events.push((removed, envelope)),
+                _ => (),
+            }
+        }
+    }
+
+    for (id, envelope) in prev {
+        if !next.contains_key(id) {
+            if envelope.flags.contains(&Flag::Deleted) {
+                events.push((WatchEventKind::Deleted, envelope));
+            } else {
+                events.push((WatchEventKind::Moved, envelope));
+            }
+        }
+    }
+
+    events.sort_by(|(_, a), (_, b)| compare_ids(&a.id, &b.id));
+    events
+}
+
+/// Compare the given envelope ids, numerically if possible.
+fn compare_ids(a: &str, b: &str) -> Ordering {
+    match (a.parse::<u64>(), b.parse::<u64>()) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        _ => a.cmp(b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use email::{envelope::Flags, flag::Flag};
+
+    use super::{diff_snapshots, snapshot, RawEnvelope, WatchEventKind};
+
+    fn raw(id: &str, flags: &[Flag]) -> RawEnvelope {
+        RawEnvelope {
+            id: id.into(),
+            flags: Flags::from_iter(flags.iter().cloned()),
+            headers: Vec::new(),
+            size: 0,
+        }
+    }
+
+    #[test]
+    fn diff() {
+        let prev = snapshot(vec![
+            raw("1", &[]),
+            raw("2", &[Flag::Seen]),
+            raw("3", &[Flag::Deleted]),
+            raw("10", &[]),
+        ]);
+
+        let next = snapshot(vec![
+            raw("1", &[Flag::Seen, Flag::Flagged]),
+            raw("2", &[]),
+            raw("4", &[]),
+        ]);
+
+        let events: Vec<_> = diff_snapshots(&prev, &next)
+            .into_iter()
+            .map(|(kind, envelope)| (kind, envelope.id.as_str()))
+            .collect();
+
+        assert_eq!(
+            events,
+            vec![
+                (WatchEventKind::Seen, "1"),
+                (WatchEventKind::Flagged, "1"),
+                (WatchEventKind::Unseen, "2"),
+                (WatchEventKind::Deleted, "3"),
+                (WatchEventKind::Received, "4"),
+                (WatchEventKind::Moved, "10"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,51 @@
+//! Module dedicated to adding IMAP messages with their original date.
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use email::{envelope::SingleId, flag::Flags, imap::ImapContextSync};
+use imap_proto::UidSetMember;
+use log::{debug, info};
+use utf7_imap::encode_utf7_imap as encode_utf7;
+
+/// Add the given raw message to the given IMAP folder, using the
+/// given date as internal date.
+///
+/// Like the default addition, the UID of the added message can only
+/// be returned when the server supports the UIDPLUS extension.
+pub async fn add_imap_message_with_date(
+    ctx: &ImapContextSync,
+    folder: &str,
+    msg: &[u8],
+    flags: &Flags,
+    date: DateTime<Utc>,
+) -> Result<SingleId> {
+    info!("adding imap message to folder {folder} with flags {flags} and date {date}");
+
+    let mut ctx = ctx.lock().await;
+
+    let folder = ctx.account_config.get_folder_alias(folder);
+    let folder_encoded = encode_utf7(folder.clone());
+    debug!("utf7 encoded folder: {folder_encoded}");
+
+    let appended = ctx
+        .exec(
+            |session| {
+                session
+                    .append(&folder_encoded, msg)
+                    .flags(flags.to_imap_flags_vec())
+                    .internal_date(date.fixed_offset())
+                    .finish()
+            },
+            |err| anyhow!(err).context(format!("cannot add imap message to folder {folder}")),
+        )
+        .await?;
+
+    let uid = match appended.uids.as_deref() {
+        Some([UidSetMember::Uid(uid)]) => *uid,
+        Some([UidSetMember::UidRange(uids)]) => *uids.start(),
+        _ => bail!("cannot get added imap message uid: extension UIDPLUS may be missing"),
+    };
+    debug!("added imap message uid: {uid}");
+
+    Ok(SingleId::from(uid.to_string()))
+}
@@ -0,0 +1,40 @@
+//! Module dedicated to adding Maildir messages with their original
+//! date.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use email::{envelope::SingleId, flag::Flags, maildir::MaildirContextSync};
+use log::info;
+use std::{fs::File, time::SystemTime};
+
+/// Add the given raw message to the given Maildir folder, using the
+/// given date as modification date of the message file.
+pub async fn add_maildir_message_with_date(
+    ctx: &MaildirContextSync,
+    folder: &str,
+    msg: &[u8],
+    flags: &Flags,
+    date: DateTime<Utc>,
+) -> Result<SingleId> {
+    info!("adding maildir message to folder {folder} with flags {flags} and date {date}");
+
+    let ctx = ctx.lock().await;
+    let mdir = ctx.get_maildir_from_folder_name(folder)?;
+
+    let id = mdir
+        .store_cur_with_flags(msg, &flags.to_mdir_string())
+        .with_context(|| format!("cannot add maildir message to folder {folder}"))?;
+
+    let entry = mdir
+        .find(&id)
+        .ok_or_else(|| anyhow!("cannot find added maildir message {id}"))?;
+    let path = entry.path();
+
+    File::options()
+        .write(true)
+        .open(path)
+        .and_then(|file| file.set_modified(SystemTime::from(date)))
+        .with_context(|| format!("cannot set date of maildir message at {path:?}"))?;
+
+    Ok(SingleId::from(id))
+}
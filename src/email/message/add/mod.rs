@@ -0,0 +1,11 @@
+//! Module dedicated to adding messages with their original date.
+//!
+//! The date is the one of the folder entry (the IMAP INTERNALDATE or
+//! the Maildir file modification date), which is usually the date
+//! the message was received. Backends set it to the date of the
+//! addition by default.
+
+#[cfg(feature = "imap")]
+pub mod imap;
+#[cfg(any(feature = "maildir", feature = "account-sync"))]
+pub mod maildir;
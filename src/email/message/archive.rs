@@ -0,0 +1,804 @@
+//! Module dedicated to archives of messages.
+//!
+//! Messages can be exported to and imported from an mbox file (using
+//! the mboxrd quoting), a directory of `.eml` files or a Maildir.
+//! Flags are kept as Status and X-Status headers in mbox files, and
+//! as filename suffixes in Maildirs.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local, NaiveDateTime, Utc};
+use clap::{Parser, ValueEnum};
+use email::flag::{Flag as BackendFlag, Flags as BackendFlags};
+use indicatif::ProgressBar;
+use mail_parser::MessageParser;
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    vec,
+};
+use uuid::Uuid;
+
+use crate::{
+    backend::Backend,
+    envelope::{arg::ids::BATCH_SIZE, mailboxes},
+    flag::{Flag, Flags},
+};
+
+/// Represents the format of an archive of messages.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum ArchiveFormat {
+    /// A single mbox file, using the mboxrd quoting.
+    #[default]
+    Mbox,
+
+    /// A directory containing one .eml file per message.
+    Eml,
+
+    /// A Maildir, flags are kept in filenames.
+    Maildir,
+}
+
+/// The export arguments parser.
+#[derive(Debug, Parser)]
+pub struct ExportArgs {
+    /// The format of the export.
+    #[arg(long, short = 'F', value_enum, default_value_t = Default::default())]
+    pub format: ArchiveFormat,
+
+    /// The path of the export.
+    ///
+    /// The path points to a file for the mbox format (messages are
+    /// appended to it if it already exists), and to a directory for
+    /// the eml and maildir formats (created if it does not exist).
+    #[arg(long = "to", short = 't', value_name = "PATH")]
+    pub path: PathBuf,
+}
+
+impl ArchiveFormat {
+    /// Guess the format of the archive at the given path: a
+    /// directory containing a cur or a new directory is a Maildir,
+    /// other directories are eml archives, files starting with a
+    /// From_ line are mbox files and other files are eml files.
+    pub fn detect(path: &Path) -> Self {
+        if path.is_dir() {
+            if path.join("cur").is_dir() || path.join("new").is_dir() {
+                Self::Maildir
+            } else {
+                Self::Eml
+            }
+        } else if starts_with_from_line(path) {
+            Self::Mbox
+        } else {
+            Self::Eml
+        }
+    }
+}
+
+/// Write messages to an archive.
+pub struct ArchiveWriter {
+    format: ArchiveFormat,
+    path: PathBuf,
+    mbox: Option<BufWriter<File>>,
+    /// The number of the next .eml file, for the eml format.
+    next_eml: usize,
+}
+
+impl ArchiveWriter {
+    /// Open the archive at the given path, creating it if needed.
+    pub fn open(format: ArchiveFormat, path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let mut mbox = None;
+        let mut next_eml = 1;
+
+        match format {
+            ArchiveFormat::Mbox => {
+                if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                    fs::create_dir_all(dir)
+                        .with_context(|| format!("cannot create directory at {dir:?}"))?;
+                }
+
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&path)
+                    .with_context(|| format!("cannot open mbox at {path:?}"))?;
+                mbox = Some(BufWriter::new(file));
+            }
+            ArchiveFormat::Eml => {
+                fs::create_dir_all(&path)
+                    .with_context(|| format!("cannot create directory at {path:?}"))?;
+                next_eml += fs::read_dir(&path).map(|dir| dir.count()).unwrap_or(0);
+            }
+            ArchiveFormat::Maildir => {
+                for dir in ["cur", "new", "tmp"] {
+                    let dir = path.join(dir);
+                    fs::create_dir_all(&dir)
+                        .with_context(|| format!("cannot create Maildir directory at {dir:?}"))?;
+                }
+            }
+        }
+
+        Ok(Self {
+            format,
+            path,
+            mbox,
+            next_eml,
+        })
+    }
+
+    /// Write the given raw message with its flags to the archive.
+    pub fn write(&mut self, raw: &[u8], flags: &Flags) -> Result<()> {
+        match self.format {
+            ArchiveFormat::Mbox => {
+                let mbox = self.mbox.as_mut().unwrap();
+                mbox.write_all(&to_mbox_entry(raw, flags))
+                    .with_context(|| format!("cannot write message to mbox at {:?}", self.path))
+            }
+            ArchiveFormat::Eml => {
+                let path = self.next_eml_path();
+                fs::write(&path, raw).with_context(|| format!("cannot write message at {path:?}"))
+            }
+            ArchiveFormat::Maildir => {
+                let name = format!(
+                    "{}.{}.himalaya:2,{}",
+                    Utc::now().timestamp(),
+                    Uuid::new_v4().to_simple(),
+                    to_maildir_flags(flags)
+                );
+                let tmp = self.path.join("tmp").join(&name);
+                let cur = self.path.join("cur").join(&name);
+                fs::write(&tmp, raw).with_context(|| format!("cannot write message at {tmp:?}"))?;
+                fs::rename(&tmp, &cur)
+                    .with_context(|| format!("cannot move message from {tmp:?} to {cur:?}"))
+            }
+        }
+    }
+
+    /// Find the next available .eml file path of the archive.
+    fn next_eml_path(&mut self) -> PathBuf {
+        loop {
+            let path = self.path.join(format!("{:06}.eml", self.next_eml));
+            self.next_eml += 1;
+            if !path.exists() {
+                return path;
+            }
+        }
+    }
+
+    /// Flush the archive.
+    pub fn finish(mut self) -> Result<()> {
+        if let Some(mbox) = self.mbox.as_mut() {
+            mbox.flush()
+                .with_context(|| format!("cannot flush mbox at {:?}", self.path))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Export the messages matching the given ids of the given folder to
+/// the given archive, in batches. Returns the amount of exported
+/// messages.
+pub async fn export_messages(
+    backend: &Backend,
+    folder: &str,
+    ids: &[usize],
+    writer: &mut ArchiveWriter,
+    progress: &ProgressBar,
+) -> Result<usize> {
+    let mut count = 0;
+
+    for ids in ids.chunks(BATCH_SIZE) {
+        // flags are taken from envelopes, since they are not part of
+        // raw messages
+        for (id, flags, raw) in backend.peek_messages_with_flags(folder, ids).await? {
+            progress.set_message(format!("Exporting message {id} from {folder}…"));
+            writer.write(&raw, &flags.into())?;
+            progress.inc(1);
+            count += 1;
+        }
+    }
+
+    Ok(count)
+}
+
+/// Build the mbox entry of the given raw message: the From_ line,
+/// the headers (Status and X-Status headers are replaced by the ones
+/// matching the given flags), then the mboxrd-quoted body.
+fn to_mbox_entry(raw: &[u8], flags: &Flags) -> Vec<u8> {
+    let (sender, date) = match MessageParser::new().parse_headers(raw) {
+        Some(msg) => {
+            let sender = mailboxes(msg.from())
+                .into_iter()
+                .next()
+                .map(|mailbox| mailbox.addr)
+                .filter(|addr| !addr.is_empty());
+            let date = msg
+                .date()
+                .and_then(|date| DateTime::from_timestamp(date.to_timestamp(), 0));
+            (sender, date)
+        }
+        None => (None, None),
+    };
+
+    let sender = sender.unwrap_or_else(|| String::from("MAILER-DAEMON"));
+    let date = date.unwrap_or_else(Utc::now).format("%a %b %e %H:%M:%S %Y");
+
+    let mut entry = format!("From {sender} {date}\n").into_bytes();
+
+    // messages are written byte by byte, since they may not be
+    // valid UTF-8, with LF line endings
+    let mut lines = raw
+        .split(|b| *b == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line));
+    let mut skip_continuation = false;
+
+    for line in lines.by_ref() {
+        if line.is_empty() {
+            break;
+        }
+
+        let is_continuation = line.starts_with(b" ") || line.starts_with(b"\t");
+        if is_continuation && skip_continuation {
+            continue;
+        }
+
+        let name = line.split(|b| *b == b':').next().unwrap_or_default();
+        skip_continuation = !is_continuation
+            && (name.eq_ignore_ascii_case(b"status") || name.eq_ignore_ascii_case(b"x-status"));
+
+        if !skip_continuation {
+            entry.extend_from_slice(line);
+            entry.push(b'\n');
+        }
+    }
+
+    let (status, x_status) = to_mbox_status(flags);
+    entry.extend_from_slice(format!("Status: {status}\n").as_bytes());
+    if !x_status.is_empty() {
+        entry.extend_from_slice(format!("X-Status: {x_status}\n").as_bytes());
+    }
+    entry.push(b'\n');
+
+    let mut body: Vec<&[u8]> = lines.collect();
+    // the last line ending does not start a new line
+    if body.last().is_some_and(|line| line.is_empty()) {
+        body.pop();
+    }
+
+    for line in body {
+        let unquoted = line.iter().position(|b| *b != b'>').map(|i| &line[i..]);
+        if unquoted.is_some_and(|line| line.starts_with(b"From ")) {
+            entry.push(b'>');
+        }
+        entry.extend_from_slice(line);
+        entry.push(b'\n');
+    }
+
+    entry.push(b'\n');
+    entry
+}
+
+/// Build the Status and X-Status header values of the given flags,
+/// following the mutt conventions.
+fn to_mbox_status(flags: &Flags) -> (String, String) {
+    let mut status = String::new();
+    if flags.contains(&Flag::Seen) {
+        status.push('R');
+    }
+    status.push('O');
+
+    let mut x_status = String::new();
+    for (flag, c) in [
+        (Flag::Answered, 'A'),
+        (Flag::Flagged, 'F'),
+        (Flag::Draft, 'T'),
+        (Flag::Deleted, 'D'),
+    ] {
+        if flags.contains(&flag) {
+            x_status.push(c);
+        }
+    }
+
+    (status, x_status)
+}
+
+/// Build the Maildir info suffix of the given flags, in ASCII order.
+fn to_maildir_flags(flags: &Flags) -> String {
+    let mut info = String::new();
+    for (flag, c) in [
+        (Flag::Draft, 'D'),
+        (Flag::Flagged, 'F'),
+        (Flag::Answered, 'R'),
+        (Flag::Seen, 'S'),
+        (Flag::Deleted, 'T'),
+    ] {
+        if flags.contains(&flag) {
+            info.push(c);
+        }
+    }
+    info
+}
+
+fn starts_with_from_line(path: &Path) -> bool {
+    let mut start = [0; 5];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut start))
+        .is_ok_and(|_| &start == b"From ")
+}
+
+/// Represents a message read from an archive.
+#[derive(Clone, Debug)]
+pub struct ArchivedMessage {
+    /// The raw message, with CRLF line endings.
+    pub raw: Vec<u8>,
+    pub flags: BackendFlags,
+    /// The date the message was archived at: the date of the mbox
+    /// From_ line or the file modification date.
+    pub date: Option<DateTime<Utc>>,
+    /// The size of the message in the archive, in bytes.
+    pub size: u64,
+}
+
+/// Read messages from an archive.
+pub struct ArchiveReader {
+    size: u64,
+    source: ArchiveSource,
+}
+
+enum ArchiveSource {
+    Mbox(MboxReader<BufReader<File>>),
+    /// Files containing one message each, alongside with the flags
+    /// found in their name.
+    Files(vec::IntoIter<(PathBuf, BackendFlags)>),
+}
+
+impl ArchiveReader {
+    /// Open the archive at the given path.
+    pub fn open(format: ArchiveFormat, path: &Path) -> Result<Self> {
+        match format {
+            ArchiveFormat::Mbox => {
+                let file =
+                    File::open(path).with_context(|| format!("cannot open mbox at {path:?}"))?;
+                let size = file.metadata().map(|m| m.len()).unwrap_or_default();
+                let source = ArchiveSource::Mbox(MboxReader::new(BufReader::new(file)));
+                Ok(Self { size, source })
+            }
+            ArchiveFormat::Eml if path.is_file() => {
+                Self::from_files(vec![(path.to_owned(), BackendFlags::default())])
+            }
+            ArchiveFormat::Eml => {
+                let mut files = read_files(path)?;
+                files.sort();
+                let files = files
+                    .into_iter()
+                    .map(|path| (path, BackendFlags::default()))
+                    .collect();
+                Self::from_files(files)
+            }
+            ArchiveFormat::Maildir => {
+                let mut files = Vec::new();
+
+                for dir in ["cur", "new"] {
+                    let dir = path.join(dir);
+                    if !dir.is_dir() {
+                        continue;
+                    }
+
+                    let mut paths = read_files(&dir)?;
+                    paths.sort();
+                    files.extend(paths.into_iter().map(|path| {
+                        let flags = from_maildir_flags(&path);
+                        (path, flags)
+                    }));
+                }
+
+                if files.is_empty() && !path.join("cur").is_dir() && !path.join("new").is_dir() {
+                    bail!("cannot find Maildir at {path:?}");
+                }
+
+                Self::from_files(files)
+            }
+        }
+    }
+
+    fn from_files(files: Vec<(PathBuf, BackendFlags)>) -> Result<Self> {
+        let size = files
+            .iter()
+            .filter_map(|(path, _)| fs::metadata(path).ok())
+            .map(|m| m.len())
+            .sum();
+        let source = ArchiveSource::Files(files.into_iter());
+        Ok(Self { size, source })
+    }
+
+    /// Return the size of the archive in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+impl Iterator for ArchiveReader {
+    type Item = Result<ArchivedMessage>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.source {
+            ArchiveSource::Mbox(reader) => reader.next(),
+            ArchiveSource::Files(files) => {
+                let (path, flags) = files.next()?;
+                Some(read_message_file(&path, flags))
+            }
+        }
+    }
+}
+
+fn read_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = fs::read_dir(dir).with_context(|| format!("cannot read directory at {dir:?}"))?;
+
+    let mut files = Vec::new();
+    for entry in entries {
+        let path = entry
+            .with_context(|| format!("cannot read directory entry at {dir:?}"))?
+            .path();
+        let is_hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if path.is_file() && !is_hidden {
+            files.push(path);
+        }
+    }
+
+    Ok(files)
+}
+
+fn read_message_file(path: &Path, flags: BackendFlags) -> Result<ArchivedMessage> {
+    let raw = fs::read(path).with_context(|| format!("cannot read message at {path:?}"))?;
+    let date = fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .map(DateTime::<Utc>::from);
+
+    let size = raw.len() as u64;
+    let raw = raw.strip_suffix(b"\n").unwrap_or(&raw);
+    let lines = raw
+        .split(|b| *b == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line).to_vec())
+        .collect();
+    let (raw, mut headers_flags) = to_imported_message(lines, date);
+    headers_flags.extend(flags.iter().cloned());
+
+    Ok(ArchivedMessage {
+        raw,
+        flags: headers_flags,
+        date,
+        size,
+    })
+}
+
+/// Read messages from an mbox, one at a time.
+struct MboxReader<R: BufRead> {
+    reader: R,
+    /// The From_ line of the next message.
+    from_line: Option<Vec<u8>>,
+    prev_line_blank: bool,
+}
+
+impl<R: BufRead> MboxReader<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            reader,
+            from_line: None,
+            prev_line_blank: true,
+        }
+    }
+
+    fn read_line(&mut self) -> Result<Option<Vec<u8>>> {
+        let mut line = Vec::new();
+        let n = self
+            .reader
+            .read_until(b'\n', &mut line)
+            .context("cannot read mbox line")?;
+
+        if n == 0 {
+            return Ok(None);
+        }
+
+        Ok(Some(line))
+    }
+
+    fn read_message(&mut self) -> Result<Option<ArchivedMessage>> {
+        // skip anything before the first From_ line
+        while self.from_line.is_none() {
+            match self.read_line()? {
+                Some(line) if line.starts_with(b"From ") && self.prev_line_blank => {
+                    self.from_line = Some(line)
+                }
+                Some(line) => self.prev_line_blank = is_blank(&line),
+                None => return Ok(None),
+            }
+        }
+
+        let from_line = self.from_line.take().unwrap();
+        let mut size = from_line.len() as u64;
+        let mut lines = Vec::new();
+        self.prev_line_blank = false;
+
+        while let Some(line) = self.read_line()? {
+            // a From_ line preceded by a blank line starts the next
+            // message, body lines starting with From are quoted
+            if line.starts_with(b"From ") && self.prev_line_blank {
+                self.from_line = Some(line);
+                break;
+            }
+
+            size += line.len() as u64;
+            self.prev_line_blank = is_blank(&line);
+
+            let line = line.strip_suffix(b"\n").unwrap_or(&line);
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            lines.push(unquote_mbox_line(line).to_vec());
+        }
+
+        // the blank line preceding the next From_ line belongs to
+        // the separator
+        if lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+
+        let date = parse_from_line_date(&from_line);
+        let (raw, flags) = to_imported_message(lines, date);
+        Ok(Some(ArchivedMessage {
+            raw,
+            flags,
+            date,
+            size,
+        }))
+    }
+}
+
+impl<R: BufRead> Iterator for MboxReader<R> {
+    type Item = Result<ArchivedMessage>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_message().transpose()
+    }
+}
+
+fn is_blank(line: &[u8]) -> bool {
+    line.iter().all(|b| b.is_ascii_whitespace())
+}
+
+/// Remove one level of mboxrd quoting from the given line.
+fn unquote_mbox_line(line: &[u8]) -> &[u8] {
+    let unquoted = line.iter().position(|b| *b != b'>').map(|i| &line[i..]);
+
+    match unquoted {
+        Some(unquoted) if unquoted.starts_with(b"From ") && line.starts_with(b">") => &line[1..],
+        _ => line,
+    }
+}
+
+/// Parse the date of the given mbox From_ line, in the asctime
+/// format.
+fn parse_from_line_date(from_line: &[u8]) -> Option<DateTime<Utc>> {
+    let line = String::from_utf8_lossy(from_line);
+    let mut words = line.split_whitespace().skip(2);
+    let date = words.by_ref().take(5).collect::<Vec<_>>().join(" ");
+
+    NaiveDateTime::parse_from_str(&date, "%a %b %e %H:%M:%S %Y")
+        .ok()
+        .map(|date| date.and_utc())
+}
+
+/// Build the message to import from the given lines: flags are
+/// extracted from the Status, X-Status and X-Mozilla-Status headers
+/// (which are removed), a Date header is added from the given date
+/// if missing, and lines are joined with CRLF.
+fn to_imported_message(
+    lines: Vec<Vec<u8>>,
+    date: Option<DateTime<Utc>>,
+) -> (Vec<u8>, BackendFlags) {
+    let mut flags = BackendFlags::default();
+    let mut headers: Vec<Vec<u8>> = Vec::new();
+    let mut has_date = false;
+    let mut skip_continuation = false;
+
+    let mut lines = lines.into_iter();
+    let mut body_lines = Vec::new();
+
+    for line in lines.by_ref() {
+        if line.is_empty() {
+            body_lines.push(Vec::new());
+            break;
+        }
+
+        if line.starts_with(b" ") || line.starts_with(b"\t") {
+            if !skip_continuation {
+                headers.push(line);
+            }
+            continue;
+        }
+
+        let (name, value) = match line.iter().position(|b| *b == b':') {
+            Some(i) => (&line[..i], String::from_utf8_lossy(&line[i + 1..])),
+            None => (line.as_slice(), Default::default()),
+        };
+        let value = value.trim();
+
+        skip_continuation = true;
+
+        if name.eq_ignore_ascii_case(b"status") {
+            if value.contains('R') {
+                flags.insert(BackendFlag::Seen);
+            }
+        } else if name.eq_ignore_ascii_case(b"x-status") {
+            for (c, flag) in [
+                ('A', BackendFlag::Answered),
+                ('F', BackendFlag::Flagged),
+                ('T', BackendFlag::Draft),
+                ('D', BackendFlag::Deleted),
+            ] {
+                if value.contains(c) {
+                    flags.insert(flag);
+                }
+            }
+        } else if name.eq_ignore_ascii_case(b"x-mozilla-status") {
+            let status = u32::from_str_radix(value, 16).unwrap_or_default();
+            for (bit, flag) in [
+                (0x0001, BackendFlag::Seen),
+                (0x0002, BackendFlag::Answered),
+                (0x0004, BackendFlag::Flagged),
+                (0x0008, BackendFlag::Deleted),
+            ] {
+                if status & bit != 0 {
+                    flags.insert(flag);
+                }
+            }
+        } else if name.eq_ignore_ascii_case(b"x-mozilla-status2") {
+            // Thunderbird internal flags, not relevant elsewhere
+        } else {
+            has_date |= name.eq_ignore_ascii_case(b"date");
+            skip_continuation = false;
+            headers.push(line);
+        }
+    }
+
+    body_lines.extend(lines);
+
+    let mut raw = Vec::new();
+
+    if let Some(date) = date.filter(|_| !has_date) {
+        let date = date.with_timezone(&Local).to_rfc2822();
+        raw.extend_from_slice(format!("Date: {date}\r\n").as_bytes());
+    }
+
+    for line in headers.iter().chain(&body_lines) {
+        raw.extend_from_slice(line);
+        raw.extend_from_slice(b"\r\n");
+    }
+
+    (raw, flags)
+}
+
+/// Parse the flags of the info suffix of the given Maildir file.
+fn from_maildir_flags(path: &Path) -> BackendFlags {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+
+    let mut flags = BackendFlags::default();
+
+    if let Some((_, info)) = name.rsplit_once(":2,") {
+        for c in info.chars() {
+            let flag = match c {
+                'D' => BackendFlag::Draft,
+                'F' => BackendFlag::Flagged,
+                'R' => BackendFlag::Answered,
+                'S' => BackendFlag::Seen,
+                'T' => BackendFlag::Deleted,
+                _ => continue,
+            };
+            flags.insert(flag);
+        }
+    }
+
+    flags
+}
+
+#[cfg(test)]
+mod tests {
+    use email::flag::Flag as BackendFlag;
+    use std::{collections::HashSet, io::Cursor};
+
+    use crate::flag::{Flag, Flags};
+
+    use super::{to_imported_message, to_maildir_flags, to_mbox_entry, MboxReader};
+
+    #[test]
+    fn mbox_entry() {
+        let raw = concat!(
+            "From: Alice <alice@localhost>\r\n",
+            "Date: Thu, 01 Feb 2024 10:00:00 +0000\r\n",
+            "Status: O\r\n",
+            "Subject: mbox\r\n",
+            "\r\n",
+            "From here\r\n",
+            ">From there\r\n",
+            "Fromage\r\n",
+        );
+        let flags = Flags(HashSet::from_iter([Flag::Seen, Flag::Answered]));
+
+        let expected = concat!(
+            "From alice@localhost Thu Feb  1 10:00:00 2024\n",
+            "From: Alice <alice@localhost>\n",
+            "Date: Thu, 01 Feb 2024 10:00:00 +0000\n",
+            "Subject: mbox\n",
+            "Status: RO\n",
+            "X-Status: A\n",
+            "\n",
+            ">From here\n",
+            ">>From there\n",
+            "Fromage\n",
+            "\n",
+        );
+
+        assert_eq!(
+            String::from_utf8(to_mbox_entry(raw.as_bytes(), &flags)).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn maildir_flags() {
+        let flags = Flags(HashSet::from_iter([Flag::Seen, Flag::Flagged, Flag::Draft]));
+        assert_eq!(to_maildir_flags(&flags), "DFS");
+        assert_eq!(to_maildir_flags(&Flags::default()), "");
+    }
+
+    #[test]
+    fn mbox_round_trip() {
+        let raw = concat!(
+            "From: alice@localhost\r\n",
+            "Date: Thu, 01 Feb 2024 10:00:00 +0000\r\n",
+            "\r\n",
+            "From here\r\n",
+            "\r\n",
+            ">From there\r\n",
+        );
+        let flags = Flags(HashSet::from_iter([Flag::Seen, Flag::Flagged]));
+
+        let mut mbox = to_mbox_entry(raw.as_bytes(), &flags);
+        mbox.extend(to_mbox_entry(raw.as_bytes(), &Flags::default()));
+
+        let messages = MboxReader::new(Cursor::new(mbox))
+            .collect::<anyhow::Result<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(messages.len(), 2);
+        assert_eq!(String::from_utf8_lossy(&messages[0].raw), raw);
+        assert!(messages[0].flags.contains(&BackendFlag::Seen));
+        assert!(messages[0].flags.contains(&BackendFlag::Flagged));
+        assert_eq!(String::from_utf8_lossy(&messages[1].raw), raw);
+        assert!(messages[1].flags.is_empty());
+    }
+
+    #[test]
+    fn imported_message_from_thunderbird() {
+        let lines = ["X-Mozilla-Status: 0005", "Subject: tb", "", "body"]
+            .into_iter()
+            .map(|line| line.as_bytes().to_vec())
+            .collect();
+        let date = chrono::DateTime::from_timestamp(0, 0);
+
+        let (raw, flags) = to_imported_message(lines, date);
+        let raw = String::from_utf8(raw).unwrap();
+
+        assert!(raw.starts_with("Date: "));
+        assert!(raw.ends_with("Subject: tb\r\n\r\nbody\r\n"));
+        assert!(flags.contains(&BackendFlag::Seen));
+        assert!(flags.contains(&BackendFlag::Flagged));
+        assert!(!flags.contains(&BackendFlag::Answered));
+    }
+}
@@ -8,15 +8,20 @@ use uuid::Uuid;
 #[cfg(feature = "account-sync")]
 use crate::cache::arg::disable::CacheDisableFlag;
 use crate::{
-    account::arg::name::AccountNameFlag, backend::Backend, config::TomlConfig,
-    envelope::arg::ids::EnvelopeIdsArgs, folder::arg::name::FolderNameOptionalFlag,
+    account::arg::name::AccountNameFlag,
+    backend::Backend,
+    config::TomlConfig,
+    envelope::arg::ids::{resolve_account, select_ids, EnvelopeIdsArgs},
+    folder::arg::name::FolderNameOptionalFlag,
+    message::part::find_part,
     printer::Printer,
 };
 
 /// Download all attachments for the given message.
 ///
 /// This command allows you to download all attachments found for the
-/// given message to your downloads directory.
+/// given message to your downloads directory. A single MIME part can
+/// be downloaded instead with the --part argument.
 #[derive(Debug, Parser)]
 pub struct AttachmentDownloadCommand {
     #[command(flatten)]
@@ -25,6 +30,13 @@ pub struct AttachmentDownloadCommand {
     #[command(flatten)]
     pub envelopes: EnvelopeIdsArgs,
 
+    /// Download only the given MIME part of the message, decoded.
+    ///
+    /// Part numbers are shown by the message parts command (1,
+    /// 1.2…). Any part can be downloaded, not only attachments.
+    #[arg(long, value_name = "NUMBER")]
+    pub part: Option<String>,
+
     #[cfg(feature = "account-sync")]
     #[command(flatten)]
     pub cache: CacheDisableFlag,
@@ -38,24 +50,39 @@ impl AttachmentDownloadCommand {
         info!("executing download attachment(s) command");
 
         let folder = &self.folder.name;
-        let ids = &self.envelopes.ids;
 
-        let (toml_account_config, account_config) = config.clone().into_account_configs(
+        let ids = self.envelopes.collect()?;
+        let account = resolve_account(
             self.account.name.as_deref(),
+            ids.iter().map(|ids| ids.account.as_deref()),
+        )?;
+        let (toml_account_config, account_config) = config.clone().into_account_configs(
+            account,
             #[cfg(feature = "account-sync")]
             self.cache.disable,
         )?;
 
         let get_messages_kind = toml_account_config.get_messages_kind();
+        let needs_listing = self.envelopes.selector.needs_listing(&ids);
+        let list_envelopes_kind = toml_account_config
+            .list_envelopes_kind()
+            .filter(|_| needs_listing);
 
         let backend = Backend::new(
             toml_account_config.clone(),
             account_config.clone(),
-            get_messages_kind,
-            |builder| builder.set_get_messages(BackendFeatureSource::Context),
+            get_messages_kind.into_iter().chain(list_envelopes_kind),
+            |builder| {
+                builder.set_get_messages(BackendFeatureSource::Context);
+                if needs_listing {
+                    builder.set_list_envelopes(BackendFeatureSource::Context);
+                }
+            },
         )
         .await?;
 
+        let ids = &select_ids(&backend, folder, &ids, &self.envelopes.selector).await?;
+
         let emails = backend.get_messages(folder, ids).await?;
 
         let mut emails_count = 0;
@@ -64,6 +91,26 @@ impl AttachmentDownloadCommand {
         let mut ids = ids.iter();
         for email in emails.to_vec() {
             let id = ids.next().unwrap();
+
+            if let Some(index) = &self.part {
+                let parsed = email
+                    .parsed()
+                    .with_context(|| format!("cannot parse message {id}"))?;
+                let part = find_part(parsed, index)
+                    .with_context(|| format!("cannot download part of message {id}"))?;
+                let filename: PathBuf = part
+                    .filename
+                    .unwrap_or_else(|| Uuid::new_v4().to_string())
+                    .into();
+                let filepath = account_config.get_download_file_path(&filename)?;
+                printer.print_log(format!("Downloading part {index} to {:?}…", filepath))?;
+                fs::write(&filepath, &part.body)
+                    .with_context(|| format!("cannot save part at {filepath:?}"))?;
+                emails_count += 1;
+                attachments_count += 1;
+                continue;
+            }
+
             let attachments = email.attachments()?;
 
             if attachments.is_empty() {
@@ -91,6 +138,10 @@ impl AttachmentDownloadCommand {
             }
         }
 
+        if self.part.is_some() {
+            return printer.print(format!("Downloaded {attachments_count} part(s)!"));
+        }
+
         match attachments_count {
             0 => printer.print("No attachment found!"),
             1 => printer.print("Downloaded 1 attachment!"),
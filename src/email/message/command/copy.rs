@@ -9,7 +9,7 @@ use crate::{
     account::arg::name::AccountNameFlag,
     backend::Backend,
     config::TomlConfig,
-    envelope::arg::ids::EnvelopeIdsArgs,
+    envelope::arg::ids::{resolve_account, select_ids, EnvelopeIdsArgs},
     folder::arg::name::{SourceFolderNameOptionalFlag, TargetFolderNameArg},
     printer::Printer,
 };
@@ -40,24 +40,39 @@ impl MessageCopyCommand {
 
         let source = &self.source_folder.name;
         let target = &self.target_folder.name;
-        let ids = &self.envelopes.ids;
 
-        let (toml_account_config, account_config) = config.clone().into_account_configs(
+        let ids = self.envelopes.collect()?;
+        let account = resolve_account(
             self.account.name.as_deref(),
+            ids.iter().map(|ids| ids.account.as_deref()),
+        )?;
+        let (toml_account_config, account_config) = config.clone().into_account_configs(
+            account,
             #[cfg(feature = "account-sync")]
             self.cache.disable,
         )?;
 
         let copy_messages_kind = toml_account_config.copy_messages_kind();
+        let needs_listing = self.envelopes.selector.needs_listing(&ids);
+        let list_envelopes_kind = toml_account_config
+            .list_envelopes_kind()
+            .filter(|_| needs_listing);
 
         let backend = Backend::new(
             toml_account_config.clone(),
             account_config,
-            copy_messages_kind,
-            |builder| builder.set_copy_messages(BackendFeatureSource::Context),
+            copy_messages_kind.into_iter().chain(list_envelopes_kind),
+            |builder| {
+                builder.set_copy_messages(BackendFeatureSource::Context);
+                if needs_listing {
+                    builder.set_list_envelopes(BackendFeatureSource::Context);
+                }
+            },
         )
         .await?;
 
+        let ids = &select_ids(&backend, source, &ids, &self.envelopes.selector).await?;
+
         backend.copy_messages(source, target, ids).await?;
 
         printer.print(format!(
@@ -6,8 +6,11 @@ use log::info;
 #[cfg(feature = "account-sync")]
 use crate::cache::arg::disable::CacheDisableFlag;
 use crate::{
-    account::arg::name::AccountNameFlag, backend::Backend, config::TomlConfig,
-    envelope::arg::ids::EnvelopeIdsArgs, folder::arg::name::FolderNameOptionalFlag,
+    account::arg::name::AccountNameFlag,
+    backend::Backend,
+    config::TomlConfig,
+    envelope::arg::ids::{resolve_account, select_ids, EnvelopeIdsArgs},
+    folder::arg::name::FolderNameOptionalFlag,
     printer::Printer,
 };
 
@@ -38,24 +41,39 @@ impl MessageDeleteCommand {
         info!("executing delete message(s) command");
 
         let folder = &self.folder.name;
-        let ids = &self.envelopes.ids;
 
-        let (toml_account_config, account_config) = config.clone().into_account_configs(
+        let ids = self.envelopes.collect()?;
+        let account = resolve_account(
             self.account.name.as_deref(),
+            ids.iter().map(|ids| ids.account.as_deref()),
+        )?;
+        let (toml_account_config, account_config) = config.clone().into_account_configs(
+            account,
             #[cfg(feature = "account-sync")]
             self.cache.disable,
         )?;
 
         let delete_messages_kind = toml_account_config.delete_messages_kind();
+        let needs_listing = self.envelopes.selector.needs_listing(&ids);
+        let list_envelopes_kind = toml_account_config
+            .list_envelopes_kind()
+            .filter(|_| needs_listing);
 
         let backend = Backend::new(
             toml_account_config.clone(),
             account_config,
-            delete_messages_kind,
-            |builder| builder.set_delete_messages(BackendFeatureSource::Context),
+            delete_messages_kind.into_iter().chain(list_envelopes_kind),
+            |builder| {
+                builder.set_delete_messages(BackendFeatureSource::Context);
+                if needs_listing {
+                    builder.set_list_envelopes(BackendFeatureSource::Context);
+                }
+            },
         )
         .await?;
 
+        let ids = &select_ids(&backend, folder, &ids, &self.envelopes.selector).await?;
+
         backend.delete_messages(folder, ids).await?;
 
         printer.print(format!("Message(s) successfully removed from {folder}!"))
@@ -0,0 +1,79 @@
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use email::backend::feature::BackendFeatureSource;
+use log::info;
+
+#[cfg(feature = "account-sync")]
+use crate::cache::arg::disable::CacheDisableFlag;
+use crate::{
+    account::arg::name::AccountNameFlag, backend::Backend, config::TomlConfig,
+    envelope::arg::ids::EnvelopeIdArg, folder::arg::name::DraftsFolderNameOptionalFlag,
+    message::template::to_draft_tpl, printer::Printer, ui::editor,
+};
+
+/// Edit a remote draft.
+///
+/// This command allows you to resume the edition of a draft saved to
+/// your drafts folder, using the editor defined in your environment
+/// variable $EDITOR. When the edition process finishes, you can
+/// choose between saving or sending the final message. The previous
+/// version of the draft is removed once the message is sent,
+/// scheduled or saved again as remote draft.
+#[derive(Debug, Parser)]
+pub struct MessageEditCommand {
+    #[command(flatten)]
+    pub folder: DraftsFolderNameOptionalFlag,
+
+    #[command(flatten)]
+    pub envelope: EnvelopeIdArg,
+
+    #[cfg(feature = "account-sync")]
+    #[command(flatten)]
+    pub cache: CacheDisableFlag,
+
+    #[command(flatten)]
+    pub account: AccountNameFlag,
+}
+
+impl MessageEditCommand {
+    pub async fn execute(self, printer: &mut impl Printer, config: &TomlConfig) -> Result<()> {
+        info!("executing edit message command");
+
+        let folder = &self.folder.name;
+
+        let (toml_account_config, account_config) = config.clone().into_account_configs(
+            self.envelope.account(self.account.name.as_deref())?,
+            #[cfg(feature = "account-sync")]
+            self.cache.disable,
+        )?;
+
+        let get_messages_kind = toml_account_config.get_messages_kind();
+        let add_message_kind = toml_account_config.add_message_kind();
+        let send_message_kind = toml_account_config.send_message_kind();
+        let delete_messages_kind = toml_account_config.delete_messages_kind();
+
+        let backend = Backend::new(
+            toml_account_config.clone(),
+            account_config.clone(),
+            get_messages_kind
+                .into_iter()
+                .chain(add_message_kind)
+                .chain(send_message_kind)
+                .chain(delete_messages_kind),
+            |builder| {
+                builder.set_get_messages(BackendFeatureSource::Context);
+                builder.set_add_message(BackendFeatureSource::Context);
+                builder.set_send_message(BackendFeatureSource::Context);
+                builder.set_delete_messages(BackendFeatureSource::Context);
+            },
+        )
+        .await?;
+
+        let id = self.envelope.id.id;
+        let emails = backend.peek_messages(folder, &[id]).await?;
+        let email = emails.first().ok_or(anyhow!("cannot find message {id}"))?;
+        let tpl = to_draft_tpl(email, &account_config).await?;
+
+        editor::edit_draft_with_editor(account_config, printer, &backend, folder, id, tpl).await
+    }
+}
@@ -0,0 +1,97 @@
+use anyhow::Result;
+use clap::Parser;
+use email::backend::feature::BackendFeatureSource;
+use indicatif::ProgressBar;
+use log::info;
+
+#[cfg(feature = "account-sync")]
+use crate::cache::arg::disable::CacheDisableFlag;
+use crate::{
+    account::arg::name::AccountNameFlag,
+    backend::Backend,
+    config::TomlConfig,
+    envelope::arg::ids::{resolve_account, select_ids, EnvelopeIdsArgs},
+    folder::arg::name::FolderNameOptionalFlag,
+    message::archive::{export_messages, ArchiveWriter, ExportArgs},
+    printer::Printer,
+    ui::progress::MAIN_PROGRESS_STYLE,
+};
+
+/// Export messages to an mbox file, an eml directory or a Maildir.
+///
+/// This command allows you to export the given messages, unedited,
+/// alongside with their flags. The "seen" flag is not applied to the
+/// corresponding envelopes. To export a whole folder, see the folder
+/// export command.
+#[derive(Debug, Parser)]
+pub struct MessageExportCommand {
+    #[command(flatten)]
+    pub folder: FolderNameOptionalFlag,
+
+    #[command(flatten)]
+    pub envelopes: EnvelopeIdsArgs,
+
+    #[command(flatten)]
+    pub export: ExportArgs,
+
+    #[cfg(feature = "account-sync")]
+    #[command(flatten)]
+    pub cache: CacheDisableFlag,
+
+    #[command(flatten)]
+    pub account: AccountNameFlag,
+}
+
+impl MessageExportCommand {
+    pub async fn execute(self, printer: &mut impl Printer, config: &TomlConfig) -> Result<()> {
+        info!("executing export message(s) command");
+
+        let folder = &self.folder.name;
+
+        let ids = self.envelopes.collect()?;
+        let account = resolve_account(
+            self.account.name.as_deref(),
+            ids.iter().map(|ids| ids.account.as_deref()),
+        )?;
+        let (toml_account_config, account_config) = config.clone().into_account_configs(
+            account,
+            #[cfg(feature = "account-sync")]
+            self.cache.disable,
+        )?;
+
+        let get_messages_kind = toml_account_config.get_messages_kind();
+        let needs_listing = self.envelopes.selector.needs_listing(&ids);
+        let list_envelopes_kind = toml_account_config
+            .list_envelopes_kind()
+            .filter(|_| needs_listing);
+
+        let backend = Backend::new(
+            toml_account_config.clone(),
+            account_config,
+            get_messages_kind.into_iter().chain(list_envelopes_kind),
+            |builder| {
+                builder.set_get_messages(BackendFeatureSource::Context);
+                if needs_listing {
+                    builder.set_list_envelopes(BackendFeatureSource::Context);
+                }
+            },
+        )
+        .await?;
+
+        let ids = &select_ids(&backend, folder, &ids, &self.envelopes.selector).await?;
+
+        let progress = if printer.is_json() {
+            ProgressBar::hidden()
+        } else {
+            ProgressBar::new(ids.len() as u64).with_style(MAIN_PROGRESS_STYLE.clone())
+        };
+
+        let path = &self.export.path;
+        let mut writer = ArchiveWriter::open(self.export.format, path)?;
+        let count = export_messages(&backend, folder, ids, &mut writer, &progress).await?;
+        writer.finish()?;
+        progress.finish_and_clear();
+
+        printer.print(format!("Exported {count} message(s) to {path:?}!"))
+    }
+}
@@ -0,0 +1,138 @@
+use anyhow::Result;
+use clap::Parser;
+use email::backend::feature::BackendFeatureSource;
+use indicatif::ProgressBar;
+use log::{debug, info, warn};
+use std::path::PathBuf;
+
+#[cfg(feature = "account-sync")]
+use crate::cache::arg::disable::CacheDisableFlag;
+use crate::{
+    account::arg::name::AccountNameFlag,
+    backend::Backend,
+    config::TomlConfig,
+    folder::arg::name::FolderNameOptionalFlag,
+    message::archive::{ArchiveFormat, ArchiveReader},
+    printer::Printer,
+    ui::progress::MAIN_PROGRESS_STYLE,
+};
+
+/// Import messages from mbox files, eml files or Maildirs.
+///
+/// This command allows you to add all the messages of the given
+/// archives to the given folder. Flags are preserved, either from
+/// Status, X-Status and X-Mozilla-Status headers (mbox and eml) or
+/// from filenames (Maildir). Messages keep their Date header, which
+/// is built from the mbox From_ line or from the file modification
+/// date when missing.
+///
+/// The date of the mbox From_ line or the file modification date is
+/// also kept as IMAP INTERNALDATE or as Maildir file date. Notmuch
+/// only knows about the Date header.
+#[derive(Debug, Parser)]
+pub struct MessageImportCommand {
+    #[command(flatten)]
+    pub folder: FolderNameOptionalFlag,
+
+    /// The paths of the archives to import.
+    #[arg(value_name = "PATH", required = true)]
+    pub paths: Vec<PathBuf>,
+
+    /// The format of the archives.
+    ///
+    /// When omitted, the format is guessed from each path:
+    /// directories containing a cur or a new directory are Maildirs,
+    /// other directories are eml archives, files starting with a
+    /// From_ line are mbox files and other files are eml files.
+    #[arg(long, short = 'F', value_enum)]
+    pub format: Option<ArchiveFormat>,
+
+    #[cfg(feature = "account-sync")]
+    #[command(flatten)]
+    pub cache: CacheDisableFlag,
+
+    #[command(flatten)]
+    pub account: AccountNameFlag,
+}
+
+impl MessageImportCommand {
+    pub async fn execute(self, printer: &mut impl Printer, config: &TomlConfig) -> Result<()> {
+        info!("executing import message(s) command");
+
+        let folder = &self.folder.name;
+        let (toml_account_config, account_config) = config.clone().into_account_configs(
+            self.account.name.as_deref(),
+            #[cfg(feature = "account-sync")]
+            self.cache.disable,
+        )?;
+
+        let add_message_kind = toml_account_config.add_message_kind();
+
+        let backend = Backend::new(
+            toml_account_config.clone(),
+            account_config,
+            add_message_kind,
+            |builder| builder.set_add_message(BackendFeatureSource::Context),
+        )
+        .await?;
+
+        let readers = self
+            .paths
+            .iter()
+            .map(|path| {
+                let format = self.format.unwrap_or_else(|| ArchiveFormat::detect(path));
+                Ok((path, ArchiveReader::open(format, path)?))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let progress = if printer.is_json() {
+            ProgressBar::hidden()
+        } else {
+            let size = readers.iter().map(|(_, reader)| reader.size()).sum();
+            ProgressBar::new(size).with_style(MAIN_PROGRESS_STYLE.clone())
+        };
+
+        let mut count = 0;
+        let mut errors = 0;
+
+        for (path, reader) in readers {
+            progress.set_message(format!("Importing {path:?}…"));
+            let mut found = false;
+
+            for (n, msg) in reader.enumerate() {
+                found = true;
+                let msg = msg?;
+                progress.inc(msg.size);
+
+                let res = backend
+                    .add_message_with_date(folder, &msg.raw, &msg.flags, msg.date)
+                    .await;
+
+                match res {
+                    Ok(_) => count += 1,
+                    Err(err) => {
+                        errors += 1;
+                        progress.suspend(|| {
+                            warn!("cannot import message {} of {path:?}: {err}", n + 1);
+                        });
+                        debug!("{err:?}");
+                    }
+                }
+            }
+
+            if !found {
+                progress.suspend(|| warn!("cannot find any message in {path:?}, skipping it"));
+            }
+        }
+
+        progress.finish_and_clear();
+
+        if errors > 0 {
+            printer.print(format!(
+                "Imported {count} message(s) to {folder}, {errors} message(s) could not be imported!"
+            ))
+        } else {
+            printer.print(format!("Imported {count} message(s) to {folder}!"))
+        }
+    }
+}
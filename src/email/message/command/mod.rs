@@ -1,9 +1,14 @@
 pub mod copy;
 pub mod delete;
+pub mod edit;
+pub mod export;
 pub mod forward;
+pub mod import;
 pub mod mailto;
 pub mod r#move;
+pub mod parts;
 pub mod read;
+pub mod redirect;
 pub mod reply;
 pub mod save;
 pub mod send;
@@ -15,10 +20,11 @@ use clap::Subcommand;
 use crate::{config::TomlConfig, printer::Printer};
 
 use self::{
-    copy::MessageCopyCommand, delete::MessageDeleteCommand, forward::MessageForwardCommand,
-    mailto::MessageMailtoCommand, r#move::MessageMoveCommand, read::MessageReadCommand,
-    reply::MessageReplyCommand, save::MessageSaveCommand, send::MessageSendCommand,
-    write::MessageWriteCommand,
+    copy::MessageCopyCommand, delete::MessageDeleteCommand, edit::MessageEditCommand,
+    export::MessageExportCommand, forward::MessageForwardCommand, import::MessageImportCommand,
+    mailto::MessageMailtoCommand, parts::MessagePartsCommand, r#move::MessageMoveCommand,
+    read::MessageReadCommand, redirect::MessageRedirectCommand, reply::MessageReplyCommand,
+    save::MessageSaveCommand, send::MessageSendCommand, write::MessageWriteCommand,
 };
 
 /// Manage messages.
@@ -32,15 +38,26 @@ pub enum MessageSubcommand {
     #[command(arg_required_else_help = true)]
     Read(MessageReadCommand),
 
+    #[command(arg_required_else_help = true)]
+    #[command(alias = "structure")]
+    Parts(MessagePartsCommand),
+
     #[command(aliases = ["add", "create", "new", "compose"])]
     Write(MessageWriteCommand),
 
+    #[command(arg_required_else_help = true)]
+    Edit(MessageEditCommand),
+
     #[command()]
     Reply(MessageReplyCommand),
 
     #[command(aliases = ["fwd", "fd"])]
     Forward(MessageForwardCommand),
 
+    #[command(arg_required_else_help = true)]
+    #[command(alias = "bounce")]
+    Redirect(MessageRedirectCommand),
+
     #[command()]
     Mailto(MessageMailtoCommand),
 
@@ -59,6 +76,12 @@ pub enum MessageSubcommand {
     #[command(arg_required_else_help = true)]
     #[command(aliases = ["remove", "rm"])]
     Delete(MessageDeleteCommand),
+
+    #[command(arg_required_else_help = true)]
+    Export(MessageExportCommand),
+
+    #[command(arg_required_else_help = true)]
+    Import(MessageImportCommand),
 }
 
 impl MessageSubcommand {
@@ -66,15 +89,20 @@ impl MessageSubcommand {
     pub async fn execute(self, printer: &mut impl Printer, config: &TomlConfig) -> Result<()> {
         match self {
             Self::Read(cmd) => cmd.execute(printer, config).await,
+            Self::Parts(cmd) => cmd.execute(printer, config).await,
             Self::Write(cmd) => cmd.execute(printer, config).await,
+            Self::Edit(cmd) => cmd.execute(printer, config).await,
             Self::Reply(cmd) => cmd.execute(printer, config).await,
             Self::Forward(cmd) => cmd.execute(printer, config).await,
+            Self::Redirect(cmd) => cmd.execute(printer, config).await,
             Self::Mailto(cmd) => cmd.execute(printer, config).await,
             Self::Save(cmd) => cmd.execute(printer, config).await,
             Self::Send(cmd) => cmd.execute(printer, config).await,
             Self::Copy(cmd) => cmd.execute(printer, config).await,
             Self::Move(cmd) => cmd.execute(printer, config).await,
             Self::Delete(cmd) => cmd.execute(printer, config).await,
+            Self::Export(cmd) => cmd.execute(printer, config).await,
+            Self::Import(cmd) => cmd.execute(printer, config).await,
         }
     }
 }
@@ -0,0 +1,81 @@
+use anyhow::{anyhow, Context, Result};
+use clap::Parser;
+use email::backend::feature::BackendFeatureSource;
+use log::info;
+
+#[cfg(feature = "account-sync")]
+use crate::cache::arg::disable::CacheDisableFlag;
+use crate::{
+    account::arg::name::AccountNameFlag,
+    backend::Backend,
+    config::TomlConfig,
+    envelope::arg::ids::EnvelopeIdArg,
+    folder::arg::name::FolderNameOptionalFlag,
+    message::part::MessageParts,
+    printer::{PrintTableOpts, Printer},
+    ui::arg::max_width::TableMaxWidthFlag,
+};
+
+/// List the MIME parts of a message.
+///
+/// This command prints the MIME tree of the given message: the number
+/// of each part, its content type, its charset, its decoded size and
+/// its filename. Part numbers can be given to the --part argument of
+/// the read and attachment download commands. The "seen" flag is not
+/// applied to the corresponding envelope.
+#[derive(Debug, Parser)]
+pub struct MessagePartsCommand {
+    #[command(flatten)]
+    pub folder: FolderNameOptionalFlag,
+
+    #[command(flatten)]
+    pub envelope: EnvelopeIdArg,
+
+    #[command(flatten)]
+    pub table: TableMaxWidthFlag,
+
+    #[cfg(feature = "account-sync")]
+    #[command(flatten)]
+    pub cache: CacheDisableFlag,
+
+    #[command(flatten)]
+    pub account: AccountNameFlag,
+}
+
+impl MessagePartsCommand {
+    pub async fn execute(self, printer: &mut impl Printer, config: &TomlConfig) -> Result<()> {
+        info!("executing list message parts command");
+
+        let folder = &self.folder.name;
+        let (toml_account_config, account_config) = config.clone().into_account_configs(
+            self.envelope.account(self.account.name.as_deref())?,
+            #[cfg(feature = "account-sync")]
+            self.cache.disable,
+        )?;
+
+        let get_messages_kind = toml_account_config.get_messages_kind();
+
+        let backend = Backend::new(
+            toml_account_config.clone(),
+            account_config.clone(),
+            get_messages_kind,
+            |builder| builder.set_get_messages(BackendFeatureSource::Context),
+        )
+        .await?;
+
+        let id = self.envelope.id.id;
+        let emails = backend.peek_messages(folder, &[id]).await?;
+        let email = emails.first().ok_or(anyhow!("cannot find message {id}"))?;
+        let parsed = email
+            .parsed()
+            .with_context(|| format!("cannot parse message {id}"))?;
+
+        printer.print_table(
+            Box::new(MessageParts::from_message(parsed)),
+            PrintTableOpts {
+                format: &account_config.get_message_read_format(),
+                max_width: self.table.max_width,
+            },
+        )
+    }
+}
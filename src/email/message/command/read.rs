@@ -1,15 +1,22 @@
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use clap::Parser;
 use email::backend::feature::BackendFeatureSource;
 use log::info;
-use mml::message::FilterParts;
+use mml::{message::FilterParts, MimeInterpreterBuilder};
 
 #[cfg(feature = "account-sync")]
 use crate::cache::arg::disable::CacheDisableFlag;
 #[allow(unused)]
 use crate::{
-    account::arg::name::AccountNameFlag, backend::Backend, config::TomlConfig,
-    envelope::arg::ids::EnvelopeIdsArgs, folder::arg::name::FolderNameOptionalFlag,
+    account::arg::name::AccountNameFlag,
+    backend::Backend,
+    config::TomlConfig,
+    envelope::arg::ids::{resolve_account, select_ids, EnvelopeIdsArgs},
+    folder::arg::name::FolderNameOptionalFlag,
+    message::{
+        part::find_part,
+        render::{to_read_tpl, MessageRender},
+    },
     printer::Printer,
 };
 
@@ -51,6 +58,33 @@ pub struct MessageReadCommand {
     #[arg(conflicts_with = "raw")]
     pub html: bool,
 
+    /// Choose the part used to render the body of the message.
+    ///
+    /// With plain, the text/plain part is shown, and the text/html
+    /// part is rendered as text only when no text/plain part exists.
+    /// With html, the text/html part is rendered as text (links as
+    /// footnotes, lists, tables and quotes), wrapped to the terminal
+    /// width. Defaults to the one set up in your TOML configuration
+    /// file (message.read.render), otherwise plain.
+    #[arg(long, value_name = "PART", value_enum)]
+    #[arg(conflicts_with = "raw")]
+    #[arg(conflicts_with = "html")]
+    pub render: Option<MessageRender>,
+
+    /// Read only the given MIME part of the message, decoded.
+    ///
+    /// Part numbers are shown by the parts command (1, 1.2…). Only
+    /// text parts can be read, other parts can be extracted with the
+    /// attachment download command. Multipart and message parts are
+    /// shown raw, MIME structure included.
+    #[arg(long, value_name = "NUMBER")]
+    #[arg(conflicts_with = "raw")]
+    #[arg(conflicts_with = "html")]
+    #[arg(conflicts_with = "render")]
+    #[arg(conflicts_with = "no_headers")]
+    #[arg(conflicts_with = "headers")]
+    pub part: Option<String>,
+
     /// Read only the body of the message.
     ///
     /// All headers will be removed from the message.
@@ -83,56 +117,107 @@ impl MessageReadCommand {
         info!("executing read message(s) command");
 
         let folder = &self.folder.name;
-        let ids = &self.envelopes.ids;
 
-        let (toml_account_config, account_config) = config.clone().into_account_configs(
+        let ids = self.envelopes.collect()?;
+        let account = resolve_account(
             self.account.name.as_deref(),
+            ids.iter().map(|ids| ids.account.as_deref()),
+        )?;
+        let (toml_account_config, account_config) = config.clone().into_account_configs(
+            account,
             #[cfg(feature = "account-sync")]
             self.cache.disable,
         )?;
 
         let get_messages_kind = toml_account_config.get_messages_kind();
+        let needs_listing = self.envelopes.selector.needs_listing(&ids);
+        let list_envelopes_kind = toml_account_config
+            .list_envelopes_kind()
+            .filter(|_| needs_listing);
 
         let backend = Backend::new(
             toml_account_config.clone(),
             account_config.clone(),
-            get_messages_kind,
-            |builder| builder.set_get_messages(BackendFeatureSource::Context),
+            get_messages_kind.into_iter().chain(list_envelopes_kind),
+            |builder| {
+                builder.set_get_messages(BackendFeatureSource::Context);
+                if needs_listing {
+                    builder.set_list_envelopes(BackendFeatureSource::Context);
+                }
+            },
         )
         .await?;
 
+        let ids = &select_ids(&backend, folder, &ids, &self.envelopes.selector).await?;
+        let render = self
+            .render
+            .unwrap_or_else(|| toml_account_config.read_message_render());
+
         let emails = if self.preview {
             backend.peek_messages(folder, ids).await
         } else {
             backend.get_messages(folder, ids).await
         }?;
 
+        let emails = emails.to_vec();
+
+        if emails.len() != ids.len() {
+            bail!(
+                "cannot read messages: expected {} message(s), got {}",
+                ids.len(),
+                emails.len()
+            );
+        }
+
         let mut glue = "";
         let mut bodies = String::default();
 
-        for email in emails.to_vec() {
+        for (id, email) in ids.iter().zip(emails) {
             bodies.push_str(glue);
 
-            if self.raw {
+            if let Some(index) = &self.part {
+                let parsed = email
+                    .parsed()
+                    .with_context(|| format!("cannot parse message {id}"))?;
+                let part = find_part(parsed, index)
+                    .with_context(|| format!("cannot read part of message {id}"))?;
+
+                match part.text {
+                    Some(text) => bodies.push_str(&text),
+                    None if part.content_type.starts_with("multipart/")
+                        || part.content_type.starts_with("message/") =>
+                    {
+                        bodies.push_str(&String::from_utf8_lossy(&part.body))
+                    }
+                    None => bail!(
+                        "cannot read part {index} of message {id}: {} is not a text part",
+                        part.content_type
+                    ),
+                }
+            } else if self.raw {
                 // emails do not always have valid utf8, uses "lossy" to
                 // display what can be displayed
                 bodies.push_str(&String::from_utf8_lossy(email.raw()?));
             } else {
-                let tpl = email
-                    .to_read_tpl(&account_config, |mut tpl| {
-                        if self.no_headers {
-                            tpl = tpl.with_hide_all_headers();
-                        } else if !self.headers.is_empty() {
-                            tpl = tpl.with_show_only_headers(&self.headers);
-                        }
-
-                        if self.html {
-                            tpl = tpl.with_filter_parts(FilterParts::Only("text/html".into()));
-                        }
-
-                        tpl
-                    })
-                    .await?;
+                let with_headers = |mut tpl: MimeInterpreterBuilder| {
+                    if self.no_headers {
+                        tpl = tpl.with_hide_all_headers();
+                    } else if !self.headers.is_empty() {
+                        tpl = tpl.with_show_only_headers(&self.headers);
+                    }
+                    tpl
+                };
+
+                let tpl = if self.html {
+                    email
+                        .to_read_tpl(&account_config, |tpl| {
+                            with_headers(tpl)
+                                .with_filter_parts(FilterParts::Only("text/html".into()))
+                        })
+                        .await?
+                } else {
+                    to_read_tpl(email, &account_config, render, with_headers).await?
+                };
                 bodies.push_str(&tpl);
             }
 
@@ -0,0 +1,83 @@
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use email::backend::feature::BackendFeatureSource;
+use log::info;
+
+#[cfg(feature = "account-sync")]
+use crate::cache::arg::disable::CacheDisableFlag;
+use crate::{
+    account::arg::name::AccountNameFlag, backend::Backend, config::TomlConfig,
+    envelope::arg::ids::EnvelopeIdArg, folder::arg::name::FolderNameOptionalFlag,
+    message::redirect::to_redirected_message, printer::Printer,
+};
+
+/// Redirect a message.
+///
+/// This command allows you to send the given message unchanged to
+/// new recipients, also known as bouncing. Unlike forward, the
+/// original headers and body are kept: only the Resent-From,
+/// Resent-To, Resent-Date and Resent-Message-ID headers are added.
+/// The message is sent using the send message backend, and no copy
+/// is saved to the sent folder.
+#[derive(Debug, Parser)]
+pub struct MessageRedirectCommand {
+    #[command(flatten)]
+    pub folder: FolderNameOptionalFlag,
+
+    #[command(flatten)]
+    pub envelope: EnvelopeIdArg,
+
+    /// The address to redirect the message to.
+    ///
+    /// This argument can be repeated in order to redirect the message
+    /// to multiple addresses.
+    #[arg(long, short, value_name = "ADDR", required = true)]
+    pub to: Vec<String>,
+
+    #[cfg(feature = "account-sync")]
+    #[command(flatten)]
+    pub cache: CacheDisableFlag,
+
+    #[command(flatten)]
+    pub account: AccountNameFlag,
+}
+
+impl MessageRedirectCommand {
+    pub async fn execute(self, printer: &mut impl Printer, config: &TomlConfig) -> Result<()> {
+        info!("executing redirect message command");
+
+        let folder = &self.folder.name;
+
+        let (toml_account_config, account_config) = config.clone().into_account_configs(
+            self.envelope.account(self.account.name.as_deref())?,
+            #[cfg(feature = "account-sync")]
+            self.cache.disable,
+        )?;
+
+        let get_messages_kind = toml_account_config.get_messages_kind();
+        let send_message_kind = toml_account_config.send_message_kind();
+
+        let backend = Backend::new(
+            toml_account_config.clone(),
+            account_config.clone(),
+            get_messages_kind.into_iter().chain(send_message_kind),
+            |builder| {
+                builder.set_get_messages(BackendFeatureSource::Context);
+                builder.set_send_message(BackendFeatureSource::Context);
+            },
+        )
+        .await?;
+
+        let id = self.envelope.id.id;
+        let emails = backend.get_messages(folder, &[id]).await?;
+        let email = emails.first().ok_or(anyhow!("cannot find message {id}"))?;
+
+        let msg = to_redirected_message(&account_config, email.raw()?, &self.to)?;
+        backend.redirect_message(&msg, &self.to).await?;
+
+        printer.print(format!(
+            "Message {id} successfully redirected to {}!",
+            self.to.join(", ")
+        ))
+    }
+}
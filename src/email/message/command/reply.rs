@@ -53,7 +53,7 @@ impl MessageReplyCommand {
 
         let folder = &self.folder.name;
         let (toml_account_config, account_config) = config.clone().into_account_configs(
-            self.account.name.as_deref(),
+            self.envelope.account(self.account.name.as_deref())?,
             #[cfg(feature = "account-sync")]
             self.cache.disable,
         )?;
@@ -72,7 +72,7 @@ impl MessageReplyCommand {
         )
         .await?;
 
-        let id = self.envelope.id;
+        let id = self.envelope.id.id;
         let tpl = backend
             .get_messages(folder, &[id])
             .await?
@@ -1,25 +1,40 @@
 use anyhow::Result;
+use chrono::{DateTime, Local};
 use clap::Parser;
-use email::{backend::feature::BackendFeatureSource, message::send::SendMessageThenSaveCopy};
+use email::backend::feature::BackendFeatureSource;
 use log::info;
 use std::io::{self, BufRead, IsTerminal};
 
 #[cfg(feature = "account-sync")]
 use crate::cache::arg::disable::CacheDisableFlag;
 use crate::{
-    account::arg::name::AccountNameFlag, backend::Backend, config::TomlConfig,
-    message::arg::MessageRawArg, printer::Printer,
+    account::arg::name::AccountNameFlag,
+    backend::Backend,
+    config::TomlConfig,
+    message::arg::MessageRawArg,
+    outbox::{parse_send_at, send_message_or_queue, Outbox},
+    printer::Printer,
 };
 
 /// Send a message.
 ///
 /// This command allows you to send a raw message and to save a copy
-/// to your send folder.
+/// to your send folder. The message can also be scheduled to be sent
+/// later, see the outbox command.
 #[derive(Debug, Parser)]
 pub struct MessageSendCommand {
     #[command(flatten)]
     pub message: MessageRawArg,
 
+    /// Send the message later, at the given date.
+    ///
+    /// The message is stored in the local outbox, then sent by the
+    /// outbox flush command once the date is reached. The date is
+    /// either local (YYYY-MM-DD HH:MM) or absolute (RFC 3339 or RFC
+    /// 2822).
+    #[arg(long, value_name = "DATE", value_parser = parse_send_at)]
+    pub at: Option<DateTime<Local>>,
+
     #[cfg(feature = "account-sync")]
     #[command(flatten)]
     pub cache: CacheDisableFlag,
@@ -38,6 +53,26 @@ impl MessageSendCommand {
             self.cache.disable,
         )?;
 
+        let msg = if io::stdin().is_terminal() {
+            self.message.raw()
+        } else {
+            io::stdin()
+                .lock()
+                .lines()
+                .map_while(Result::ok)
+                .collect::<Vec<_>>()
+                .join("\r\n")
+        };
+
+        if let Some(send_at) = self.at {
+            let entry = Outbox::new(&account_config)?.push(msg.as_bytes(), send_at)?;
+            return printer.print(format!(
+                "Message {} successfully scheduled for {}!",
+                entry.short_id(),
+                send_at.format("%F %R")
+            ));
+        }
+
         let send_message_kind = toml_account_config.send_message_kind().into_iter().chain(
             toml_account_config
                 .add_message_kind()
@@ -55,19 +90,12 @@ impl MessageSendCommand {
         )
         .await?;
 
-        let msg = if io::stdin().is_terminal() {
-            self.message.raw()
-        } else {
-            io::stdin()
-                .lock()
-                .lines()
-                .map_while(Result::ok)
-                .collect::<Vec<_>>()
-                .join("\r\n")
-        };
-
-        backend.send_message_then_save_copy(msg.as_bytes()).await?;
-
-        printer.print("Message successfully sent!")
+        match send_message_or_queue(&backend, msg.as_bytes()).await? {
+            Some(entry) => printer.print(format!(
+                "Message could not be sent, queued to the outbox as {}!",
+                entry.short_id()
+            )),
+            None => printer.print("Message successfully sent!"),
+        }
     }
 }
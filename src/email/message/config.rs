@@ -3,7 +3,7 @@ use email::message::sync::config::MessageSyncConfig;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 
-use crate::backend::BackendKind;
+use crate::{backend::BackendKind, message::render::MessageRender};
 
 #[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
 pub struct MessageConfig {
@@ -111,6 +111,9 @@ impl MessagePeekConfig {
 pub struct MessageGetConfig {
     pub backend: Option<BackendKind>,
 
+    /// The preferred part used to render the body of messages.
+    pub render: Option<MessageRender>,
+
     #[serde(flatten)]
     pub remote: email::message::get::config::MessageReadConfig,
 }
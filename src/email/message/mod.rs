@@ -1,5 +1,10 @@
+pub mod add;
+pub mod archive;
 pub mod arg;
 pub mod attachment;
 pub mod command;
 pub mod config;
+pub mod part;
+pub mod redirect;
+pub mod render;
 pub mod template;
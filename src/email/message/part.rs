@@ -0,0 +1,353 @@
+//! Module dedicated to the MIME structure of messages.
+//!
+//! Parts are numbered the same way IMAP numbers them: the children
+//! of a multipart are numbered from 1, nested parts get the number
+//! of their parent as prefix (1.2, 1.2.1…). The multipart root of a
+//! message has no number, and a message without multipart root has
+//! a single part numbered 1.
+
+use anyhow::{anyhow, Result};
+use mail_parser::{Message, MessagePart, MimeHeaders, PartType};
+use serde::Serialize;
+use std::{borrow::Cow, ops};
+
+use crate::{
+    envelope::format_size,
+    printer::{PrintTable, PrintTableOpts, WriteColor},
+    ui::{Cell, Row, Table},
+};
+
+/// Represents a MIME part of a message.
+#[derive(Clone, Debug, Serialize)]
+pub struct MessagePartEntry {
+    /// The number of the part, `None` for the multipart root.
+    pub index: Option<String>,
+    pub depth: usize,
+    pub content_type: String,
+    pub charset: Option<String>,
+    /// The size of the decoded body of the part, in bytes.
+    pub size: u64,
+    pub filename: Option<String>,
+}
+
+impl Table for MessagePartEntry {
+    fn head() -> Row {
+        Row::new()
+            .cell(Cell::new("PART").bold().underline().white())
+            .cell(Cell::new("TYPE").bold().underline().white())
+            .cell(Cell::new("CHARSET").bold().underline().white())
+            .cell(Cell::new("SIZE").bold().underline().white())
+            .cell(Cell::new("FILENAME").bold().underline().white())
+    }
+
+    fn row(&self) -> Row {
+        let content_type = format!("{}{}", "  ".repeat(self.depth), self.content_type);
+
+        Row::new()
+            .cell(Cell::new(self.index.as_deref().unwrap_or_default()).red())
+            .cell(Cell::new(content_type).green())
+            .cell(Cell::new(self.charset.as_deref().unwrap_or_default()).white())
+            .cell(Cell::new(format_size(self.size)).yellow())
+            .cell(
+                Cell::new(self.filename.as_deref().unwrap_or_default())
+                    .shrinkable()
+                    .blue(),
+            )
+    }
+}
+
+/// Represents the MIME tree of a message, flattened in depth-first
+/// order.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct MessageParts(Vec<MessagePartEntry>);
+
+impl MessageParts {
+    pub fn from_message(message: &Message) -> Self {
+        walk(message)
+            .into_iter()
+            .map(|walked| MessagePartEntry {
+                content_type: content_type(walked.part),
+                charset: walked
+                    .part
+                    .content_type()
+                    .and_then(|ctype| ctype.attribute("charset"))
+                    .map(ToOwned::to_owned),
+                size: part_size(walked.part),
+                filename: walked.part.attachment_name().map(ToOwned::to_owned),
+                index: walked.index,
+                depth: walked.depth,
+            })
+            .collect()
+    }
+}
+
+impl ops::Deref for MessageParts {
+    type Target = Vec<MessagePartEntry>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl FromIterator<MessagePartEntry> for MessageParts {
+    fn from_iter<T: IntoIterator<Item = MessagePartEntry>>(iter: T) -> Self {
+        MessageParts(iter.into_iter().collect())
+    }
+}
+
+impl PrintTable for MessageParts {
+    fn print_table(&self, writer: &mut dyn WriteColor, opts: PrintTableOpts) -> Result<()> {
+        writeln!(writer)?;
+        Table::print(writer, self, opts)?;
+        writeln!(writer)?;
+        Ok(())
+    }
+}
+
+/// Represents the decoded body of a MIME part.
+#[derive(Clone, Debug)]
+pub struct DecodedPart<'a> {
+    pub content_type: String,
+    pub filename: Option<String>,
+    /// The decoded text, for text parts only.
+    pub text: Option<Cow<'a, str>>,
+    /// The decoded body. Multipart and message parts are returned
+    /// raw, MIME structure included.
+    pub body: Cow<'a, [u8]>,
+}
+
+/// Find the part matching the given number, then decode it.
+pub fn find_part<'a>(message: &'a Message, index: &str) -> Result<DecodedPart<'a>> {
+    let index = index.trim();
+    let WalkedPart { message, part, .. } = walk(message)
+        .into_iter()
+        .find(|walked| walked.index.as_deref() == Some(index))
+        .ok_or_else(|| anyhow!("cannot find part {index}"))?;
+
+    let body = match &part.body {
+        PartType::Multipart(_) => message
+            .raw_message()
+            .get(part.offset_body..part.offset_end)
+            .unwrap_or_default(),
+        _ => part.contents(),
+    };
+
+    let text = match &part.body {
+        PartType::Text(text) | PartType::Html(text) => Some(Cow::Borrowed(text.as_ref())),
+        _ => None,
+    };
+
+    Ok(DecodedPart {
+        content_type: content_type(part),
+        filename: part.attachment_name().map(ToOwned::to_owned),
+        text,
+        body: Cow::Borrowed(body),
+    })
+}
+
+/// Return true if the given message has a part of the given content
+/// type. Text parts must have a non-blank text to count.
+///
+/// Parts of encrypted multiparts are not visible until decrypted.
+pub fn has_part(message: &Message, ctype: &str) -> bool {
+    walk(message).into_iter().any(|walked| {
+        content_type(walked.part) == ctype
+            && match &walked.part.body {
+                PartType::Text(text) | PartType::Html(text) => !text.trim().is_empty(),
+                _ => true,
+            }
+    })
+}
+
+/// Return the size of the decoded body of the given part. The size
+/// of a multipart is the size of its raw body.
+fn part_size(part: &MessagePart) -> u64 {
+    let size = match &part.body {
+        PartType::Multipart(_) => part.offset_end.saturating_sub(part.offset_body),
+        _ => part.len(),
+    };
+
+    size as u64
+}
+
+fn content_type(part: &MessagePart) -> String {
+    match part.content_type() {
+        Some(ctype) => match &ctype.c_subtype {
+            Some(subtype) => format!("{}/{subtype}", ctype.c_type),
+            None => ctype.c_type.to_string(),
+        }
+        .to_lowercase(),
+        None => match &part.body {
+            PartType::Html(_) => String::from("text/html"),
+            PartType::Binary(_) | PartType::InlineBinary(_) => {
+                String::from("application/octet-stream")
+            }
+            PartType::Message(_) => String::from("message/rfc822"),
+            PartType::Multipart(_) => String::from("multipart/mixed"),
+            PartType::Text(_) => String::from("text/plain"),
+        },
+    }
+}
+
+struct WalkedPart<'a> {
+    index: Option<String>,
+    depth: usize,
+    /// The message owning the part, which differs from the root
+    /// message for parts of nested messages.
+    message: &'a Message<'a>,
+    part: &'a MessagePart<'a>,
+}
+
+/// Walk the MIME tree of the given message, depth-first, returning
+/// parts alongside with their number and their depth.
+fn walk<'a>(message: &'a Message<'a>) -> Vec<WalkedPart<'a>> {
+    let mut parts = Vec::new();
+    walk_message(message, None, 0, &mut parts);
+    parts
+}
+
+fn walk_message<'a>(
+    message: &'a Message<'a>,
+    prefix: Option<&str>,
+    depth: usize,
+    parts: &mut Vec<WalkedPart<'a>>,
+) {
+    let Some(root) = message.parts.first() else {
+        return;
+    };
+
+    match &root.body {
+        PartType::Multipart(ids) => {
+            parts.push(WalkedPart {
+                index: None,
+                depth,
+                message,
+                part: root,
+            });
+            walk_children(message, ids, prefix, depth + 1, parts);
+        }
+        _ => walk_part(message, 0, join(prefix, 1), depth, parts),
+    }
+}
+
+fn walk_children<'a>(
+    message: &'a Message<'a>,
+    ids: &[usize],
+    prefix: Option<&str>,
+    depth: usize,
+    parts: &mut Vec<WalkedPart<'a>>,
+) {
+    for (i, id) in ids.iter().enumerate() {
+        walk_part(message, *id, join(prefix, i + 1), depth, parts);
+    }
+}
+
+fn walk_part<'a>(
+    message: &'a Message<'a>,
+    id: usize,
+    index: String,
+    depth: usize,
+    parts: &mut Vec<WalkedPart<'a>>,
+) {
+    let Some(part) = message.parts.get(id) else {
+        return;
+    };
+
+    parts.push(WalkedPart {
+        index: Some(index.clone()),
+        depth,
+        message,
+        part,
+    });
+
+    match &part.body {
+        PartType::Multipart(ids) => walk_children(message, ids, Some(&index), depth + 1, parts),
+        PartType::Message(nested) => walk_message(nested, Some(&index), depth + 1, parts),
+        _ => (),
+    }
+}
+
+fn join(prefix: Option<&str>, n: usize) -> String {
+    match prefix {
+        Some(prefix) => format!("{prefix}.{n}"),
+        None => n.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mail_parser::MessageParser;
+
+    use super::{find_part, MessageParts};
+
+    const RAW: &str = concat!(
+        "From: alice@localhost\r\n",
+        "Subject: parts\r\n",
+        "Content-Type: multipart/mixed; boundary=\"outer\"\r\n",
+        "\r\n",
+        "--outer\r\n",
+        "Content-Type: multipart/alternative; boundary=\"inner\"\r\n",
+        "\r\n",
+        "--inner\r\n",
+        "Content-Type: text/plain; charset=utf-8\r\n",
+        "\r\n",
+        "Hello\r\n",
+        "--inner\r\n",
+        "Content-Type: text/html; charset=utf-8\r\n",
+        "Content-Transfer-Encoding: quoted-printable\r\n",
+        "\r\n",
+        "<p>Hello=21</p>\r\n",
+        "--inner--\r\n",
+        "--outer\r\n",
+        "Content-Type: application/octet-stream\r\n",
+        "Content-Disposition: attachment; filename=\"data.bin\"\r\n",
+        "Content-Transfer-Encoding: base64\r\n",
+        "\r\n",
+        "AAEC\r\n",
+        "--outer--\r\n",
+    );
+
+    #[test]
+    fn list_parts() {
+        let message = MessageParser::default().parse(RAW.as_bytes()).unwrap();
+        let parts = MessageParts::from_message(&message);
+
+        let summary: Vec<_> = parts
+            .iter()
+            .map(|part| {
+                (
+                    part.index.as_deref().unwrap_or_default(),
+                    part.depth,
+                    part.content_type.as_str(),
+                    part.filename.as_deref(),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                ("", 0, "multipart/mixed", None),
+                ("1", 1, "multipart/alternative", None),
+                ("1.1", 2, "text/plain", None),
+                ("1.2", 2, "text/html", None),
+                ("2", 1, "application/octet-stream", Some("data.bin")),
+            ]
+        );
+        assert_eq!(parts[2].charset.as_deref(), Some("utf-8"));
+    }
+
+    #[test]
+    fn find_decoded_part() {
+        let message = MessageParser::default().parse(RAW.as_bytes()).unwrap();
+
+        let html = find_part(&message, "1.2").unwrap();
+        assert_eq!(html.text.as_deref(), Some("<p>Hello!</p>"));
+
+        let bin = find_part(&message, "2").unwrap();
+        assert_eq!(bin.filename.as_deref(), Some("data.bin"));
+        assert_eq!(bin.body.as_ref(), &[0, 1, 2]);
+
+        assert!(find_part(&message, "3").is_err());
+    }
+}
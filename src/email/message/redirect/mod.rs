@@ -0,0 +1,121 @@
+//! Module dedicated to message redirection.
+//!
+//! A redirected message is sent unchanged to new recipients. Only
+//! the resent fields are prepended to its headers, as defined in the
+//! [RFC 5322](https://www.rfc-editor.org/rfc/rfc5322#section-3.6.6).
+
+#[cfg(feature = "sendmail")]
+pub mod sendmail;
+#[cfg(feature = "smtp")]
+pub mod smtp;
+
+use anyhow::{bail, Result};
+use email::account::config::AccountConfig;
+use mail_builder::headers::{address::Address, date::Date, message_id::MessageId, Header};
+use std::io::Write;
+use uuid::Uuid;
+
+/// Prepend the resent fields to the given raw message.
+///
+/// The fields use the line ending of the given message, so that the
+/// rest of the message can be kept untouched.
+pub fn to_redirected_message(config: &AccountConfig, msg: &[u8], to: &[String]) -> Result<Vec<u8>> {
+    for addr in to {
+        if !email_address::EmailAddress::is_valid(addr) {
+            bail!("cannot redirect message: invalid address {addr}");
+        }
+    }
+
+    let domain = config
+        .email
+        .rsplit_once('@')
+        .map(|(_, domain)| domain)
+        .unwrap_or("localhost");
+    let message_id = format!("{}@{domain}", Uuid::new_v4().to_simple());
+
+    let headers = write_resent_headers(
+        config.display_name.as_deref(),
+        &config.email,
+        to,
+        Date::now(),
+        &message_id,
+    )?;
+
+    let crlf = match msg.iter().position(|b| *b == b'\n') {
+        Some(pos) => pos > 0 && msg[pos - 1] == b'\r',
+        None => true,
+    };
+
+    let mut redirected = Vec::with_capacity(headers.len() + msg.len());
+
+    if crlf {
+        redirected.extend(headers);
+    } else {
+        redirected.extend(headers.into_iter().filter(|b| *b != b'\r'));
+    }
+
+    redirected.extend_from_slice(msg);
+
+    Ok(redirected)
+}
+
+fn write_resent_headers(
+    from_name: Option<&str>,
+    from: &str,
+    to: &[String],
+    date: Date,
+    message_id: &str,
+) -> Result<Vec<u8>> {
+    let mut headers = Vec::new();
+
+    headers.write_all(b"Resent-From: ")?;
+    Address::new_address(from_name, from).write_header(&mut headers, 13)?;
+
+    headers.write_all(b"Resent-To: ")?;
+    let to = to
+        .iter()
+        .map(|addr| Address::new_address(None::<&str>, addr.as_str()))
+        .collect();
+    Address::new_list(to).write_header(&mut headers, 11)?;
+
+    headers.write_all(b"Resent-Date: ")?;
+    date.write_header(&mut headers, 13)?;
+
+    headers.write_all(b"Resent-Message-ID: ")?;
+    MessageId::new(message_id).write_header(&mut headers, 19)?;
+
+    Ok(headers)
+}
+
+#[cfg(test)]
+mod tests {
+    use mail_builder::headers::date::Date;
+
+    use super::write_resent_headers;
+
+    #[test]
+    fn resent_headers() {
+        let to = vec![
+            String::from("bob@localhost"),
+            String::from("carol@localhost"),
+        ];
+        let headers = write_resent_headers(
+            Some("Alice"),
+            "alice@localhost",
+            &to,
+            Date::new(0),
+            "id@localhost",
+        )
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(headers).unwrap(),
+            concat!(
+                "Resent-From: \"Alice\" <alice@localhost>\r\n",
+                "Resent-To: <bob@localhost>, <carol@localhost>\r\n",
+                "Resent-Date: Thu, 1 Jan 1970 00:00:00 +0000\r\n",
+                "Resent-Message-ID: <id@localhost>\r\n",
+            )
+        );
+    }
+}
@@ -0,0 +1,81 @@
+//! Module dedicated to sendmail message redirection.
+
+use anyhow::{bail, Context, Result};
+use email::sendmail::SendmailContextSync;
+use log::info;
+use process::Cmd;
+
+/// Redirect the given raw message to the given addresses using
+/// sendmail.
+///
+/// The message is piped to the sendmail command as it is, and the
+/// given addresses are passed to the command as recipients. The `-t`
+/// option is removed from the command, so that recipients are never
+/// read from the headers of the message.
+pub async fn redirect_sendmail_message(
+    ctx: &SendmailContextSync,
+    msg: &[u8],
+    to: &[String],
+) -> Result<()> {
+    info!("redirecting sendmail message to {to:?}");
+
+    to_redirect_cmd(&ctx.sendmail_config.cmd, to)?
+        .run_with(msg)
+        .await
+        .context("cannot run sendmail command")?;
+
+    Ok(())
+}
+
+/// Build the sendmail command sending a message to the given
+/// addresses only.
+///
+/// Pipelines are refused, since the addresses could not be passed to
+/// the right command.
+fn to_redirect_cmd(cmd: &Cmd, to: &[String]) -> Result<Cmd> {
+    let Cmd::SingleCmd(cmd) = cmd else {
+        bail!("cannot redirect message: sendmail command cannot be a pipeline");
+    };
+
+    let mut cmd = cmd
+        .split(' ')
+        .filter(|arg| *arg != "-t")
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    for addr in to {
+        if addr.starts_with('-') {
+            bail!("cannot redirect message: invalid address {addr}");
+        }
+
+        cmd.push_str(" '");
+        cmd.push_str(&addr.replace('\'', r"'\''"));
+        cmd.push('\'');
+    }
+
+    Ok(Cmd::from(cmd))
+}
+
+#[cfg(test)]
+mod tests {
+    use process::Cmd;
+
+    use super::to_redirect_cmd;
+
+    #[test]
+    fn redirect_cmd() {
+        let to = vec![String::from("bob@localhost"), String::from("o'k@localhost")];
+
+        let cmd = to_redirect_cmd(&Cmd::from("/usr/sbin/sendmail -oi -t"), &to).unwrap();
+        assert_eq!(
+            cmd.to_string(),
+            r"/usr/sbin/sendmail -oi 'bob@localhost' 'o'\''k@localhost'"
+        );
+
+        let to = vec![String::from("-oQ@localhost")];
+        assert!(to_redirect_cmd(&Cmd::from("sendmail"), &to).is_err());
+
+        let pipeline = Cmd::from(vec!["cat", "sendmail"]);
+        assert!(to_redirect_cmd(&pipeline, &[]).is_err());
+    }
+}
@@ -0,0 +1,46 @@
+//! Module dedicated to SMTP message redirection.
+
+use anyhow::{Context, Result};
+use email::smtp::{build_client, SmtpContextSync};
+use log::info;
+use mail_send::{smtp::message::Message as SmtpMessage, SmtpClientBuilder};
+
+/// Redirect the given raw message using SMTP.
+///
+/// The SMTP client of the context derives the envelope recipients
+/// from the To, Cc and Bcc headers, which would send the message
+/// back to its original recipients. A dedicated client is used
+/// instead, with the redirection addresses as envelope recipients.
+pub async fn redirect_smtp_message(
+    ctx: &SmtpContextSync,
+    msg: &[u8],
+    from: &str,
+    to: &[String],
+) -> Result<()> {
+    info!("redirecting smtp message");
+
+    let smtp_config = ctx.lock().await.smtp_config.clone();
+
+    let mut client_builder = SmtpClientBuilder::new(smtp_config.host.clone(), smtp_config.port)
+        .credentials(smtp_config.credentials().await?)
+        .implicit_tls(!smtp_config.is_start_tls_encryption_enabled());
+
+    if smtp_config.is_encryption_disabled() {
+        client_builder = client_builder.allow_invalid_certs();
+    }
+
+    let (_, mut client) = build_client(&smtp_config, client_builder).await?;
+
+    let msg = SmtpMessage {
+        mail_from: from.into(),
+        rcpt_to: to.iter().map(|addr| addr.as_str().into()).collect(),
+        body: msg.into(),
+    };
+
+    client
+        .send(msg)
+        .await
+        .context("cannot send redirected message")?;
+
+    Ok(())
+}
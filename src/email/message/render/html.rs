@@ -0,0 +1,1020 @@
+//! Module dedicated to HTML to text rendering.
+//!
+//! The renderer is voluntarily lenient: it parses the HTML into a
+//! light tree (unknown or misplaced tags are tolerated), then renders
+//! it as plain text. Links are rendered as numbered footnotes, lists
+//! are prefixed by bullets or numbers, quotes are prefixed by `>` and
+//! tables are aligned into columns. Text is wrapped to the given
+//! width, 0 disabling the wrapping.
+//!
+//! The depth of the tree is capped (see [`MAX_DEPTH`]): elements
+//! nested deeper are flattened, so that hostile messages cannot
+//! overflow the stack of the recursive renderer.
+
+use std::{collections::HashMap, mem};
+
+use unicode_width::UnicodeWidthStr;
+
+/// Render the given HTML as plain text, wrapped to the given width.
+pub fn render_html(html: &str, width: usize) -> String {
+    let root = parse(html);
+    let mut renderer = Renderer::default();
+    let (mut lines, _) = renderer.render_blocks(&root.children, width, false);
+
+    if !renderer.links.is_empty() {
+        lines.push(String::new());
+        for (i, link) in renderer.links.iter().enumerate() {
+            lines.push(format!("[{}] {link}", i + 1));
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// The maximum depth of the HTML tree. Elements nested deeper are
+/// flattened into their ancestor at this depth.
+const MAX_DEPTH: usize = 64;
+
+const VOID_ELEMENTS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+const RAW_TEXT_ELEMENTS: [&str; 2] = ["script", "style"];
+
+const SKIPPED_ELEMENTS: [&str; 7] = [
+    "head", "title", "script", "style", "template", "noscript", "svg",
+];
+
+const BLOCK_ELEMENTS: [&str; 36] = [
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "body",
+    "center",
+    "dd",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "html",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "summary",
+    "table",
+    "tr",
+    "ul",
+];
+
+/// Represents a node of the HTML tree.
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    Element(Element),
+}
+
+/// Represents an HTML element.
+#[derive(Debug, Default)]
+struct Element {
+    name: String,
+    attrs: Vec<(String, String)>,
+    children: Vec<Node>,
+}
+
+impl Element {
+    fn new(name: impl ToString, attrs: Vec<(String, String)>) -> Self {
+        Self {
+            name: name.to_string(),
+            attrs,
+            children: Vec::new(),
+        }
+    }
+
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, val)| val.as_str())
+    }
+
+    fn is_block(&self) -> bool {
+        BLOCK_ELEMENTS.contains(&self.name.as_str())
+    }
+
+    fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|node| match node {
+            Node::Element(el) => Some(el),
+            Node::Text(_) => None,
+        })
+    }
+
+    fn contains(&self, name: &str) -> bool {
+        self.elements()
+            .any(|el| el.name == name || el.contains(name))
+    }
+}
+
+impl Drop for Element {
+    // drops the tree iteratively, the default drop is recursive
+    fn drop(&mut self) {
+        let mut nodes = mem::take(&mut self.children);
+
+        while let Some(node) = nodes.pop() {
+            if let Node::Element(mut el) = node {
+                nodes.append(&mut el.children);
+            }
+        }
+    }
+}
+
+/// Represents a token of the HTML source.
+#[derive(Debug, Eq, PartialEq)]
+enum Token {
+    Start(String, Vec<(String, String)>, bool),
+    End(String),
+    Text(String),
+}
+
+/// Split the given HTML into tokens. Comments, doctypes and the
+/// content of raw text elements (scripts and styles) are skipped.
+fn tokenize(html: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        let Some(pos) = rest.find('<') else {
+            tokens.push(Token::Text(decode_entities(rest)));
+            break;
+        };
+
+        if pos > 0 {
+            tokens.push(Token::Text(decode_entities(&rest[..pos])));
+            rest = &rest[pos..];
+        }
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment
+                .find("-->")
+                .map(|end| &comment[end + 3..])
+                .unwrap_or_default();
+            continue;
+        }
+
+        let next = rest[1..].chars().next();
+
+        if matches!(next, Some('!' | '?')) {
+            rest = rest
+                .find('>')
+                .map(|end| &rest[end + 1..])
+                .unwrap_or_default();
+            continue;
+        }
+
+        if let Some(tag) = rest.strip_prefix("</") {
+            let end = tag.find('>').unwrap_or(tag.len());
+            let name = tag[..end].trim().to_ascii_lowercase();
+            tokens.push(Token::End(name));
+            rest = tag.get(end + 1..).unwrap_or_default();
+            continue;
+        }
+
+        if !next.is_some_and(|c| c.is_ascii_alphabetic()) {
+            tokens.push(Token::Text(String::from("<")));
+            rest = &rest[1..];
+            continue;
+        }
+
+        let (name, attrs, self_closing, len) = parse_tag(rest);
+        rest = &rest[len..];
+
+        if RAW_TEXT_ELEMENTS.contains(&name.as_str()) && !self_closing {
+            let closing = format!("</{name}");
+            rest = rest
+                .as_bytes()
+                .windows(closing.len())
+                .position(|w| w.eq_ignore_ascii_case(closing.as_bytes()))
+                .map(|end| &rest[end..])
+                .unwrap_or_default();
+        }
+
+        tokens.push(Token::Start(name, attrs, self_closing));
+    }
+
+    tokens
+}
+
+/// Parse the start tag at the beginning of the given HTML. Return
+/// its name, its attributes, whether it is self-closing and its
+/// length.
+fn parse_tag(html: &str) -> (String, Vec<(String, String)>, bool, usize) {
+    let mut chars = html.char_indices().skip(1).peekable();
+    let mut name = String::new();
+    let mut attrs = Vec::new();
+    let mut self_closing = false;
+
+    while let Some((_, c)) = chars.next_if(|(_, c)| !c.is_whitespace() && !matches!(c, '>' | '/')) {
+        name.push(c.to_ascii_lowercase());
+    }
+
+    loop {
+        while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+
+        let Some((i, c)) = chars.next() else {
+            return (name, attrs, self_closing, html.len());
+        };
+
+        match c {
+            '>' => return (name, attrs, self_closing, i + 1),
+            '/' => self_closing = true,
+            _ => {
+                self_closing = false;
+
+                let mut key = String::from(c.to_ascii_lowercase());
+                while let Some((_, c)) =
+                    chars.next_if(|(_, c)| !c.is_whitespace() && !matches!(c, '>' | '/' | '='))
+                {
+                    key.push(c.to_ascii_lowercase());
+                }
+
+                while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+
+                let mut val = String::new();
+                if chars.next_if(|(_, c)| *c == '=').is_some() {
+                    while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+
+                    match chars.next_if(|(_, c)| matches!(c, '"' | '\'')) {
+                        Some((_, quote)) => {
+                            while let Some((_, c)) = chars.next_if(|(_, c)| *c != quote) {
+                                val.push(c);
+                            }
+                            chars.next();
+                        }
+                        None => {
+                            while let Some((_, c)) =
+                                chars.next_if(|(_, c)| !c.is_whitespace() && *c != '>')
+                            {
+                                val.push(c);
+                            }
+                        }
+                    }
+                }
+
+                attrs.push((key, decode_entities(&val)));
+            }
+        }
+    }
+}
+
+/// Parse the given HTML into a tree.
+///
+/// Unclosed elements are implicitly closed, following a simplified
+/// version of the HTML rules (list items, table rows and cells,
+/// paragraphs). Elements deeper than [`MAX_DEPTH`] are flattened,
+/// block ones being replaced by line breaks.
+fn parse(html: &str) -> Element {
+    let mut stack = vec![Element::new("", Vec::new())];
+    // open elements that have been flattened, by name
+    let mut flattened: HashMap<String, usize> = HashMap::new();
+
+    fn close(stack: &mut Vec<Element>, name: &str, boundaries: &[&str]) {
+        let pos = stack
+            .iter()
+            .rposition(|el| el.name == name || boundaries.contains(&el.name.as_str()));
+
+        if let Some(pos) = pos.filter(|pos| *pos > 0 && stack[*pos].name == name) {
+            while stack.len() > pos {
+                let el = stack.pop().unwrap();
+                stack.last_mut().unwrap().children.push(Node::Element(el));
+            }
+        }
+    }
+
+    for token in tokenize(html) {
+        match token {
+            Token::Text(text) => {
+                stack.last_mut().unwrap().children.push(Node::Text(text));
+            }
+            Token::Start(name, attrs, self_closing) => {
+                match name.as_str() {
+                    "li" => close(&mut stack, "li", &["ul", "ol"]),
+                    "dt" | "dd" => {
+                        close(&mut stack, "dt", &["dl"]);
+                        close(&mut stack, "dd", &["dl"]);
+                    }
+                    "tr" => close(&mut stack, "tr", &["table"]),
+                    "td" | "th" => {
+                        close(&mut stack, "td", &["tr", "table"]);
+                        close(&mut stack, "th", &["tr", "table"]);
+                    }
+                    _ => (),
+                }
+
+                let el = Element::new(&name, attrs);
+
+                if el.is_block() {
+                    close(&mut stack, "p", &["div", "td", "th", "li", "blockquote"]);
+                }
+
+                if self_closing || VOID_ELEMENTS.contains(&name.as_str()) {
+                    stack.last_mut().unwrap().children.push(Node::Element(el));
+                } else if stack.len() > MAX_DEPTH {
+                    if el.is_block() {
+                        let br = Element::new("br", Vec::new());
+                        stack.last_mut().unwrap().children.push(Node::Element(br));
+                    }
+                    *flattened.entry(name).or_default() += 1;
+                } else {
+                    stack.push(el);
+                }
+            }
+            Token::End(name) => match flattened.get_mut(&name) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    if BLOCK_ELEMENTS.contains(&name.as_str()) {
+                        let br = Element::new("br", Vec::new());
+                        stack.last_mut().unwrap().children.push(Node::Element(br));
+                    }
+                }
+                _ => close(&mut stack, &name, &[]),
+            },
+        }
+    }
+
+    while stack.len() > 1 {
+        let el = stack.pop().unwrap();
+        stack.last_mut().unwrap().children.push(Node::Element(el));
+    }
+
+    stack.pop().unwrap()
+}
+
+/// Represents a rendered block of lines. Spaced blocks are separated
+/// from their siblings by an empty line.
+struct Block {
+    lines: Vec<String>,
+    spaced: bool,
+}
+
+#[derive(Debug, Default)]
+struct Renderer {
+    links: Vec<String>,
+}
+
+impl Renderer {
+    /// Render the given nodes as lines. Return the lines and whether
+    /// they contain spaced blocks.
+    fn render_blocks(
+        &mut self,
+        nodes: &[Node],
+        width: usize,
+        in_list: bool,
+    ) -> (Vec<String>, bool) {
+        let mut blocks = Vec::new();
+        let mut inline = String::new();
+
+        for node in nodes {
+            match node {
+                Node::Text(text) => inline.push_str(&text.replace(['\r', '\n', '\t'], " ")),
+                Node::Element(el) if !el.is_block() => self.render_inline(el, &mut inline),
+                Node::Element(el) => {
+                    push_inline(&mut blocks, &mut inline, width);
+                    if let Some(block) = self.render_block(el, width, in_list) {
+                        blocks.push(block);
+                    }
+                }
+            }
+        }
+
+        push_inline(&mut blocks, &mut inline, width);
+
+        let spaced = blocks.iter().any(|block| block.spaced);
+        let mut lines = Vec::new();
+        let mut prev_spaced = false;
+
+        for (i, block) in blocks.into_iter().enumerate() {
+            if i > 0 && (prev_spaced || block.spaced) {
+                lines.push(String::new());
+            }
+            prev_spaced = block.spaced;
+            lines.extend(block.lines);
+        }
+
+        (lines, spaced)
+    }
+
+    fn render_block(&mut self, el: &Element, width: usize, in_list: bool) -> Option<Block> {
+        let name = el.name.as_str();
+
+        let block = match name {
+            _ if SKIPPED_ELEMENTS.contains(&name) => return None,
+            "p" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "dt" | "summary" => {
+                let mut text = String::new();
+                self.render_inline_children(el, &mut text);
+
+                if let Some(level) = name.strip_prefix('h').and_then(|l| l.parse().ok()) {
+                    text = format!("{} {text}", "#".repeat(level));
+                }
+
+                Block {
+                    lines: wrap(&text, width),
+                    spaced: !matches!(name, "dt" | "summary"),
+                }
+            }
+            "dd" => {
+                let (lines, spaced) =
+                    self.render_blocks(&el.children, sub_width(width, 4), in_list);
+                Block {
+                    lines: indent(lines, "    ", "    "),
+                    spaced,
+                }
+            }
+            "blockquote" => {
+                let (lines, _) = self.render_blocks(&el.children, sub_width(width, 2), false);
+                let lines = lines
+                    .into_iter()
+                    .map(|line| {
+                        if line.is_empty() {
+                            String::from(">")
+                        } else {
+                            format!("> {line}")
+                        }
+                    })
+                    .collect();
+                Block {
+                    lines,
+                    spaced: true,
+                }
+            }
+            "pre" => {
+                let mut text = String::new();
+                raw_text(el, &mut text);
+                let text = text.strip_prefix('\n').unwrap_or(&text);
+                Block {
+                    lines: text.trim_end().lines().map(ToOwned::to_owned).collect(),
+                    spaced: true,
+                }
+            }
+            "hr" => Block {
+                lines: vec!["─".repeat(if width == 0 { 40 } else { width })],
+                spaced: true,
+            },
+            "ul" | "ol" => Block {
+                lines: self.render_list(el, width),
+                spaced: !in_list,
+            },
+            "li" => Block {
+                lines: self.render_list_item(el, "• ", width),
+                spaced: false,
+            },
+            "table" => self.render_table(el, width),
+            _ => {
+                let (lines, spaced) = self.render_blocks(&el.children, width, in_list);
+                Block { lines, spaced }
+            }
+        };
+
+        Some(block).filter(|block| !block.lines.is_empty())
+    }
+
+    fn render_inline(&mut self, el: &Element, text: &mut String) {
+        match el.name.as_str() {
+            name if SKIPPED_ELEMENTS.contains(&name) => (),
+            "br" => text.push('\n'),
+            "img" => {
+                if let Some(alt) = el.attr("alt").map(str::trim).filter(|alt| !alt.is_empty()) {
+                    text.push_str(&format!("[{alt}]"));
+                }
+            }
+            "a" => {
+                let mut label = String::new();
+                self.render_inline_children(el, &mut label);
+                text.push_str(&label);
+
+                let href = el.attr("href").map(str::trim).unwrap_or_default();
+                let label = label.trim();
+                let is_local = href.is_empty() || href.starts_with('#');
+                let is_script = href.to_ascii_lowercase().starts_with("javascript:");
+                let is_label = label == href || Some(label) == href.strip_prefix("mailto:");
+
+                if !is_local && !is_script && !is_label {
+                    let id = self.link_id(href);
+                    text.push_str(&format!("[{id}]"));
+                }
+            }
+            _ if el.is_block() => {
+                text.push('\n');
+                self.render_inline_children(el, text);
+                text.push('\n');
+            }
+            _ => self.render_inline_children(el, text),
+        }
+    }
+
+    fn render_inline_children(&mut self, el: &Element, text: &mut String) {
+        for node in &el.children {
+            match node {
+                Node::Text(t) => text.push_str(&t.replace(['\r', '\n', '\t'], " ")),
+                Node::Element(el) => self.render_inline(el, text),
+            }
+        }
+    }
+
+    fn render_list(&mut self, el: &Element, width: usize) -> Vec<String> {
+        let ordered = el.name == "ol";
+        let mut index: usize = el
+            .attr("start")
+            .and_then(|start| start.trim().parse().ok())
+            .unwrap_or(1);
+        let mut lines = Vec::new();
+
+        for node in &el.children {
+            match node {
+                Node::Element(li) if li.name == "li" => {
+                    let marker = if ordered {
+                        format!("{index}. ")
+                    } else {
+                        String::from("• ")
+                    };
+                    index += 1;
+                    lines.extend(self.render_list_item(li, &marker, width));
+                }
+                Node::Element(el) => {
+                    if let Some(block) = self.render_block(el, sub_width(width, 2), true) {
+                        lines.extend(indent(block.lines, "  ", "  "));
+                    }
+                }
+                Node::Text(text) => lines.extend(wrap(text, width)),
+            }
+        }
+
+        lines
+    }
+
+    fn render_list_item(&mut self, li: &Element, marker: &str, width: usize) -> Vec<String> {
+        let pad = " ".repeat(marker.width());
+        let (lines, _) = self.render_blocks(&li.children, sub_width(width, pad.len()), true);
+
+        if lines.is_empty() {
+            vec![marker.trim_end().to_owned()]
+        } else {
+            indent(lines, marker, &pad)
+        }
+    }
+
+    fn render_table(&mut self, table: &Element, width: usize) -> Block {
+        let mut rows = Vec::new();
+        collect_rows(table, &mut rows);
+
+        let columns = rows.iter().map(|row| cells(row).len()).max().unwrap_or(0);
+        let is_layout = columns <= 1
+            || rows
+                .iter()
+                .flat_map(|row| cells(row))
+                .any(|cell| cell.contains("table"));
+
+        // tables used for layout purpose are rendered as a sequence
+        // of blocks
+        if is_layout {
+            let mut lines = Vec::new();
+            let mut spaced = false;
+
+            for cell in rows.iter().flat_map(|row| cells(row)) {
+                let (cell_lines, cell_spaced) = self.render_blocks(&cell.children, width, false);
+                if cell_lines.is_empty() {
+                    continue;
+                }
+                if !lines.is_empty() && (spaced || cell_spaced) {
+                    lines.push(String::new());
+                }
+                spaced = cell_spaced;
+                lines.extend(cell_lines);
+            }
+
+            return Block { lines, spaced };
+        }
+
+        let mut texts: Vec<Vec<String>> = Vec::new();
+        for row in &rows {
+            let mut row_texts: Vec<String> = cells(row)
+                .into_iter()
+                .map(|cell| {
+                    let mut text = String::new();
+                    self.render_inline_children(cell, &mut text);
+                    text.split_whitespace().collect::<Vec<_>>().join(" ")
+                })
+                .collect();
+            row_texts.resize(columns, String::new());
+            texts.push(row_texts);
+        }
+
+        let mut widths = vec![0; columns];
+        for row in &texts {
+            for (i, text) in row.iter().enumerate() {
+                widths[i] = widths[i].max(text.width());
+            }
+        }
+
+        if width > 0 {
+            shrink_widths(&mut widths, width.saturating_sub((columns - 1) * 3));
+        }
+
+        let has_head = rows.first().is_some_and(|row| {
+            let cells = cells(row);
+            !cells.is_empty() && cells.iter().all(|cell| cell.name == "th")
+        });
+
+        let mut lines = Vec::new();
+        for (i, row) in texts.iter().enumerate() {
+            let cells: Vec<Vec<String>> = row
+                .iter()
+                .enumerate()
+                .map(|(i, text)| wrap(text, widths[i]))
+                .collect();
+            let height = cells.iter().map(Vec::len).max().unwrap_or(0).max(1);
+
+            for j in 0..height {
+                let line = cells
+                    .iter()
+                    .enumerate()
+                    .map(|(i, cell)| {
+                        let text = cell.get(j).map(String::as_str).unwrap_or_default();
+                        let pad = widths[i].saturating_sub(text.width());
+                        format!("{text}{}", " ".repeat(pad))
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" │ ");
+                lines.push(line.trim_end().to_owned());
+            }
+
+            if i == 0 && has_head {
+                let sep = widths
+                    .iter()
+                    .map(|w| "─".repeat(*w))
+                    .collect::<Vec<_>>()
+                    .join("─┼─");
+                lines.push(sep);
+            }
+        }
+
+        Block {
+            lines,
+            spaced: true,
+        }
+    }
+
+    /// Get the footnote id of the given link, registering it if
+    /// needed.
+    fn link_id(&mut self, href: &str) -> usize {
+        match self.links.iter().position(|link| link == href) {
+            Some(pos) => pos + 1,
+            None => {
+                self.links.push(href.to_owned());
+                self.links.len()
+            }
+        }
+    }
+}
+
+/// Wrap the pending inline text into a block, if any.
+fn push_inline(blocks: &mut Vec<Block>, inline: &mut String, width: usize) {
+    let lines = wrap(inline, width);
+    inline.clear();
+
+    if !lines.is_empty() {
+        blocks.push(Block {
+            lines,
+            spaced: false,
+        });
+    }
+}
+
+/// Shrink the widest of the given column widths until their sum
+/// fits the given available width. Columns are never shrunk below 3.
+fn shrink_widths(widths: &mut [usize], available: usize) {
+    let capped = |widths: &[usize], cap: usize| widths.iter().map(|w| cap.min(*w)).sum::<usize>();
+
+    if widths.iter().sum::<usize>() <= available {
+        return;
+    }
+
+    // finds the largest cap that fits by bisection, the cap of 3
+    // is used when nothing fits
+    let mut min = 3;
+    let mut max = widths.iter().copied().max().unwrap_or_default();
+
+    while max > min + 1 {
+        let cap = (min + max) / 2;
+        if capped(widths, cap) <= available {
+            min = cap;
+        } else {
+            max = cap;
+        }
+    }
+
+    let mut remaining = available.saturating_sub(capped(widths, min));
+
+    for w in widths.iter_mut() {
+        if *w > min && remaining > 0 {
+            *w = min + 1;
+            remaining -= 1;
+        } else {
+            *w = min.min(*w);
+        }
+    }
+}
+
+fn cells(row: &Element) -> Vec<&Element> {
+    row.elements()
+        .filter(|el| matches!(el.name.as_str(), "td" | "th"))
+        .collect()
+}
+
+fn collect_rows<'a>(el: &'a Element, rows: &mut Vec<&'a Element>) {
+    for child in el.elements() {
+        match child.name.as_str() {
+            "tr" => rows.push(child),
+            "thead" | "tbody" | "tfoot" => collect_rows(child, rows),
+            _ => (),
+        }
+    }
+}
+
+/// Collect the text of the given element, preserving whitespaces.
+fn raw_text(el: &Element, text: &mut String) {
+    for node in &el.children {
+        match node {
+            Node::Text(t) => text.push_str(&t.replace('\r', "")),
+            Node::Element(el) if el.name == "br" => text.push('\n'),
+            Node::Element(el) => raw_text(el, text),
+        }
+    }
+}
+
+/// Wrap the given text to the given width. Words are never broken,
+/// and a width of 0 disables the wrapping. Hard line breaks (`\n`)
+/// are preserved, consecutive empty lines are merged.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        let mut line_width = 0;
+
+        for word in paragraph.split_whitespace() {
+            let word_width = word.width();
+
+            if width > 0 && line_width > 0 && line_width + 1 + word_width > width {
+                lines.push(line);
+                line = String::new();
+                line_width = 0;
+            }
+
+            if line_width > 0 {
+                line.push(' ');
+                line_width += 1;
+            }
+
+            line.push_str(word);
+            line_width += word_width;
+        }
+
+        if !line.is_empty() || lines.last().is_some_and(|last| !last.is_empty()) {
+            lines.push(line);
+        }
+    }
+
+    while lines.last().is_some_and(String::is_empty) {
+        lines.pop();
+    }
+
+    lines
+}
+
+/// Prefix the first given line with the given marker, and the other
+/// non-empty lines with the given padding.
+fn indent(lines: Vec<String>, marker: &str, pad: &str) -> Vec<String> {
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(i, line)| match i {
+            0 => format!("{marker}{line}"),
+            _ if line.is_empty() => line,
+            _ => format!("{pad}{line}"),
+        })
+        .collect()
+}
+
+fn sub_width(width: usize, n: usize) -> usize {
+    if width == 0 {
+        0
+    } else {
+        width.saturating_sub(n).max(1)
+    }
+}
+
+/// Decode HTML character references of the given text.
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(pos) = rest.find('&') {
+        decoded.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+
+        let entity = rest[1..]
+            .find(';')
+            .filter(|end| *end <= 32)
+            .map(|end| &rest[1..end + 1]);
+
+        let c = entity.and_then(|entity| match entity.strip_prefix('#') {
+            Some(code) => match code.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                None => code.parse().ok(),
+            }
+            .and_then(char::from_u32)
+            .map(String::from),
+            None => decode_named_entity(entity).map(String::from),
+        });
+
+        match (entity, c) {
+            (Some(entity), Some(c)) => {
+                decoded.push_str(&c);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    decoded.push_str(rest);
+    decoded
+}
+
+fn decode_named_entity(name: &str) -> Option<&'static str> {
+    let c = match name {
+        "amp" => "&",
+        "lt" => "<",
+        "gt" => ">",
+        "quot" => "\"",
+        "apos" => "'",
+        "nbsp" | "ensp" | "emsp" | "thinsp" => " ",
+        "shy" | "zwnj" | "zwj" | "lrm" | "rlm" => "",
+        "copy" => "©",
+        "reg" => "®",
+        "trade" => "™",
+        "hellip" => "…",
+        "mdash" => "—",
+        "ndash" => "–",
+        "lsquo" => "‘",
+        "rsquo" => "’",
+        "sbquo" => "‚",
+        "ldquo" => "“",
+        "rdquo" => "”",
+        "bdquo" => "„",
+        "laquo" => "«",
+        "raquo" => "»",
+        "bull" => "•",
+        "middot" => "·",
+        "deg" => "°",
+        "euro" => "€",
+        "pound" => "£",
+        "yen" => "¥",
+        "cent" => "¢",
+        "sect" => "§",
+        "para" => "¶",
+        "times" => "×",
+        "divide" => "÷",
+        "larr" => "←",
+        "rarr" => "→",
+        "uarr" => "↑",
+        "darr" => "↓",
+        _ => return None,
+    };
+
+    Some(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render_html;
+
+    #[test]
+    fn paragraphs_and_wrap() {
+        let html = "<p>Hello   <b>world</b>,\nhow are you?</p><p>Fine &amp; you?</p>";
+
+        assert_eq!(
+            render_html(html, 12),
+            concat!("Hello world,\n", "how are you?\n", "\n", "Fine & you?"),
+        );
+    }
+
+    #[test]
+    fn links_as_footnotes() {
+        let html = concat!("<div>See <a href=\"https://a.org/?x=1&amp;y=2\">this</a>\n", "and <a href=\"https://b.org\">that</a>, or <a href=\"https://a.org/?x=1&amp;y=2\">this</a>.</div>\n", "<a href=\"https://c.org\">https://c.org</a>");
+
+        assert_eq!(
+            render_html(html, 0),
+            concat!(
+                "See this[1] and that[2], or this[1].\n",
+                "https://c.org\n",
+                "\n",
+                "[1] https://a.org/?x=1&y=2\n",
+                "[2] https://b.org"
+            ),
+        );
+    }
+
+    #[test]
+    fn lists() {
+        let html = concat!(
+            "<ul><li>one<li>two<ol start=3><li>three</li><li>four</li></ol></ul>\n",
+            "<p>end</p>"
+        );
+
+        assert_eq!(
+            render_html(html, 0),
+            concat!(
+                "• one\n",
+                "• two\n",
+                "  3. three\n",
+                "  4. four\n",
+                "\n",
+                "end"
+            ),
+        );
+    }
+
+    #[test]
+    fn quotes() {
+        let html = "<p>Reply</p><blockquote><p>Quoted text</p><p>Again</p></blockquote>";
+
+        assert_eq!(
+            render_html(html, 0),
+            concat!("Reply\n", "\n", "> Quoted text\n", ">\n", "> Again"),
+        );
+    }
+
+    #[test]
+    fn tables() {
+        let html = concat!(
+            "<table><tr><th>Name</th><th>Qty</th></tr>\n",
+            "<tr><td>Apple</td><td>2</td></tr>\n",
+            "<tr><td>Banana split</td><td>10</td></tr></table>"
+        );
+
+        assert_eq!(
+            render_html(html, 0),
+            concat!(
+                "Name         │ Qty\n",
+                "─────────────┼────\n",
+                "Apple        │ 2\n",
+                "Banana split │ 10"
+            ),
+        );
+    }
+
+    #[test]
+    fn layout_tables() {
+        let html = concat!(
+            "<html><head><style>p { color: red; }</style></head>\n",
+            "<body><table><tr><td><p>First</p></td></tr>\n",
+            "<tr><td><p>Second</p></td></tr></table></body></html>"
+        );
+
+        assert_eq!(render_html(html, 0), concat!("First\n", "\n", "Second"));
+    }
+
+    #[test]
+    fn deep_nesting() {
+        let html = format!(
+            "{}text{}",
+            "<div>".repeat(100_000),
+            "</div>".repeat(100_000)
+        );
+        assert_eq!(render_html(&html, 0), "text");
+
+        let html = format!("{}</div>after", "<div><p>text</p>".repeat(100));
+        assert_eq!(render_html(&html, 0), "text\n\n".repeat(100) + "after");
+    }
+}
@@ -0,0 +1,143 @@
+pub mod html;
+
+use anyhow::Result;
+use clap::ValueEnum;
+use email::{
+    account::config::AccountConfig, email::config::EmailTextPlainFormat, message::Message,
+};
+use mml::{message::FilterParts, MimeInterpreterBuilder};
+use serde::{Deserialize, Serialize};
+use terminal_size::terminal_size;
+
+use crate::{message::part::has_part, ui::table::DEFAULT_TERM_WIDTH};
+
+use self::html::render_html;
+
+/// Represents the preferred part used to render the body of a
+/// message.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum MessageRender {
+    /// Prefer the text/plain part, the text/html part is rendered as
+    /// text only when no text/plain part exists.
+    #[default]
+    Plain,
+
+    /// Prefer the text/html part rendered as text, the text/plain
+    /// part is used only when no text/html part exists.
+    Html,
+}
+
+/// Build the read template of the given message, rendering its
+/// text/html part as text depending on the given preference.
+///
+/// The given function customizes the interpreter used to build
+/// headers (and the whole template when the text/html part does not
+/// need to be rendered).
+///
+/// Which parts exist is found from the MIME structure, so that the
+/// message is interpreted (and decrypted) at most twice: once for
+/// the text/html part, once for the headers and the attachments.
+pub async fn to_read_tpl(
+    email: &Message<'_>,
+    config: &AccountConfig,
+    render: MessageRender,
+    with_interpreter: impl Fn(MimeInterpreterBuilder) -> MimeInterpreterBuilder,
+) -> Result<String> {
+    let parsed = email.parsed()?;
+    let has_html = has_part(parsed, "text/html");
+
+    // parts of encrypted messages are only known once decrypted, the
+    // html part is then looked for only if explicitly preferred
+    let should_render = match render {
+        MessageRender::Html => has_html || has_part(parsed, "multipart/encrypted"),
+        MessageRender::Plain => has_html && !has_part(parsed, "text/plain"),
+    };
+
+    if !should_render {
+        return email.to_read_tpl(config, with_interpreter).await;
+    }
+
+    let html = email
+        .to_read_tpl(config, |tpl| {
+            tpl.with_hide_all_headers()
+                .with_filter_parts(FilterParts::Only("text/html".into()))
+        })
+        .await?;
+
+    if html.trim().is_empty() {
+        return email.to_read_tpl(config, with_interpreter).await;
+    }
+
+    let width = match config.get_message_read_format() {
+        EmailTextPlainFormat::Fixed(width) => width,
+        EmailTextPlainFormat::Flowed => 0,
+        EmailTextPlainFormat::Auto => terminal_size()
+            .map(|(w, _)| w.0 as usize)
+            .unwrap_or(DEFAULT_TERM_WIDTH),
+    };
+
+    let headers_and_attachments = email
+        .to_read_tpl(config, |tpl| {
+            with_interpreter(tpl).with_filter_parts(FilterParts::Exclude(vec![
+                "text/plain".into(),
+                "text/html".into(),
+            ]))
+        })
+        .await?;
+    let (headers, attachments) = split_headers(&headers_and_attachments);
+
+    let mut tpl = String::new();
+
+    if !headers.trim().is_empty() {
+        tpl.push_str(headers.trim_end());
+        tpl.push_str("\n\n");
+    }
+
+    tpl.push_str(&render_html(&html, width));
+    tpl.push('\n');
+
+    if !attachments.trim().is_empty() {
+        tpl.push('\n');
+        tpl.push_str(attachments.trim_end());
+        tpl.push('\n');
+    }
+
+    Ok(tpl)
+}
+
+/// Split the given template into its headers and its body.
+///
+/// Text parts being excluded, the body is only made of MML parts:
+/// when the template does not start with one, it starts with
+/// headers, separated from the body by an empty line.
+fn split_headers(tpl: &str) -> (&str, &str) {
+    if tpl.trim().is_empty() || tpl.starts_with("<#") {
+        return ("", tpl);
+    }
+
+    tpl.split_once("\n\n").unwrap_or((tpl, ""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::split_headers;
+
+    #[test]
+    fn headers() {
+        let tpl = "From: a@localhost\nSubject: Hi\n\n<#part filename=a.pdf><#/part>\n";
+        assert_eq!(
+            split_headers(tpl),
+            (
+                "From: a@localhost\nSubject: Hi",
+                "<#part filename=a.pdf><#/part>\n"
+            ),
+        );
+
+        let tpl = "<#part filename=a.pdf><#/part>\n";
+        assert_eq!(split_headers(tpl), ("", tpl));
+
+        let tpl = "From: a@localhost\n\n\n";
+        assert_eq!(split_headers(tpl), ("From: a@localhost", "\n"));
+    }
+}
@@ -51,10 +51,10 @@ impl TemplateReplyCommand {
         info!("executing reply template command");
 
         let folder = &self.folder.name;
-        let id = self.envelope.id;
+        let id = self.envelope.id.id;
 
         let (toml_account_config, account_config) = config.clone().into_account_configs(
-            self.account.name.as_deref(),
+            self.envelope.account(self.account.name.as_deref())?,
             #[cfg(feature = "account-sync")]
             self.cache.disable,
         )?;
@@ -1,6 +1,6 @@
 use anyhow::Result;
 use clap::Parser;
-use email::{backend::feature::BackendFeatureSource, message::send::SendMessageThenSaveCopy};
+use email::backend::feature::BackendFeatureSource;
 use log::info;
 use mml::MmlCompilerBuilder;
 use std::io::{self, BufRead, IsTerminal};
@@ -9,7 +9,7 @@ use std::io::{self, BufRead, IsTerminal};
 use crate::cache::arg::disable::CacheDisableFlag;
 use crate::{
     account::arg::name::AccountNameFlag, backend::Backend, config::TomlConfig,
-    email::template::arg::TemplateRawArg, printer::Printer,
+    email::template::arg::TemplateRawArg, outbox::send_message_or_queue, printer::Printer,
 };
 
 /// Send a template.
@@ -77,8 +77,12 @@ impl TemplateSendCommand {
 
         let msg = compiler.build(tpl.as_str())?.compile().await?.into_vec()?;
 
-        backend.send_message_then_save_copy(&msg).await?;
-
-        printer.print("Message successfully sent!")
+        match send_message_or_queue(&backend, &msg).await? {
+            Some(entry) => printer.print(format!(
+                "Message could not be sent, queued to the outbox as {}!",
+                entry.short_id()
+            )),
+            None => printer.print("Message successfully sent!"),
+        }
     }
 }
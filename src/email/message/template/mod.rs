@@ -1,2 +1,38 @@
 pub mod arg;
 pub mod command;
+
+use anyhow::Result;
+use email::{account::config::AccountConfig, message::Message};
+use mml::message::FilterHeaders;
+
+/// The headers generated again when the template of a draft is
+/// compiled, and therefore excluded from it.
+const DRAFT_EXCLUDED_HEADERS: [&str; 6] = [
+    "Date",
+    "Message-ID",
+    "MIME-Version",
+    "Content-Type",
+    "Content-Transfer-Encoding",
+    "Content-Disposition",
+];
+
+/// Convert the given draft back to a template.
+///
+/// Unlike read templates, all headers are kept (except the ones
+/// generated at compilation time), the multipart structure is kept
+/// and attachments are saved to the downloads directory, so that the
+/// template compiles back to the same message.
+pub async fn to_draft_tpl(email: &Message<'_>, config: &AccountConfig) -> Result<String> {
+    let excluded_headers = DRAFT_EXCLUDED_HEADERS.map(String::from).to_vec();
+
+    let tpl = email
+        .to_read_tpl(config, |tpl| {
+            tpl.with_show_headers(FilterHeaders::Exclude(excluded_headers.clone()))
+                .with_show_multiparts(true)
+                .with_show_plain_texts_signature(true)
+                .with_save_attachments(true)
+        })
+        .await?;
+
+    Ok(tpl)
+}
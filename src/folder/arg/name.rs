@@ -1,5 +1,5 @@
 use clap::Parser;
-use email::folder::INBOX;
+use email::folder::{DRAFTS, INBOX};
 
 /// The optional folder name flag parser.
 #[derive(Debug, Parser)]
@@ -10,6 +10,15 @@ pub struct FolderNameOptionalFlag {
     pub name: String,
 }
 
+/// The optional drafts folder name flag parser.
+#[derive(Debug, Parser)]
+pub struct DraftsFolderNameOptionalFlag {
+    /// The name of the drafts folder.
+    #[arg(long = "folder", short = 'f')]
+    #[arg(name = "folder_name", value_name = "NAME", default_value = DRAFTS)]
+    pub name: String,
+}
+
 /// The optional folder name argument parser.
 #[derive(Debug, Parser)]
 pub struct FolderNameOptionalArg {
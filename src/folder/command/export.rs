@@ -0,0 +1,93 @@
+use anyhow::{Context, Result};
+use clap::Parser;
+use email::backend::feature::BackendFeatureSource;
+use indicatif::ProgressBar;
+use log::info;
+
+#[cfg(feature = "account-sync")]
+use crate::cache::arg::disable::CacheDisableFlag;
+use crate::{
+    account::arg::name::AccountNameFlag,
+    backend::Backend,
+    config::TomlConfig,
+    folder::arg::name::FolderNameArg,
+    message::archive::{export_messages, ArchiveWriter, ExportArgs},
+    printer::Printer,
+    ui::progress::MAIN_PROGRESS_STYLE,
+};
+
+/// Export a folder to an mbox file, an eml directory or a Maildir.
+///
+/// This command allows you to export all the messages of the given
+/// folder, unedited, alongside with their flags.
+#[derive(Debug, Parser)]
+pub struct FolderExportCommand {
+    #[command(flatten)]
+    pub folder: FolderNameArg,
+
+    #[command(flatten)]
+    pub export: ExportArgs,
+
+    #[cfg(feature = "account-sync")]
+    #[command(flatten)]
+    pub cache: CacheDisableFlag,
+
+    #[command(flatten)]
+    pub account: AccountNameFlag,
+}
+
+impl FolderExportCommand {
+    pub async fn execute(self, printer: &mut impl Printer, config: &TomlConfig) -> Result<()> {
+        info!("executing export folder command");
+
+        let folder = &self.folder.name;
+        let (toml_account_config, account_config) = config.clone().into_account_configs(
+            self.account.name.as_deref(),
+            #[cfg(feature = "account-sync")]
+            self.cache.disable,
+        )?;
+
+        let get_messages_kind = toml_account_config.get_messages_kind();
+        let list_envelopes_kind = toml_account_config.list_envelopes_kind();
+
+        let backend = Backend::new(
+            toml_account_config.clone(),
+            account_config,
+            get_messages_kind.into_iter().chain(list_envelopes_kind),
+            |builder| {
+                builder.set_get_messages(BackendFeatureSource::Context);
+                builder.set_list_envelopes(BackendFeatureSource::Context);
+            },
+        )
+        .await?;
+
+        let mut ids = backend
+            .search_envelopes(folder, None, None, 0, 0)
+            .await?
+            .iter()
+            .map(|envelope| {
+                envelope
+                    .id
+                    .parse::<usize>()
+                    .with_context(|| format!("cannot parse envelope id {}", envelope.id))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        ids.sort();
+
+        let progress = if printer.is_json() {
+            ProgressBar::hidden()
+        } else {
+            ProgressBar::new(ids.len() as u64).with_style(MAIN_PROGRESS_STYLE.clone())
+        };
+
+        let path = &self.export.path;
+        let mut writer = ArchiveWriter::open(self.export.format, path)?;
+        let count = export_messages(&backend, folder, &ids, &mut writer, &progress).await?;
+        writer.finish()?;
+        progress.finish_and_clear();
+
+        printer.print(format!(
+            "Exported {count} message(s) from {folder} to {path:?}!"
+        ))
+    }
+}
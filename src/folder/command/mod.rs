@@ -1,8 +1,10 @@
 mod add;
 mod delete;
+mod export;
 mod expunge;
 mod list;
 mod purge;
+mod stats;
 
 use anyhow::Result;
 use clap::Subcommand;
@@ -10,8 +12,9 @@ use clap::Subcommand;
 use crate::{config::TomlConfig, printer::Printer};
 
 use self::{
-    add::AddFolderCommand, delete::FolderDeleteCommand, expunge::FolderExpungeCommand,
-    list::FolderListCommand, purge::FolderPurgeCommand,
+    add::AddFolderCommand, delete::FolderDeleteCommand, export::FolderExportCommand,
+    expunge::FolderExpungeCommand, list::FolderListCommand, purge::FolderPurgeCommand,
+    stats::FolderStatsCommand,
 };
 
 /// Manage folders.
@@ -26,6 +29,9 @@ pub enum FolderSubcommand {
     #[command(alias = "lst")]
     List(FolderListCommand),
 
+    #[command(alias = "status")]
+    Stats(FolderStatsCommand),
+
     #[command()]
     Expunge(FolderExpungeCommand),
 
@@ -34,6 +40,9 @@ pub enum FolderSubcommand {
 
     #[command(alias = "remove", alias = "rm")]
     Delete(FolderDeleteCommand),
+
+    #[command(arg_required_else_help = true)]
+    Export(FolderExportCommand),
 }
 
 impl FolderSubcommand {
@@ -42,9 +51,11 @@ impl FolderSubcommand {
         match self {
             Self::Add(cmd) => cmd.execute(printer, config).await,
             Self::List(cmd) => cmd.execute(printer, config).await,
+            Self::Stats(cmd) => cmd.execute(printer, config).await,
             Self::Expunge(cmd) => cmd.execute(printer, config).await,
             Self::Purge(cmd) => cmd.execute(printer, config).await,
             Self::Delete(cmd) => cmd.execute(printer, config).await,
+            Self::Export(cmd) => cmd.execute(printer, config).await,
         }
     }
 }
@@ -0,0 +1,94 @@
+use anyhow::Result;
+use clap::Parser;
+use email::{backend::feature::BackendFeatureSource, folder::list::ListFolders};
+use log::{debug, info, warn};
+
+#[cfg(feature = "account-sync")]
+use crate::cache::arg::disable::CacheDisableFlag;
+use crate::{
+    account::arg::name::AccountNameFlag,
+    backend::Backend,
+    config::TomlConfig,
+    folder::stats::{FolderStats, FoldersStats},
+    printer::{PrintTableOpts, Printer},
+    ui::arg::max_width::TableMaxWidthFlag,
+};
+
+/// Show folders statistics.
+///
+/// This command allows you to show the total, unseen and recent
+/// amount of messages of folders, as well as their size. When no
+/// folder is given, statistics of all existing folders are shown.
+/// Folders whose statistics cannot be computed are shown with dashes,
+/// and their error is reported.
+#[derive(Debug, Parser)]
+pub struct FolderStatsCommand {
+    /// The names of the folders.
+    #[arg(name = "folder_names", value_name = "FOLDER")]
+    pub names: Vec<String>,
+
+    #[command(flatten)]
+    pub table: TableMaxWidthFlag,
+
+    #[cfg(feature = "account-sync")]
+    #[command(flatten)]
+    pub cache: CacheDisableFlag,
+
+    #[command(flatten)]
+    pub account: AccountNameFlag,
+}
+
+impl FolderStatsCommand {
+    pub async fn execute(self, printer: &mut impl Printer, config: &TomlConfig) -> Result<()> {
+        info!("executing folder stats command");
+
+        let (toml_account_config, account_config) = config.clone().into_account_configs(
+            self.account.name.as_deref(),
+            #[cfg(feature = "account-sync")]
+            self.cache.disable,
+        )?;
+
+        let list_folders_kind = toml_account_config.list_folders_kind();
+
+        let backend = Backend::new(
+            toml_account_config.clone(),
+            account_config.clone(),
+            list_folders_kind,
+            |builder| builder.set_list_folders(BackendFeatureSource::Context),
+        )
+        .await?;
+
+        let names = if self.names.is_empty() {
+            backend
+                .list_folders()
+                .await?
+                .iter()
+                .map(|folder| folder.name.clone())
+                .collect()
+        } else {
+            self.names
+        };
+
+        let mut stats = Vec::with_capacity(names.len());
+        for name in &names {
+            // a failing folder should not hide statistics of the
+            // other ones
+            match backend.get_folder_stats(name).await {
+                Ok(folder_stats) => stats.push(folder_stats),
+                Err(err) => {
+                    warn!("cannot get folder {name} statistics: {err:#}");
+                    debug!("{err:?}");
+                    stats.push(FolderStats::from_error(name, &err));
+                }
+            }
+        }
+
+        printer.print_table(
+            Box::new(FoldersStats::from_iter(stats)),
+            PrintTableOpts {
+                format: &account_config.get_message_read_format(),
+                max_width: self.table.max_width,
+            },
+        )
+    }
+}
@@ -16,9 +16,20 @@ pub struct FolderConfig {
     pub delete: Option<FolderDeleteConfig>,
     #[cfg(feature = "account-sync")]
     pub sync: Option<FolderSyncConfig>,
+    #[cfg(not(feature = "account-sync"))]
+    pub sync: Option<FolderSyncFilterConfig>,
 }
 
 impl FolderConfig {
+    /// Return `true` if the given folder matches the folder
+    /// synchronization filter.
+    pub fn sync_filter_matches(&self, folder: &str) -> bool {
+        self.sync
+            .as_ref()
+            .map(|sync| sync.filter.matches(folder))
+            .unwrap_or(true)
+    }
+
     pub fn get_used_backends(&self) -> HashSet<&BackendKind> {
         let mut kinds = HashSet::default();
 
@@ -46,6 +57,40 @@ impl FolderConfig {
     }
 }
 
+/// The folder synchronization config, reduced to its filter.
+///
+/// Folder listings spanning several folders follow the filter, even
+/// when the account synchronization feature is disabled.
+#[cfg(not(feature = "account-sync"))]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct FolderSyncFilterConfig {
+    #[serde(default)]
+    pub filter: FolderSyncStrategy,
+}
+
+/// The folder synchronization strategy, mirroring the one of the
+/// account synchronization feature.
+#[cfg(not(feature = "account-sync"))]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FolderSyncStrategy {
+    #[default]
+    All,
+    Include(std::collections::BTreeSet<String>),
+    Exclude(std::collections::BTreeSet<String>),
+}
+
+#[cfg(not(feature = "account-sync"))]
+impl FolderSyncStrategy {
+    pub fn matches(&self, folder: &str) -> bool {
+        match self {
+            Self::All => true,
+            Self::Include(folders) => folders.contains(folder),
+            Self::Exclude(folders) => !folders.contains(folder),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
 pub struct FolderAddConfig {
     pub backend: Option<BackendKind>,
@@ -1,6 +1,7 @@
 pub mod arg;
 pub mod command;
 pub mod config;
+pub mod stats;
 
 use anyhow::Result;
 use serde::Serialize;
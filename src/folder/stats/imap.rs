@@ -0,0 +1,145 @@
+//! Module dedicated to IMAP folder statistics.
+
+use anyhow::{anyhow, Result};
+use email::imap::ImapContextSync;
+use log::{debug, info};
+use std::collections::HashMap;
+use utf7_imap::encode_utf7_imap as encode_utf7;
+
+use crate::envelope::query::imap::quote;
+
+use super::FolderStats;
+
+/// The IMAP status data items used to get folder statistics.
+const FOLDER_STATS_QUERY: &str = "(MESSAGES UNSEEN RECENT)";
+
+/// The IMAP status data items used to get folder statistics,
+/// including the size, as defined in the [RFC
+/// 8438](https://www.rfc-editor.org/rfc/rfc8438).
+const FOLDER_STATS_WITH_SIZE_QUERY: &str = "(MESSAGES UNSEEN RECENT SIZE)";
+
+/// Get statistics of the given IMAP folder.
+///
+/// Counts come from the STATUS command, as defined in the [RFC
+/// 3501](https://www.rfc-editor.org/rfc/rfc3501#section-6.3.10).
+/// When the server supports the STATUS=SIZE extension, the size comes
+/// from the same command. Otherwise it is the sum of message sizes,
+/// fetched after examining the folder so that the recent flag is kept
+/// untouched.
+pub async fn get_imap_folder_stats(ctx: &ImapContextSync, folder: &str) -> Result<FolderStats> {
+    info!("getting imap folder {folder} statistics");
+
+    let mut ctx = ctx.lock().await;
+    let mut stats = FolderStats::new(folder);
+
+    let folder = ctx.account_config.get_folder_alias(folder);
+    let folder_encoded = encode_utf7(folder.clone());
+    debug!("utf7 encoded folder: {folder_encoded}");
+
+    let capabilities = ctx
+        .exec(
+            |session| session.capabilities(),
+            |err| anyhow!(err).context("cannot get imap capabilities"),
+        )
+        .await?;
+
+    if capabilities.has_str("STATUS=SIZE") {
+        let cmd = format!(
+            "STATUS {} {FOLDER_STATS_WITH_SIZE_QUERY}",
+            quote(&folder_encoded)?
+        );
+
+        let res = ctx
+            .exec(
+                |session| session.run_command_and_read_response(&cmd),
+                |err| anyhow!(err).context(format!("cannot get imap folder {folder} status")),
+            )
+            .await?;
+
+        let items = parse_status_items(&res);
+        stats.total = items.get("MESSAGES").copied().unwrap_or_default();
+        stats.unseen = items.get("UNSEEN").copied().unwrap_or_default();
+        stats.recent = items.get("RECENT").copied().unwrap_or_default();
+        stats.size = items.get("SIZE").copied().unwrap_or_default();
+
+        return Ok(stats);
+    }
+
+    let status = ctx
+        .exec(
+            |session| session.status(&folder_encoded, FOLDER_STATS_QUERY),
+            |err| anyhow!(err).context(format!("cannot get imap folder {folder} status")),
+        )
+        .await?;
+
+    stats.total = status.exists.into();
+    stats.unseen = status.unseen.unwrap_or_default().into();
+    stats.recent = status.recent.into();
+
+    if status.exists > 0 {
+        ctx.exec(
+            |session| session.examine(&folder_encoded),
+            |err| anyhow!(err).context(format!("cannot examine imap folder {folder}")),
+        )
+        .await?;
+
+        let fetches = ctx
+            .exec(
+                |session| session.fetch("1:*", "RFC822.SIZE"),
+                |err| anyhow!(err).context(format!("cannot fetch imap folder {folder} sizes")),
+            )
+            .await?;
+
+        stats.size = fetches
+            .iter()
+            .map(|fetch| u64::from(fetch.size.unwrap_or_default()))
+            .sum();
+    }
+
+    Ok(stats)
+}
+
+/// Parse the data items of the given raw STATUS response.
+///
+/// The items are the pairs of the last parenthesized list of the
+/// untagged STATUS line, which comes after the mailbox name.
+fn parse_status_items(res: &[u8]) -> HashMap<String, u64> {
+    let res = String::from_utf8_lossy(res);
+
+    let Some(line) = res.lines().find(|line| line.starts_with("* STATUS ")) else {
+        return HashMap::new();
+    };
+
+    let Some((_, items)) = line.rsplit_once('(') else {
+        return HashMap::new();
+    };
+
+    let items: Vec<_> = items
+        .trim_end()
+        .trim_end_matches(')')
+        .split_whitespace()
+        .collect();
+
+    items
+        .as_chunks::<2>()
+        .0
+        .iter()
+        .filter_map(|[key, val]| Some((key.to_uppercase(), val.parse().ok()?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_status_items;
+
+    #[test]
+    fn status_items() {
+        let res = b"* STATUS \"Sent (old)\" (MESSAGES 231 UNSEEN 3 RECENT 0 SIZE 44421)\r\n";
+        let items = parse_status_items(res);
+
+        assert_eq!(items.get("MESSAGES"), Some(&231));
+        assert_eq!(items.get("UNSEEN"), Some(&3));
+        assert_eq!(items.get("RECENT"), Some(&0));
+        assert_eq!(items.get("SIZE"), Some(&44421));
+    }
+}
@@ -0,0 +1,90 @@
+//! Module dedicated to Maildir folder statistics.
+
+use anyhow::Result;
+use email::maildir::MaildirContextSync;
+use log::{debug, info};
+use std::{fs, path::Path};
+
+use super::FolderStats;
+
+/// Get statistics of the given Maildir folder.
+///
+/// Messages are counted from file names only: messages of the `new`
+/// directory are recent, and messages without the `S` flag are
+/// unseen.
+pub async fn get_maildir_folder_stats(
+    ctx: &MaildirContextSync,
+    folder: &str,
+) -> Result<FolderStats> {
+    info!("getting maildir folder {folder} statistics");
+
+    let ctx = ctx.lock().await;
+    let mdir = ctx.get_maildir_from_folder_name(folder)?;
+    let mut stats = FolderStats::new(folder);
+
+    for (name, size) in scan_dir(&mdir.path().join("new")) {
+        debug!("found recent maildir entry {name}");
+        stats.total += 1;
+        stats.unseen += 1;
+        stats.recent += 1;
+        stats.size += size;
+    }
+
+    for (name, size) in scan_dir(&mdir.path().join("cur")) {
+        stats.total += 1;
+        stats.size += size;
+
+        if !is_seen(&name) {
+            stats.unseen += 1;
+        }
+    }
+
+    Ok(stats)
+}
+
+/// List file names and sizes of the given Maildir subdirectory.
+///
+/// Hidden files are ignored, as well as entries that cannot be read.
+fn scan_dir(dir: &Path) -> Vec<(String, u64)> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            debug!("cannot read maildir directory {}: {err}", dir.display());
+            return Vec::new();
+        }
+    };
+
+    entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') {
+                return None;
+            }
+
+            let size = entry.metadata().map(|m| m.len()).unwrap_or_default();
+            Some((name, size))
+        })
+        .collect()
+}
+
+/// Check if the given Maildir file name contains the seen flag.
+fn is_seen(name: &str) -> bool {
+    name.split_once(":2,")
+        .or_else(|| name.split_once(";2,"))
+        .map(|(_, flags)| flags.contains('S'))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_seen;
+
+    #[test]
+    fn seen() {
+        assert!(is_seen("1234.host:2,FS"));
+        assert!(is_seen("1234.host;2,S"));
+        assert!(!is_seen("1234.host:2,F"));
+        assert!(!is_seen("1234.host"));
+    }
+}
@@ -0,0 +1,117 @@
+//! Module dedicated to folder statistics.
+//!
+//! Statistics are computed without listing envelopes: IMAP relies on
+//! the STATUS command, whereas Maildir only scans file names and
+//! metadata.
+
+#[cfg(feature = "imap")]
+pub mod imap;
+#[cfg(any(feature = "maildir", feature = "account-sync"))]
+pub mod maildir;
+#[cfg(feature = "notmuch")]
+pub mod notmuch;
+
+use anyhow::Result;
+use serde::Serialize;
+use std::ops;
+
+use crate::{
+    envelope::format_size,
+    printer::{PrintTable, PrintTableOpts, WriteColor},
+    ui::{Cell, Row, Table},
+};
+
+/// Represents the statistics of a folder.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct FolderStats {
+    pub name: String,
+    pub total: u64,
+    pub unseen: u64,
+    pub recent: u64,
+    pub size: u64,
+    /// The error that prevented statistics from being computed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl FolderStats {
+    pub fn new(name: impl ToString) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Build the statistics of a folder that could not be computed.
+    pub fn from_error(name: impl ToString, err: &anyhow::Error) -> Self {
+        Self {
+            name: name.to_string(),
+            error: Some(format!("{err:#}")),
+            ..Default::default()
+        }
+    }
+}
+
+impl Table for FolderStats {
+    fn head() -> Row {
+        Row::new()
+            .cell(Cell::new("NAME").bold().underline().white())
+            .cell(Cell::new("TOTAL").bold().underline().white())
+            .cell(Cell::new("UNSEEN").bold().underline().white())
+            .cell(Cell::new("RECENT").bold().underline().white())
+            .cell(Cell::new("SIZE").bold().underline().white())
+    }
+
+    fn row(&self) -> Row {
+        if self.error.is_some() {
+            let unknown = || Cell::new("-").red();
+            return Row::new()
+                .cell(Cell::new(&self.name).blue())
+                .cell(unknown())
+                .cell(unknown())
+                .cell(unknown())
+                .cell(unknown());
+        }
+
+        let unseen = Cell::new(self.unseen.to_string());
+        let unseen = if self.unseen > 0 {
+            unseen.bold()
+        } else {
+            unseen
+        };
+
+        Row::new()
+            .cell(Cell::new(&self.name).blue())
+            .cell(Cell::new(self.total.to_string()))
+            .cell(unseen)
+            .cell(Cell::new(self.recent.to_string()))
+            .cell(Cell::new(format_size(self.size)).green())
+    }
+}
+
+/// Represents the list of folder statistics.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct FoldersStats(Vec<FolderStats>);
+
+impl ops::Deref for FoldersStats {
+    type Target = Vec<FolderStats>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl FromIterator<FolderStats> for FoldersStats {
+    fn from_iter<T: IntoIterator<Item = FolderStats>>(iter: T) -> Self {
+        FoldersStats(iter.into_iter().collect())
+    }
+}
+
+impl PrintTable for FoldersStats {
+    fn print_table(&self, writer: &mut dyn WriteColor, opts: PrintTableOpts) -> Result<()> {
+        writeln!(writer)?;
+        Table::print(writer, self, opts)?;
+        writeln!(writer)?;
+        Ok(())
+    }
+}
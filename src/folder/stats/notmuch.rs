@@ -0,0 +1,57 @@
+//! Module dedicated to Notmuch folder statistics.
+
+use anyhow::Result;
+use email::notmuch::NotmuchContextSync;
+use log::{debug, info};
+use std::fs;
+
+use crate::envelope::query::notmuch::to_notmuch_folder_query;
+
+use super::FolderStats;
+
+/// Get statistics of the given Notmuch folder.
+///
+/// Unseen messages are the ones tagged unread, recent messages are
+/// the ones still located in a `new` Maildir directory.
+pub async fn get_notmuch_folder_stats(
+    ctx: &NotmuchContextSync,
+    folder: &str,
+) -> Result<FolderStats> {
+    info!("getting notmuch folder {folder} statistics");
+
+    let ctx = ctx.lock().await;
+    let config = &ctx.account_config;
+    let db = ctx.open_db()?;
+
+    let query = to_notmuch_folder_query(config, folder);
+    debug!("notmuch query: {query}");
+
+    let mut stats = FolderStats::new(folder);
+
+    {
+        let query_builder = db.create_query(&query)?;
+
+        for msg in query_builder.search_messages()? {
+            let path = msg.filename();
+
+            stats.total += 1;
+            stats.size += fs::metadata(&path).map(|m| m.len()).unwrap_or_default();
+
+            if msg.tags().any(|tag| tag == "unread") {
+                stats.unseen += 1;
+            }
+
+            let is_recent = path
+                .parent()
+                .and_then(|dir| dir.file_name())
+                .is_some_and(|dir| dir == "new");
+            if is_recent {
+                stats.recent += 1;
+            }
+        }
+    }
+
+    db.close()?;
+
+    Ok(stats)
+}
@@ -0,0 +1,81 @@
+use anyhow::{bail, Result};
+use clap::Parser;
+use email::backend::feature::BackendFeatureSource;
+use log::info;
+
+#[cfg(feature = "account-sync")]
+use crate::cache::arg::disable::CacheDisableFlag;
+use crate::{
+    account::arg::name::AccountNameFlag, backend::Backend, config::TomlConfig, journal::Journal,
+    printer::Printer,
+};
+
+/// Undo an operation recorded in the journal.
+///
+/// This command replays the inverse of the given operation: flags are
+/// restored, moved messages are moved back to their original folder,
+/// copies are deleted and deleted messages are restored from the
+/// trash. If no id is given, the most recent operation not undone yet
+/// is used. See the history command to list recorded operations.
+#[derive(Debug, Parser)]
+pub struct UndoCommand {
+    /// The id of the journal entry to undo.
+    #[arg(value_name = "ID")]
+    pub id: Option<usize>,
+
+    #[cfg(feature = "account-sync")]
+    #[command(flatten)]
+    pub cache: CacheDisableFlag,
+
+    #[command(flatten)]
+    pub account: AccountNameFlag,
+}
+
+impl UndoCommand {
+    pub async fn execute(self, printer: &mut impl Printer, config: &TomlConfig) -> Result<()> {
+        info!("executing undo command");
+
+        let (toml_account_config, account_config) = config.clone().into_account_configs(
+            self.account.name.as_deref(),
+            #[cfg(feature = "account-sync")]
+            self.cache.disable,
+        )?;
+
+        let journal = Journal::new(&account_config, toml_account_config.journal_retention())?;
+        let entry = journal.find(self.id)?;
+
+        if entry.undone {
+            bail!("cannot undo operation {}: already undone", entry.id);
+        }
+
+        let set_flags_kind = toml_account_config.set_flags_kind();
+        let move_messages_kind = toml_account_config.move_messages_kind();
+        let delete_messages_kind = toml_account_config.delete_messages_kind();
+        let list_envelopes_kind = toml_account_config.list_envelopes_kind();
+
+        let backend = Backend::new(
+            toml_account_config.clone(),
+            account_config,
+            set_flags_kind
+                .into_iter()
+                .chain(move_messages_kind)
+                .chain(delete_messages_kind)
+                .chain(list_envelopes_kind),
+            |builder| {
+                builder.set_set_flags(BackendFeatureSource::Context);
+                builder.set_move_messages(BackendFeatureSource::Context);
+                builder.set_delete_messages(BackendFeatureSource::Context);
+                builder.set_list_envelopes(BackendFeatureSource::Context);
+            },
+        )
+        .await?;
+
+        backend.undo_operation(&entry.operation).await?;
+        journal.mark_undone(entry.id)?;
+
+        printer.print(format!(
+            "Operation {} ({}) successfully undone!",
+            entry.id, entry.operation
+        ))
+    }
+}
@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+/// The journal configuration.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct JournalConfig {
+    /// The amount of days operations are kept in the journal. A
+    /// retention of 0 disables the journal.
+    pub retention: Option<u64>,
+}
+
+impl JournalConfig {
+    pub const DEFAULT_RETENTION: u64 = 30;
+}
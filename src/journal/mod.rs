@@ -0,0 +1,426 @@
+//! Module dedicated to the undo journal.
+//!
+//! Every mutating operation performed on envelopes (flags changes,
+//! moves, copies and deletions) is recorded in a local journal,
+//! alongside with the state of the envelopes before the operation.
+//! This state is used to replay the inverse of the operation. Each
+//! account has its own journal, stored as JSON lines under the data
+//! directory.
+//!
+//! The journal is updated under an exclusive lock, by writing a
+//! temporary file then renaming it over the journal, so that neither
+//! a crash nor concurrent processes can corrupt it.
+
+pub mod command;
+pub mod config;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{Local, TimeZone};
+use dirs::data_dir;
+use email::{account::config::AccountConfig, flag::Flags};
+use fs2::FileExt;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt, fs,
+    io::{BufWriter, Write},
+    ops,
+    path::PathBuf,
+};
+
+use crate::{
+    envelope::raw::RawEnvelope,
+    printer::{PrintTable, PrintTableOpts, WriteColor},
+    ui::{Cell, Row, Table},
+};
+
+/// The local journal of an account.
+#[derive(Clone, Debug)]
+pub struct Journal {
+    path: PathBuf,
+    retention: u64,
+}
+
+impl Journal {
+    /// Open the journal of the given account, keeping entries the
+    /// given amount of days.
+    pub fn new(account_config: &AccountConfig, retention: u64) -> Result<Self> {
+        let digest = md5::compute(&account_config.name);
+        let path = data_dir()
+            .ok_or(anyhow!("cannot get XDG data directory"))?
+            .join("himalaya")
+            .join(".journals")
+            .join(format!("{digest:x}.jsonl"));
+
+        Ok(Self { path, retention })
+    }
+
+    /// Return true if operations should be recorded.
+    pub fn is_enabled(&self) -> bool {
+        self.retention > 0
+    }
+
+    /// Read all the entries of the journal, from the oldest to the
+    /// newest.
+    pub fn entries(&self) -> Result<JournalEntries> {
+        if !self.path.exists() {
+            return Ok(JournalEntries::default());
+        }
+
+        let content = fs::read_to_string(&self.path)
+            .with_context(|| format!("cannot read journal at {:?}", self.path))?;
+
+        let entries = content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match serde_json::from_str(line) {
+                Ok(entry) => Some(entry),
+                Err(err) => {
+                    debug!("cannot parse journal entry, skipping it: {err}");
+                    debug!("{err:?}");
+                    None
+                }
+            })
+            .collect();
+
+        Ok(entries)
+    }
+
+    /// Find the entry matching the given id, or the most recent entry
+    /// not undone yet.
+    pub fn find(&self, id: Option<usize>) -> Result<JournalEntry> {
+        let entries = self.entries()?;
+
+        match id {
+            Some(id) => entries
+                .0
+                .into_iter()
+                .find(|entry| entry.id == id)
+                .ok_or_else(|| anyhow!("cannot find journal entry {id}")),
+            None => entries
+                .0
+                .into_iter()
+                .rev()
+                .find(|entry| !entry.undone)
+                .ok_or_else(|| anyhow!("cannot find operation to undo")),
+        }
+    }
+
+    /// Record the given operation, removing entries older than the
+    /// retention.
+    pub fn record(&self, operation: Operation) -> Result<()> {
+        let now = Local::now().timestamp();
+        let oldest = now - (self.retention * 24 * 60 * 60) as i64;
+
+        self.update(|entries| {
+            let id = entries.last().map(|entry| entry.id + 1).unwrap_or(1);
+            entries.0.retain(|entry| entry.date >= oldest);
+            entries.0.push(JournalEntry {
+                id,
+                date: now,
+                undone: false,
+                operation,
+            });
+            debug!("recording journal entry {id}");
+        })
+    }
+
+    /// Mark the entry matching the given id as undone.
+    pub fn mark_undone(&self, id: usize) -> Result<()> {
+        self.update(|entries| {
+            for entry in entries.0.iter_mut().filter(|entry| entry.id == id) {
+                entry.undone = true;
+            }
+        })
+    }
+
+    /// Update the entries of the journal with the given function,
+    /// under an exclusive lock.
+    fn update(&self, f: impl FnOnce(&mut JournalEntries)) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("cannot create journal directory at {dir:?}"))?;
+        }
+
+        let lock_path = self.path.with_extension("lock");
+        let lock = fs::File::create(&lock_path)
+            .with_context(|| format!("cannot create journal lock at {lock_path:?}"))?;
+        FileExt::lock_exclusive(&lock)
+            .with_context(|| format!("cannot lock journal at {lock_path:?}"))?;
+
+        let mut entries = self.entries()?;
+        f(&mut entries);
+
+        // the lock is released once the lock file is closed
+        self.write(&entries)
+    }
+
+    /// Write the given entries to a temporary file, then replace the
+    /// journal with it.
+    fn write(&self, entries: &JournalEntries) -> Result<()> {
+        let tmp_path = self.path.with_extension("jsonl.tmp");
+        let file = fs::File::create(&tmp_path)
+            .with_context(|| format!("cannot create journal at {tmp_path:?}"))?;
+        let mut writer = BufWriter::new(file);
+
+        for entry in entries.iter() {
+            serde_json::to_writer(&mut writer, entry).context("cannot write journal entry")?;
+            writeln!(writer)?;
+        }
+
+        let file = writer
+            .into_inner()
+            .map_err(|err| anyhow!(err.into_error()).context("cannot flush journal"))?;
+        file.sync_all().context("cannot sync journal")?;
+
+        fs::rename(&tmp_path, &self.path)
+            .with_context(|| format!("cannot replace journal at {:?}", self.path))
+    }
+}
+
+/// Represents an entry of the journal.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct JournalEntry {
+    pub id: usize,
+    /// The date of the operation, as a UNIX timestamp.
+    pub date: i64,
+    #[serde(default)]
+    pub undone: bool,
+    pub operation: Operation,
+}
+
+impl Table for JournalEntry {
+    fn head() -> Row {
+        Row::new()
+            .cell(Cell::new("ID").bold().underline().white())
+            .cell(Cell::new("DATE").bold().underline().white())
+            .cell(Cell::new("OPERATION").bold().underline().white())
+            .cell(Cell::new("UNDONE").bold().underline().white())
+    }
+
+    fn row(&self) -> Row {
+        let date = Local
+            .timestamp_opt(self.date, 0)
+            .single()
+            .map(|date| date.format("%F %R").to_string())
+            .unwrap_or_default();
+
+        Row::new()
+            .cell(Cell::new(self.id.to_string()).red())
+            .cell(Cell::new(date).yellow())
+            .cell(Cell::new(self.operation.to_string()).shrinkable().green())
+            .cell(Cell::new(if self.undone { "yes" } else { "" }))
+    }
+}
+
+/// Represents the list of journal entries.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct JournalEntries(Vec<JournalEntry>);
+
+impl ops::Deref for JournalEntries {
+    type Target = Vec<JournalEntry>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl FromIterator<JournalEntry> for JournalEntries {
+    fn from_iter<T: IntoIterator<Item = JournalEntry>>(iter: T) -> Self {
+        JournalEntries(iter.into_iter().collect())
+    }
+}
+
+impl PrintTable for JournalEntries {
+    fn print_table(&self, writer: &mut dyn WriteColor, opts: PrintTableOpts) -> Result<()> {
+        writeln!(writer)?;
+        Table::print(writer, self, opts)?;
+        writeln!(writer)?;
+        Ok(())
+    }
+}
+
+/// Represents a mutating operation, alongside with the state of the
+/// envelopes before the operation.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Operation {
+    AddFlags {
+        folder: String,
+        flags: Vec<String>,
+        envelopes: Vec<JournalEnvelope>,
+    },
+    SetFlags {
+        folder: String,
+        flags: Vec<String>,
+        envelopes: Vec<JournalEnvelope>,
+    },
+    RemoveFlags {
+        folder: String,
+        flags: Vec<String>,
+        envelopes: Vec<JournalEnvelope>,
+    },
+    Move {
+        from: String,
+        to: String,
+        envelopes: Vec<JournalEnvelope>,
+    },
+    Copy {
+        from: String,
+        to: String,
+        envelopes: Vec<JournalEnvelope>,
+    },
+    /// Deleting messages moves them to the trash folder, unless they
+    /// already are in the trash folder: they are flagged as deleted
+    /// instead (in which case the trash is `None`).
+    Delete {
+        folder: String,
+        trash: Option<String>,
+        envelopes: Vec<JournalEnvelope>,
+    },
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AddFlags {
+                folder,
+                flags,
+                envelopes,
+            } => write!(
+                f,
+                "add flag(s) {} to {} message(s) of {folder}",
+                flags.join(", "),
+                envelopes.len()
+            ),
+            Self::SetFlags {
+                folder,
+                flags,
+                envelopes,
+            } => write!(
+                f,
+                "set flag(s) {} to {} message(s) of {folder}",
+                flags.join(", "),
+                envelopes.len()
+            ),
+            Self::RemoveFlags {
+                folder,
+                flags,
+                envelopes,
+            } => write!(
+                f,
+                "remove flag(s) {} from {} message(s) of {folder}",
+                flags.join(", "),
+                envelopes.len()
+            ),
+            Self::Move {
+                from,
+                to,
+                envelopes,
+            } => write!(f, "move {} message(s) from {from} to {to}", envelopes.len()),
+            Self::Copy {
+                from,
+                to,
+                envelopes,
+            } => write!(f, "copy {} message(s) from {from} to {to}", envelopes.len()),
+            Self::Delete {
+                folder, envelopes, ..
+            } => write!(f, "delete {} message(s) from {folder}", envelopes.len()),
+        }
+    }
+}
+
+/// Represents the state of an envelope before an operation.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct JournalEnvelope {
+    /// The backend id of the envelope.
+    pub id: String,
+    /// The Message-ID of the envelope, used to pair it with its copy
+    /// in the target folder.
+    pub message_id: String,
+    pub flags: Vec<String>,
+    /// The backend id of the copy of the envelope in the target
+    /// folder, for moves, copies and deletions to the trash.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_id: Option<String>,
+}
+
+impl JournalEnvelope {
+    pub fn from_raw(envelope: &RawEnvelope) -> Self {
+        Self {
+            id: envelope.id.clone(),
+            message_id: envelope.to_envelope().message_id,
+            flags: envelope.flags.clone().into(),
+            target_id: None,
+        }
+    }
+
+    pub fn flags(&self) -> Flags {
+        Flags::from_iter(self.flags.iter().map(|flag| flag.as_str().into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use super::{Journal, JournalEntries, JournalEntry, Operation};
+
+    fn journal() -> Journal {
+        let name = format!("himalaya-journal-{}.jsonl", uuid::Uuid::new_v4());
+        let path = env::temp_dir().join(name);
+        Journal { path, retention: 1 }
+    }
+
+    fn remove(journal: &Journal) {
+        let _ = fs::remove_file(&journal.path);
+        let _ = fs::remove_file(journal.path.with_extension("lock"));
+    }
+
+    fn operation() -> Operation {
+        Operation::AddFlags {
+            folder: "INBOX".into(),
+            flags: vec!["seen".into()],
+            envelopes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn record_then_undo() {
+        let journal = journal();
+
+        journal.record(operation()).unwrap();
+        journal.record(operation()).unwrap();
+
+        let ids: Vec<_> = journal.entries().unwrap().iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![1, 2]);
+        assert_eq!(journal.find(None).unwrap().id, 2);
+
+        journal.mark_undone(2).unwrap();
+        assert_eq!(journal.find(None).unwrap().id, 1);
+        assert!(journal.find(Some(2)).unwrap().undone);
+        assert!(journal.find(Some(3)).is_err());
+
+        remove(&journal);
+    }
+
+    #[test]
+    fn retention() {
+        let journal = journal();
+
+        let entry = JournalEntry {
+            id: 1,
+            date: 0,
+            undone: false,
+            operation: operation(),
+        };
+        journal.write(&JournalEntries(vec![entry])).unwrap();
+        journal.record(operation()).unwrap();
+
+        let ids: Vec<_> = journal.entries().unwrap().iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![2]);
+        assert!(!journal.path.with_extension("jsonl.tmp").exists());
+
+        remove(&journal);
+    }
+}
@@ -8,11 +8,13 @@ pub mod email;
 pub mod folder;
 #[cfg(feature = "imap")]
 pub mod imap;
+pub mod journal;
 #[cfg(feature = "maildir")]
 pub mod maildir;
 pub mod manual;
 #[cfg(feature = "notmuch")]
 pub mod notmuch;
+pub mod outbox;
 pub mod output;
 pub mod printer;
 #[cfg(feature = "sendmail")]
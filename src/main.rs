@@ -2,8 +2,11 @@ use anyhow::Result;
 use clap::Parser;
 use env_logger::{Builder as LoggerBuilder, Env, DEFAULT_FILTER_ENV};
 use himalaya::{
-    cli::Cli, config::TomlConfig, envelope::command::list::ListEnvelopesCommand,
-    message::command::mailto::MessageMailtoCommand, printer::StdoutPrinter,
+    cli::{load_config, Cli},
+    config::TomlConfig,
+    envelope::command::list::ListEnvelopesCommand,
+    message::command::mailto::MessageMailtoCommand,
+    printer::{Printer, StdoutPrinter},
 };
 use log::{debug, trace};
 
@@ -28,6 +31,7 @@ async fn main() -> Result<()> {
     {
         let mut printer = StdoutPrinter::default();
         let config = TomlConfig::from_default_paths().await?;
+        printer.set_pager(config.pager.clone());
 
         return MessageMailtoCommand::new(url)?
             .execute(&mut printer, &config)
@@ -37,10 +41,14 @@ async fn main() -> Result<()> {
     let cli = Cli::parse();
     let mut printer = StdoutPrinter::new(cli.output, cli.color);
 
+    if cli.no_pager {
+        printer = printer.without_pager();
+    }
+
     match cli.command {
         Some(cmd) => cmd.execute(&mut printer, cli.config_path.as_ref()).await,
         None => {
-            let config = TomlConfig::from_some_path_or_default(cli.config_path.as_ref()).await?;
+            let config = load_config(&mut printer, cli.config_path.as_ref()).await?;
             ListEnvelopesCommand::default()
                 .execute(&mut printer, &config)
                 .await
@@ -0,0 +1,49 @@
+use anyhow::Result;
+use clap::Parser;
+use log::info;
+
+#[cfg(feature = "account-sync")]
+use crate::cache::arg::disable::CacheDisableFlag;
+use crate::{
+    account::arg::name::AccountNameFlag, config::TomlConfig, outbox::Outbox, printer::Printer,
+};
+
+/// Cancel a message of the outbox.
+///
+/// This command removes the given message from the outbox, so that
+/// it is never sent. The id can be shortened, as long as it matches
+/// only one message.
+#[derive(Debug, Parser)]
+pub struct OutboxCancelCommand {
+    /// The id of the outbox message to cancel.
+    #[arg(value_name = "ID")]
+    pub id: String,
+
+    #[cfg(feature = "account-sync")]
+    #[command(flatten)]
+    pub cache: CacheDisableFlag,
+
+    #[command(flatten)]
+    pub account: AccountNameFlag,
+}
+
+impl OutboxCancelCommand {
+    pub async fn execute(self, printer: &mut impl Printer, config: &TomlConfig) -> Result<()> {
+        info!("executing cancel outbox message command");
+
+        let (_, account_config) = config.clone().into_account_configs(
+            self.account.name.as_deref(),
+            #[cfg(feature = "account-sync")]
+            self.cache.disable,
+        )?;
+
+        let outbox = Outbox::new(&account_config)?;
+        let entry = outbox.find(&self.id)?;
+        outbox.remove(&entry)?;
+
+        printer.print(format!(
+            "Outbox message {} successfully canceled!",
+            entry.id
+        ))
+    }
+}
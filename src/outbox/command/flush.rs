@@ -0,0 +1,54 @@
+use anyhow::Result;
+use chrono::Local;
+use clap::Parser;
+use log::info;
+
+#[cfg(feature = "account-sync")]
+use crate::cache::arg::disable::CacheDisableFlag;
+use crate::{
+    account::arg::name::AccountNameFlag,
+    config::TomlConfig,
+    outbox::{flush_outbox, Outbox},
+    printer::Printer,
+};
+
+/// Send the messages of the outbox that are due.
+///
+/// This command sends messages whose scheduled date is reached, saves
+/// a copy of them to your sent folder, then removes them from the
+/// outbox. Messages that cannot be sent are kept in the outbox, and
+/// are retried later following the retry policy of the account. This
+/// command is meant to be run periodically, for example from a cron
+/// job.
+#[derive(Debug, Parser)]
+pub struct OutboxFlushCommand {
+    #[cfg(feature = "account-sync")]
+    #[command(flatten)]
+    pub cache: CacheDisableFlag,
+
+    #[command(flatten)]
+    pub account: AccountNameFlag,
+}
+
+impl OutboxFlushCommand {
+    pub async fn execute(self, printer: &mut impl Printer, config: &TomlConfig) -> Result<()> {
+        info!("executing flush outbox command");
+
+        let (toml_account_config, account_config) = config.clone().into_account_configs(
+            self.account.name.as_deref(),
+            #[cfg(feature = "account-sync")]
+            self.cache.disable,
+        )?;
+
+        let outbox = Outbox::new(&account_config)?;
+        let now = Local::now().timestamp();
+
+        if !outbox.entries()?.iter().any(|entry| entry.is_due(now)) {
+            return printer.print("No outbox message to send");
+        }
+
+        let sent = flush_outbox(toml_account_config, account_config).await?;
+
+        printer.print(format!("Sent {sent} outbox message(s)!"))
+    }
+}
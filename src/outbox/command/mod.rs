@@ -0,0 +1,46 @@
+mod cancel;
+mod flush;
+mod list;
+mod retry;
+
+use anyhow::Result;
+use clap::Subcommand;
+
+use crate::{config::TomlConfig, printer::Printer};
+
+use self::{
+    cancel::OutboxCancelCommand, flush::OutboxFlushCommand, list::OutboxListCommand,
+    retry::OutboxRetryCommand,
+};
+
+/// Manage the outbox.
+///
+/// The outbox is a local folder containing messages scheduled to be
+/// sent later, as well as messages that could not be sent when the
+/// outbox fallback is enabled. This subcommand allows you to list
+/// them, to cancel them and to send the ones that are due.
+#[derive(Debug, Subcommand)]
+pub enum OutboxSubcommand {
+    #[command(alias = "lst")]
+    List(OutboxListCommand),
+
+    #[command(arg_required_else_help = true)]
+    #[command(visible_alias = "drop", aliases = ["remove", "rm"])]
+    Cancel(OutboxCancelCommand),
+
+    #[command(alias = "send")]
+    Flush(OutboxFlushCommand),
+
+    Retry(OutboxRetryCommand),
+}
+
+impl OutboxSubcommand {
+    pub async fn execute(self, printer: &mut impl Printer, config: &TomlConfig) -> Result<()> {
+        match self {
+            Self::List(cmd) => cmd.execute(printer, config).await,
+            Self::Cancel(cmd) => cmd.execute(printer, config).await,
+            Self::Flush(cmd) => cmd.execute(printer, config).await,
+            Self::Retry(cmd) => cmd.execute(printer, config).await,
+        }
+    }
+}
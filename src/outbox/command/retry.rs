@@ -0,0 +1,86 @@
+use anyhow::{bail, Result};
+use clap::Parser;
+use log::{debug, info, warn};
+
+#[cfg(feature = "account-sync")]
+use crate::cache::arg::disable::CacheDisableFlag;
+use crate::{
+    account::arg::name::AccountNameFlag,
+    config::TomlConfig,
+    outbox::{build_send_backend, Outbox},
+    printer::Printer,
+};
+
+/// Send again messages of the outbox that could not be sent.
+///
+/// This command sends the given message right away, whatever its
+/// scheduled date. If no id is given, all the messages of the outbox
+/// that could not be sent are retried. Messages that still cannot be
+/// sent are kept in the outbox.
+#[derive(Debug, Parser)]
+pub struct OutboxRetryCommand {
+    /// The id of the outbox message to retry.
+    ///
+    /// The id can be shortened, as long as it matches only one
+    /// message.
+    #[arg(value_name = "ID")]
+    pub id: Option<String>,
+
+    #[cfg(feature = "account-sync")]
+    #[command(flatten)]
+    pub cache: CacheDisableFlag,
+
+    #[command(flatten)]
+    pub account: AccountNameFlag,
+}
+
+impl OutboxRetryCommand {
+    pub async fn execute(self, printer: &mut impl Printer, config: &TomlConfig) -> Result<()> {
+        info!("executing retry outbox message command");
+
+        let (toml_account_config, account_config) = config.clone().into_account_configs(
+            self.account.name.as_deref(),
+            #[cfg(feature = "account-sync")]
+            self.cache.disable,
+        )?;
+
+        let outbox = Outbox::new(&account_config)?;
+
+        if let Some(id) = &self.id {
+            let entry = outbox.find(id)?;
+            let backend = build_send_backend(toml_account_config, account_config).await?;
+            if !outbox.send(&backend, &entry).await? {
+                bail!("cannot send outbox entry {}: already being sent", entry.id);
+            }
+            return printer.print(format!("Outbox message {} successfully sent!", entry.id));
+        }
+
+        let entries: Vec<_> = outbox
+            .entries()?
+            .iter()
+            .filter(|entry| entry.error.is_some())
+            .cloned()
+            .collect();
+
+        if entries.is_empty() {
+            return printer.print("No outbox message to retry");
+        }
+
+        let backend = build_send_backend(toml_account_config, account_config).await?;
+
+        let mut sent = 0;
+
+        for entry in &entries {
+            match outbox.send(&backend, entry).await {
+                Ok(true) => sent += 1,
+                Ok(false) => debug!("outbox entry {} already being sent, skipping it", entry.id),
+                Err(err) => {
+                    warn!("{err:#}, skipping it");
+                    debug!("{err:?}");
+                }
+            }
+        }
+
+        printer.print(format!("Sent {sent} outbox message(s)!"))
+    }
+}
@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// The outbox configuration.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct OutboxConfig {
+    /// Queue messages that cannot be sent to the outbox, instead of
+    /// failing. Queued messages are sent again later.
+    pub fallback: Option<bool>,
+
+    /// The retry policy of queued messages.
+    pub retry: Option<OutboxRetryConfig>,
+}
+
+/// The retry policy of messages queued after a failed send.
+///
+/// A queued message is sent again after a delay that doubles at each
+/// failed attempt, starting from the initial delay and capped by the
+/// maximum delay. It is given up after the maximum amount of
+/// attempts, and can then only be sent again manually.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct OutboxRetryConfig {
+    /// The delay before the first retry, in seconds.
+    pub initial_delay: Option<u64>,
+
+    /// The maximum delay between two retries, in seconds.
+    pub max_delay: Option<u64>,
+
+    /// The maximum amount of attempts to send a message.
+    pub max_attempts: Option<u32>,
+}
+
+impl OutboxRetryConfig {
+    pub const DEFAULT_INITIAL_DELAY: u64 = 60;
+    pub const DEFAULT_MAX_DELAY: u64 = 6 * 60 * 60;
+    pub const DEFAULT_MAX_ATTEMPTS: u32 = 10;
+
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts.unwrap_or(Self::DEFAULT_MAX_ATTEMPTS)
+    }
+
+    /// Compute the delay before the next retry, given the amount of
+    /// failed attempts (starting from 1).
+    pub fn find_delay(&self, attempts: u32) -> Duration {
+        let initial_delay = self.initial_delay.unwrap_or(Self::DEFAULT_INITIAL_DELAY);
+        let max_delay = self.max_delay.unwrap_or(Self::DEFAULT_MAX_DELAY);
+        let factor = 2u64.saturating_pow(attempts.saturating_sub(1));
+        let delay = initial_delay.saturating_mul(factor).min(max_delay);
+
+        Duration::from_secs(delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::OutboxRetryConfig;
+
+    #[test]
+    fn retry_delay() {
+        let config = OutboxRetryConfig {
+            initial_delay: Some(30),
+            max_delay: Some(100),
+            max_attempts: None,
+        };
+
+        let delays: Vec<_> = (1..=4)
+            .map(|attempts| config.find_delay(attempts))
+            .collect();
+
+        assert_eq!(
+            delays,
+            vec![
+                Duration::from_secs(30),
+                Duration::from_secs(60),
+                Duration::from_secs(100),
+                Duration::from_secs(100),
+            ]
+        );
+
+        let config = OutboxRetryConfig::default();
+        assert_eq!(config.find_delay(1), Duration::from_secs(60));
+        assert_eq!(config.find_delay(64), Duration::from_secs(6 * 60 * 60));
+    }
+}
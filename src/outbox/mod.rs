@@ -0,0 +1,766 @@
+//! Module dedicated to the outbox.
+//!
+//! Messages scheduled to be sent later are stored in a local outbox
+//! until they are delivered by the outbox flush command, which can be
+//! invoked from a cron job. Each account has its own outbox, stored
+//! as a Maildir under the data directory. The date a message should
+//! be sent at is stored in its `X-Himalaya-Send-At` header, which is
+//! removed before delivery. The `Date` header is set at delivery.
+//!
+//! Entries are claimed before being sent, by moving them to the
+//! `claimed` directory of the outbox, so that concurrent flushes
+//! never send the same message twice. Claims left by interrupted
+//! flushes are released after [`CLAIM_TIMEOUT`].
+//!
+//! When the outbox fallback is enabled, messages that cannot be sent
+//! are queued to the outbox as well, alongside with the amount of
+//! failed attempts and the last error. They are sent again by the
+//! synchronization and watch loops, following the retry policy of the
+//! account. Once the maximum amount of attempts is reached, their
+//! send date is removed and they are only sent again manually.
+
+pub mod command;
+pub mod config;
+
+use anyhow::{anyhow, bail, Context, Error, Result};
+use chrono::{DateTime, Duration, Local, NaiveDateTime, TimeZone};
+use dirs::data_dir;
+use email::{
+    account::config::AccountConfig,
+    backend::feature::BackendFeatureSource,
+    flag::Flag,
+    folder::SENT,
+    message::{add::AddMessage, send::SendMessage, send::SendMessageThenSaveCopy},
+};
+use log::{debug, warn};
+use mail_parser::MessageParser;
+use serde::Serialize;
+use std::{
+    fs,
+    io::{self, Write},
+    ops,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+use uuid::Uuid;
+
+use crate::{
+    account::config::TomlAccountConfig,
+    backend::Backend,
+    envelope::{mailboxes, Mailbox},
+    printer::{PrintTable, PrintTableOpts, WriteColor},
+    ui::{Cell, Row, Table},
+};
+
+use self::config::OutboxRetryConfig;
+
+/// The header holding the date a message should be sent at.
+pub const SEND_AT_HEADER: &str = "X-Himalaya-Send-At";
+
+/// The header holding the amount of failed attempts to send a
+/// message.
+pub const ATTEMPTS_HEADER: &str = "X-Himalaya-Attempts";
+
+/// The header holding the error of the last failed attempt to send a
+/// message.
+pub const ERROR_HEADER: &str = "X-Himalaya-Error";
+
+/// The interval between two flushes of the outbox, when triggered
+/// from the synchronization and watch loops.
+pub const FLUSH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// The delay after which a claimed entry is considered abandoned by
+/// its flush, and is released back to the outbox.
+pub const CLAIM_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// The formats accepted when parsing a local send date.
+const SEND_AT_FORMATS: [&str; 4] = [
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%dT%H:%M:%S",
+];
+
+/// The local outbox of an account.
+#[derive(Clone, Debug)]
+pub struct Outbox {
+    path: PathBuf,
+}
+
+impl Outbox {
+    /// Open the outbox of the given account.
+    pub fn new(account_config: &AccountConfig) -> Result<Self> {
+        let digest = md5::compute(&account_config.name);
+        let path = data_dir()
+            .ok_or(anyhow!("cannot get XDG data directory"))?
+            .join("himalaya")
+            .join(".outboxes")
+            .join(format!("{digest:x}"));
+
+        Ok(Self { path })
+    }
+
+    /// Store the given message in the outbox, to be sent at the given
+    /// date.
+    pub fn push(&self, msg: &[u8], send_at: DateTime<Local>) -> Result<OutboxEntry> {
+        let id = Uuid::new_v4().to_simple().to_string();
+        self.write(&id, msg, Some(send_at), None)
+    }
+
+    /// Store the given message that cannot be sent in the outbox, to
+    /// be sent again following the given retry policy.
+    pub fn push_failed(
+        &self,
+        msg: &[u8],
+        err: &Error,
+        retry: &OutboxRetryConfig,
+    ) -> Result<OutboxEntry> {
+        let id = Uuid::new_v4().to_simple().to_string();
+        let send_at = next_attempt_date(1, retry)?;
+        self.write(&id, msg, send_at, Some((1, err)))
+    }
+
+    /// Record a failed attempt to send the given claimed entry, which
+    /// is rescheduled following the given retry policy and released
+    /// back to the outbox.
+    pub fn record_failure(
+        &self,
+        entry: &OutboxEntry,
+        err: &Error,
+        retry: &OutboxRetryConfig,
+    ) -> Result<OutboxEntry> {
+        let attempts = entry.attempts.saturating_add(1);
+        let send_at = next_attempt_date(attempts, retry)?;
+
+        if send_at.is_none() {
+            warn!(
+                "giving up outbox entry {} after {attempts} attempts, see `outbox retry`",
+                entry.id
+            );
+        }
+
+        let rescheduled =
+            self.write(&entry.id, &entry.message()?, send_at, Some((attempts, err)))?;
+        self.remove(entry)?;
+        Ok(rescheduled)
+    }
+
+    /// Write the given message to the outbox, prepended with the
+    /// outbox headers. An existing entry with the same id is
+    /// replaced.
+    fn write(
+        &self,
+        id: &str,
+        msg: &[u8],
+        send_at: Option<DateTime<Local>>,
+        failure: Option<(u32, &Error)>,
+    ) -> Result<OutboxEntry> {
+        for dir in ["cur", "new", "tmp"] {
+            let dir = self.path.join(dir);
+            fs::create_dir_all(&dir)
+                .with_context(|| format!("cannot create outbox directory at {dir:?}"))?;
+        }
+
+        let tmp = self.path.join("tmp").join(id);
+        let cur = self.path.join("cur").join(format!("{id}:2,"));
+
+        let mut file = fs::File::create(&tmp)
+            .with_context(|| format!("cannot create outbox message at {tmp:?}"))?;
+        if let Some(send_at) = send_at {
+            write!(file, "{SEND_AT_HEADER}: {}\r\n", send_at.to_rfc2822())?;
+        }
+        if let Some((attempts, err)) = failure {
+            // the error is kept on a single line, so that it does not
+            // break the headers of the message
+            let err = format!("{err:#}").replace(['\r', '\n'], " ");
+            write!(file, "{ATTEMPTS_HEADER}: {attempts}\r\n")?;
+            write!(file, "{ERROR_HEADER}: {}\r\n", err.trim())?;
+        }
+        file.write_all(msg)?;
+        file.sync_all()?;
+
+        fs::rename(&tmp, &cur).with_context(|| format!("cannot move outbox message to {cur:?}"))?;
+
+        OutboxEntry::from_path(&cur)
+    }
+
+    /// Read all the entries of the outbox, from the first to send to
+    /// the last one. Claimed entries are not part of them, except
+    /// abandoned ones which are released first.
+    pub fn entries(&self) -> Result<OutboxEntries> {
+        self.release_abandoned_claims()?;
+
+        let dir = self.path.join("cur");
+
+        if !dir.exists() {
+            return Ok(OutboxEntries::default());
+        }
+
+        let mut entries = Vec::new();
+
+        for entry in fs::read_dir(&dir).with_context(|| format!("cannot read outbox at {dir:?}"))? {
+            let path = entry?.path();
+            match OutboxEntry::from_path(&path) {
+                Ok(entry) => entries.push(entry),
+                Err(err) => {
+                    debug!("cannot read outbox entry at {path:?}, skipping it: {err}");
+                    debug!("{err:?}");
+                }
+            }
+        }
+
+        entries.sort_by_key(|entry| (entry.send_at, entry.id.clone()));
+
+        Ok(OutboxEntries(entries))
+    }
+
+    /// Find the entry matching the given id, or the given id prefix.
+    pub fn find(&self, id: &str) -> Result<OutboxEntry> {
+        let mut entries = self
+            .entries()?
+            .0
+            .into_iter()
+            .filter(|entry| entry.id.starts_with(id));
+
+        match (entries.next(), entries.next()) {
+            (Some(entry), None) if !id.is_empty() => Ok(entry),
+            (Some(_), _) => bail!("cannot find outbox entry {id}: ambiguous id"),
+            (None, _) => bail!("cannot find outbox entry {id}"),
+        }
+    }
+
+    /// Remove the given entry from the outbox.
+    pub fn remove(&self, entry: &OutboxEntry) -> Result<()> {
+        fs::remove_file(&entry.path)
+            .with_context(|| format!("cannot remove outbox entry {}", entry.id))
+    }
+
+    /// Claim the given entry, so that no other flush sends it.
+    ///
+    /// Returns the claimed entry, read again since it may have been
+    /// updated in the meantime, or `None` if the entry has already
+    /// been claimed or removed.
+    pub fn claim(&self, entry: &OutboxEntry) -> Result<Option<OutboxEntry>> {
+        let dir = self.path.join("claimed");
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("cannot create outbox directory at {dir:?}"))?;
+
+        let Some(name) = entry.path.file_name() else {
+            bail!("cannot get file name of outbox entry {}", entry.id);
+        };
+        let claimed = dir.join(name);
+
+        // the modification date of the entry is the date of the
+        // claim, so that the claim is not taken for an abandoned one
+        let touched = fs::File::options()
+            .write(true)
+            .open(&entry.path)
+            .and_then(|file| file.set_modified(SystemTime::now()));
+
+        let res = touched.and_then(|()| fs::rename(&entry.path, &claimed));
+
+        match res {
+            Ok(()) => Ok(Some(OutboxEntry::from_path(&claimed)?)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => {
+                Err(anyhow!(err).context(format!("cannot claim outbox entry {}", entry.id)))
+            }
+        }
+    }
+
+    /// Release the given claimed entry back to the outbox.
+    pub fn release(&self, entry: &OutboxEntry) -> Result<()> {
+        let Some(name) = entry.path.file_name() else {
+            bail!("cannot get file name of outbox entry {}", entry.id);
+        };
+        let cur = self.path.join("cur").join(name);
+
+        fs::rename(&entry.path, &cur)
+            .with_context(|| format!("cannot release outbox entry {}", entry.id))
+    }
+
+    /// Release the entries claimed for longer than [`CLAIM_TIMEOUT`],
+    /// whose flush has most likely been interrupted.
+    fn release_abandoned_claims(&self) -> Result<()> {
+        let dir = self.path.join("claimed");
+
+        if !dir.exists() {
+            return Ok(());
+        }
+
+        for entry in fs::read_dir(&dir).with_context(|| format!("cannot read outbox at {dir:?}"))? {
+            let path = entry?.path();
+            let abandoned = fs::metadata(&path)
+                .and_then(|meta| meta.modified())
+                .ok()
+                .and_then(|date| date.elapsed().ok())
+                .is_some_and(|elapsed| elapsed > CLAIM_TIMEOUT);
+
+            if !abandoned {
+                continue;
+            }
+
+            let res = OutboxEntry::from_path(&path).and_then(|entry| {
+                warn!("releasing abandoned outbox entry {}", entry.id);
+                self.release(&entry)
+            });
+
+            if let Err(err) = res {
+                debug!("cannot release outbox entry at {path:?}, skipping it: {err}");
+                debug!("{err:?}");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Claim then send the given entry, then remove it from the
+    /// outbox.
+    ///
+    /// If the entry cannot be sent, the failure is recorded and the
+    /// entry is rescheduled following the retry policy of the
+    /// account. Returns false if the entry has already been claimed
+    /// by another flush.
+    pub async fn send(&self, backend: &Backend, entry: &OutboxEntry) -> Result<bool> {
+        match self.claim(entry)? {
+            Some(entry) => self.send_claimed(backend, &entry).await.map(|()| true),
+            None => Ok(false),
+        }
+    }
+
+    async fn send_claimed(&self, backend: &Backend, entry: &OutboxEntry) -> Result<()> {
+        match send_then_save_copy(backend, &entry.message_to_deliver()?).await {
+            Ok(()) => self.remove(entry),
+            Err(err) => {
+                let retry = backend.toml_account_config.outbox_retry();
+                self.record_failure(entry, &err, &retry)?;
+                Err(err.context(format!("cannot send outbox entry {}", entry.id)))
+            }
+        }
+    }
+
+    /// Send the entries of the outbox that are due, then remove them
+    /// from the outbox.
+    ///
+    /// Entries that cannot be sent are kept in the outbox, entries
+    /// claimed by another flush are skipped. Returns the amount of
+    /// sent entries.
+    pub async fn flush(&self, backend: &Backend) -> Result<usize> {
+        let now = Local::now().timestamp();
+        let mut sent = 0;
+
+        for entry in self.entries()?.0 {
+            if !entry.is_due(now) {
+                continue;
+            }
+
+            let Some(entry) = self.claim(&entry)? else {
+                debug!("outbox entry {} already claimed, skipping it", entry.id);
+                continue;
+            };
+
+            // the entry may have been rescheduled in the meantime
+            if !entry.is_due(now) {
+                self.release(&entry)?;
+                continue;
+            }
+
+            match self.send_claimed(backend, &entry).await {
+                Ok(()) => sent += 1,
+                Err(err) => {
+                    warn!("{err:#}, skipping it");
+                    debug!("{err:?}");
+                }
+            }
+        }
+
+        Ok(sent)
+    }
+}
+
+/// Send the due messages of the outbox of the given account.
+///
+/// The backend is built only when messages are due. If it cannot be
+/// built, a failed attempt is recorded for every due message. Returns
+/// the amount of sent messages.
+pub async fn flush_outbox(
+    toml_account_config: Arc<TomlAccountConfig>,
+    account_config: Arc<AccountConfig>,
+) -> Result<usize> {
+    let outbox = Outbox::new(&account_config)?;
+    let now = Local::now().timestamp();
+    let due: Vec<_> = outbox
+        .entries()?
+        .0
+        .into_iter()
+        .filter(|entry| entry.is_due(now))
+        .collect();
+
+    if due.is_empty() {
+        return Ok(0);
+    }
+
+    let backend = match build_send_backend(toml_account_config.clone(), account_config).await {
+        Ok(backend) => backend,
+        Err(err) => {
+            let retry = toml_account_config.outbox_retry();
+            for entry in &due {
+                if let Some(entry) = outbox.claim(entry)? {
+                    outbox.record_failure(&entry, &err, &retry)?;
+                }
+            }
+            return Err(err);
+        }
+    };
+
+    outbox.flush(&backend).await
+}
+
+/// Send the due messages of the outbox of the given account, from a
+/// long-running loop.
+///
+/// Errors are logged instead of being returned, so that an
+/// unreachable send backend does not stop the loop. Returns the
+/// amount of sent messages.
+pub async fn flush_outbox_or_warn(
+    toml_account_config: Arc<TomlAccountConfig>,
+    account_config: Arc<AccountConfig>,
+) -> usize {
+    let account_name = account_config.name.clone();
+
+    match flush_outbox(toml_account_config, account_config).await {
+        Ok(sent) => sent,
+        Err(err) => {
+            warn!("cannot flush outbox of account {account_name}: {err:#}");
+            debug!("{err:?}");
+            0
+        }
+    }
+}
+
+/// Send the given message, or queue it to the outbox if it cannot be
+/// sent and if the outbox fallback is enabled.
+///
+/// Returns the outbox entry of the queued message, if any.
+pub async fn send_message_or_queue(backend: &Backend, msg: &[u8]) -> Result<Option<OutboxEntry>> {
+    if !backend.toml_account_config.outbox_fallback() {
+        backend.send_message_then_save_copy(msg).await?;
+        return Ok(None);
+    }
+
+    match send_then_save_copy(backend, msg).await {
+        Ok(()) => Ok(None),
+        Err(err) => {
+            warn!("cannot send message, queuing it to the outbox: {err:#}");
+            debug!("{err:?}");
+            let retry = backend.toml_account_config.outbox_retry();
+            let entry = Outbox::new(&backend.account_config)?.push_failed(msg, &err, &retry)?;
+            Ok(Some(entry))
+        }
+    }
+}
+
+/// Build a backend able to send messages and to save copies of them.
+pub async fn build_send_backend(
+    toml_account_config: Arc<TomlAccountConfig>,
+    account_config: Arc<AccountConfig>,
+) -> Result<Backend> {
+    let send_message_kind = toml_account_config.send_message_kind().into_iter().chain(
+        toml_account_config
+            .add_message_kind()
+            .filter(|_| account_config.should_save_copy_sent_message()),
+    );
+
+    Backend::new(
+        toml_account_config.clone(),
+        account_config.clone(),
+        send_message_kind,
+        |builder| {
+            builder.set_send_message(BackendFeatureSource::Context);
+            builder.set_add_message(BackendFeatureSource::Context);
+        },
+    )
+    .await
+}
+
+/// Send the given message, then save a copy of it to the sent folder.
+///
+/// Only sending errors are returned: a copy that cannot be saved is
+/// logged, so that a message that has been sent is not queued.
+async fn send_then_save_copy(backend: &Backend, msg: &[u8]) -> Result<()> {
+    backend.send_message(msg).await?;
+
+    if backend.account_config.should_save_copy_sent_message() {
+        if let Err(err) = backend.add_message_with_flag(SENT, msg, Flag::Seen).await {
+            warn!("cannot save copy of sent message: {err}");
+            debug!("{err:?}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Compute the date of the next attempt to send a message, given the
+/// amount of failed attempts. Returns `None` once the maximum amount
+/// of attempts is reached.
+fn next_attempt_date(attempts: u32, retry: &OutboxRetryConfig) -> Result<Option<DateTime<Local>>> {
+    if attempts >= retry.max_attempts() {
+        return Ok(None);
+    }
+
+    let delay = Duration::from_std(retry.find_delay(attempts))?;
+    Ok(Some(Local::now() + delay))
+}
+
+/// Represents a message stored in the outbox.
+#[derive(Clone, Debug, Serialize)]
+pub struct OutboxEntry {
+    pub id: String,
+    /// The date the message should be sent at, as a UNIX timestamp.
+    pub send_at: Option<i64>,
+    pub to: Vec<Mailbox>,
+    pub subject: String,
+    /// The amount of failed attempts to send the message.
+    pub attempts: u32,
+    /// The error of the last failed attempt to send the message.
+    pub error: Option<String>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl OutboxEntry {
+    fn from_path(path: &Path) -> Result<Self> {
+        let id = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.split(':').next())
+            .ok_or_else(|| anyhow!("cannot get outbox entry id from {path:?}"))?
+            .to_owned();
+
+        let raw = fs::read(path).with_context(|| format!("cannot read outbox entry {id}"))?;
+        let msg = MessageParser::new()
+            .parse(&raw)
+            .ok_or_else(|| anyhow!("cannot parse outbox entry {id}"))?;
+
+        let send_at = msg
+            .header_raw(SEND_AT_HEADER)
+            .and_then(|date| DateTime::parse_from_rfc2822(date.trim()).ok())
+            .map(|date| date.timestamp());
+
+        let attempts = msg
+            .header_raw(ATTEMPTS_HEADER)
+            .and_then(|attempts| attempts.trim().parse().ok())
+            .unwrap_or_default();
+
+        let error = msg
+            .header_raw(ERROR_HEADER)
+            .map(|err| err.trim().to_owned());
+
+        Ok(Self {
+            to: mailboxes(msg.to()),
+            subject: msg.subject().unwrap_or_default().to_owned(),
+            send_at,
+            attempts,
+            error,
+            id,
+            path: path.to_owned(),
+        })
+    }
+
+    /// Return the id shortened to its first characters, which is
+    /// enough to identify the entry most of the time.
+    pub fn short_id(&self) -> &str {
+        &self.id[..self.id.len().min(8)]
+    }
+
+    /// Return true if the message should be sent at the given date.
+    /// Messages without send date are due, unless they failed to be
+    /// sent (they have been given up).
+    pub fn is_due(&self, now: i64) -> bool {
+        match self.send_at {
+            Some(send_at) => send_at <= now,
+            None => self.error.is_none(),
+        }
+    }
+
+    /// Read the message, without the headers specific to the outbox.
+    pub fn message(&self) -> Result<Vec<u8>> {
+        let raw = fs::read(&self.path)
+            .with_context(|| format!("cannot read outbox entry {}", self.id))?;
+        let msg = [SEND_AT_HEADER, ATTEMPTS_HEADER, ERROR_HEADER]
+            .into_iter()
+            .fold(raw, |msg, header| strip_header(&msg, header));
+        Ok(msg)
+    }
+
+    /// Read the message to deliver, with a Date header set to now.
+    fn message_to_deliver(&self) -> Result<Vec<u8>> {
+        let msg = strip_header(&self.message()?, "Date");
+        let mut dated = format!("Date: {}\r\n", Local::now().to_rfc2822()).into_bytes();
+        dated.extend(msg);
+        Ok(dated)
+    }
+}
+
+impl Table for OutboxEntry {
+    fn head() -> Row {
+        Row::new()
+            .cell(Cell::new("ID").bold().underline().white())
+            .cell(Cell::new("SEND AT").bold().underline().white())
+            .cell(Cell::new("TO").bold().underline().white())
+            .cell(Cell::new("SUBJECT").bold().underline().white())
+            .cell(Cell::new("ERROR").bold().underline().white())
+    }
+
+    fn row(&self) -> Row {
+        let send_at = self
+            .send_at
+            .and_then(|date| Local.timestamp_opt(date, 0).single())
+            .map(|date| date.format("%F %R").to_string())
+            .unwrap_or_default();
+
+        let to = self
+            .to
+            .iter()
+            .map(|mailbox| mailbox.name.as_deref().unwrap_or(&mailbox.addr))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let error = match &self.error {
+            Some(err) => format!("attempt {}: {err}", self.attempts),
+            None => String::new(),
+        };
+
+        Row::new()
+            .cell(Cell::new(self.short_id()).red())
+            .cell(Cell::new(send_at).yellow())
+            .cell(Cell::new(to).blue())
+            .cell(Cell::new(&self.subject).green())
+            .cell(Cell::new(error).shrinkable().red())
+    }
+}
+
+/// Represents the list of outbox entries.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct OutboxEntries(Vec<OutboxEntry>);
+
+impl ops::Deref for OutboxEntries {
+    type Target = Vec<OutboxEntry>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl PrintTable for OutboxEntries {
+    fn print_table(&self, writer: &mut dyn WriteColor, opts: PrintTableOpts) -> Result<()> {
+        writeln!(writer)?;
+        Table::print(writer, self, opts)?;
+        writeln!(writer)?;
+        Ok(())
+    }
+}
+
+/// Parse the date a message should be sent at.
+///
+/// Dates are either local (`YYYY-MM-DD HH:MM`, seconds and `T`
+/// separator accepted) or absolute (RFC 3339 or RFC 2822).
+pub fn parse_send_at(date: &str) -> Result<DateTime<Local>> {
+    let date = date.trim();
+
+    for format in SEND_AT_FORMATS {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(date, format) {
+            return Local
+                .from_local_datetime(&naive)
+                .earliest()
+                .ok_or_else(|| anyhow!("cannot parse send date {date:?}: invalid local time"));
+        }
+    }
+
+    DateTime::parse_from_rfc3339(date)
+        .or_else(|_| DateTime::parse_from_rfc2822(date))
+        .map(|date| date.with_timezone(&Local))
+        .map_err(|err| anyhow!(err).context(format!("cannot parse send date {date:?}")))
+}
+
+/// Remove the given header from the given raw message, folded lines
+/// included. The rest of the message is kept untouched.
+fn strip_header(msg: &[u8], name: &str) -> Vec<u8> {
+    let mut stripped = Vec::with_capacity(msg.len());
+    let mut skipping = false;
+    let mut rest = msg;
+
+    while !rest.is_empty() {
+        let end = rest
+            .iter()
+            .position(|b| *b == b'\n')
+            .map(|pos| pos + 1)
+            .unwrap_or(rest.len());
+        let (line, next) = rest.split_at(end);
+
+        if line == b"\r\n" || line == b"\n" {
+            stripped.extend_from_slice(rest);
+            break;
+        }
+
+        let folded = line.starts_with(b" ") || line.starts_with(b"\t");
+
+        if !folded {
+            skipping = line.len() > name.len()
+                && line[..name.len()].eq_ignore_ascii_case(name.as_bytes())
+                && line[name.len()] == b':';
+        }
+
+        if !skipping {
+            stripped.extend_from_slice(line);
+        }
+
+        rest = next;
+    }
+
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Local, TimeZone};
+
+    use super::{parse_send_at, strip_header};
+
+    #[test]
+    fn parse_send_dates() {
+        let expected = Local.with_ymd_and_hms(2026, 10, 20, 9, 0, 0).unwrap();
+
+        assert_eq!(parse_send_at("2026-10-20 09:00").unwrap(), expected);
+        assert_eq!(parse_send_at("2026-10-20T09:00:00").unwrap(), expected);
+        assert_eq!(
+            parse_send_at("2026-10-20T09:00:00+00:00")
+                .unwrap()
+                .timestamp(),
+            1792486800
+        );
+        assert!(parse_send_at("next tuesday").is_err());
+    }
+
+    #[test]
+    fn strip_outbox_header() {
+        let msg = concat!(
+            "X-Himalaya-Send-At: Tue, 20 Oct 2026\r\n",
+            " 09:00:00 +0000\r\n",
+            "Subject: hello\r\n",
+            "\r\n",
+            "x-himalaya-send-at: kept in body\r\n",
+        );
+
+        assert_eq!(
+            strip_header(msg.as_bytes(), "X-Himalaya-Send-At"),
+            concat!(
+                "Subject: hello\r\n",
+                "\r\n",
+                "x-himalaya-send-at: kept in body\r\n"
+            )
+            .as_bytes()
+        );
+    }
+}
@@ -0,0 +1,155 @@
+//! Module dedicated to the pager.
+//!
+//! Long plain output is piped to a pager when it is printed to a
+//! terminal and when it does not fit in it. The pager command is
+//! taken from the configuration, then from the `$PAGER` environment
+//! variable, then defaults to `less`.
+
+use anyhow::{Context, Result};
+use log::debug;
+use std::{
+    env,
+    io::{self, IsTerminal, Write},
+    process::{Command, Stdio},
+};
+use terminal_size::terminal_size;
+use unicode_width::UnicodeWidthStr;
+
+/// Represents the pager command.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Pager {
+    cmd: String,
+}
+
+impl Pager {
+    /// Resolve the pager from the given configured command.
+    ///
+    /// Returns `None` if the resolved command is empty, which
+    /// disables the pager.
+    pub fn new(cmd: Option<&str>) -> Option<Self> {
+        let cmd = match cmd {
+            Some(cmd) => cmd.to_owned(),
+            None => env::var("PAGER").unwrap_or_else(|_| String::from("less")),
+        };
+
+        let cmd = cmd.trim();
+
+        if cmd.is_empty() {
+            None
+        } else {
+            Some(Self {
+                cmd: cmd.to_owned(),
+            })
+        }
+    }
+
+    /// Return true if the given output should be paged, which means
+    /// that stdout is a terminal and that the output is higher than
+    /// it.
+    pub fn should_page(output: &[u8]) -> bool {
+        if !io::stdout().is_terminal() {
+            return false;
+        }
+
+        match terminal_size() {
+            Some((width, height)) => {
+                let output = String::from_utf8_lossy(output);
+                count_lines(&output, width.0 as usize) > height.0 as usize
+            }
+            None => false,
+        }
+    }
+
+    /// Pipe the given output to the pager, then wait for it to exit.
+    pub fn page(&self, output: &[u8]) -> Result<()> {
+        debug!("paging output with {}", self.cmd);
+
+        let mut cmd = if cfg!(target_os = "windows") {
+            let mut cmd = Command::new("cmd");
+            cmd.args(["/C", &self.cmd]);
+            cmd
+        } else {
+            let mut cmd = Command::new("sh");
+            cmd.args(["-c", &self.cmd]);
+            cmd
+        };
+
+        // same defaults as git: quit if the output fits in one
+        // screen, keep colors and do not clear the screen on exit
+        if env::var_os("LESS").is_none() {
+            cmd.env("LESS", "FRX");
+        }
+
+        if env::var_os("LV").is_none() {
+            cmd.env("LV", "-c");
+        }
+
+        let mut child = cmd
+            .stdin(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("cannot spawn pager {}", self.cmd))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            // the pager may exit before the whole output is written,
+            // for example when the user quits early
+            match stdin.write_all(output) {
+                Err(err) if err.kind() == io::ErrorKind::BrokenPipe => (),
+                res => res.context("cannot write output to pager")?,
+            }
+        }
+
+        child
+            .wait()
+            .with_context(|| format!("cannot wait for pager {}", self.cmd))?;
+
+        Ok(())
+    }
+}
+
+/// Count the lines taken by the given output in a terminal of the
+/// given width, ANSI escape sequences excluded.
+fn count_lines(output: &str, width: usize) -> usize {
+    output
+        .lines()
+        .map(|line| {
+            let width = width.max(1);
+            let line_width = strip_ansi(line).width();
+            line_width.div_ceil(width).max(1)
+        })
+        .sum()
+}
+
+fn strip_ansi(line: &str) -> String {
+    let mut stripped = String::with_capacity(line.len());
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // skip control sequences up to their final byte
+            if chars.next() == Some('[') {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+        } else {
+            stripped.push(c);
+        }
+    }
+
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::count_lines;
+
+    #[test]
+    fn count_lines_with_ansi_and_wrap() {
+        assert_eq!(count_lines("", 80), 0);
+        assert_eq!(count_lines("a\n\nb\n", 80), 3);
+        assert_eq!(count_lines("\x1b[1;31mabcd\x1b[0m\n", 4), 1);
+        assert_eq!(count_lines("abcdefghi\n", 4), 3);
+    }
+}
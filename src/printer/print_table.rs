@@ -1,12 +1,14 @@
 use anyhow::Result;
 use email::email::config::EmailTextPlainFormat;
 use std::io;
-use termcolor::{self, StandardStream};
+use termcolor::{self, Buffer, StandardStream};
 
 pub trait WriteColor: io::Write + termcolor::WriteColor {}
 
 impl WriteColor for StandardStream {}
 
+impl WriteColor for Buffer {}
+
 pub trait PrintTable {
     fn print_table(&self, writer: &mut dyn WriteColor, opts: PrintTableOpts) -> Result<()>;
 }
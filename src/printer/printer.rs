@@ -1,11 +1,15 @@
 use anyhow::{Context, Error, Result};
 use clap::ArgMatches;
-use std::fmt::{self, Debug};
-use termcolor::StandardStream;
+use log::{debug, warn};
+use std::{
+    fmt::{self, Debug},
+    io::Write,
+};
+use termcolor::{Buffer, StandardStream};
 
 use crate::{
     output::{args, ColorFmt, OutputFmt},
-    printer::{Print, PrintTable, PrintTableOpts, WriteColor},
+    printer::{Pager, Print, PrintTable, PrintTableOpts, WriteColor},
 };
 
 pub trait Printer {
@@ -20,26 +24,77 @@ pub trait Printer {
         data: Box<T>,
         opts: PrintTableOpts,
     ) -> Result<()>;
+    /// Print the given data as an item of a stream, which is
+    /// flushed straight away. In JSON mode, each item is printed on
+    /// its own line (NDJSON).
+    fn print_stream<T: Debug + Print + serde::Serialize>(&mut self, data: T) -> Result<()>;
+    /// Set the pager command from the configuration. When `None`,
+    /// the pager falls back to `$PAGER`, then to `less`.
+    fn set_pager(&mut self, pager: Option<String>);
     fn is_json(&self) -> bool;
 }
 
 pub struct StdoutPrinter {
     pub writer: Box<dyn WriteColor>,
     pub fmt: OutputFmt,
+    pub pager: Option<String>,
+    pub paging: bool,
 }
 
 impl Default for StdoutPrinter {
     fn default() -> Self {
-        let fmt = OutputFmt::default();
-        let writer = Box::new(StandardStream::stdout(ColorFmt::default().into()));
-        Self { fmt, writer }
+        Self::new(OutputFmt::default(), ColorFmt::default())
     }
 }
 
 impl StdoutPrinter {
     pub fn new(fmt: OutputFmt, color: ColorFmt) -> Self {
         let writer = Box::new(StandardStream::stdout(color.into()));
-        Self { fmt, writer }
+        Self {
+            fmt,
+            writer,
+            pager: None,
+            paging: true,
+        }
+    }
+
+    /// Disable the pager, output is always written straight to
+    /// stdout.
+    pub fn without_pager(mut self) -> Self {
+        self.paging = false;
+        self
+    }
+
+    /// Write the output of the given function through the pager if
+    /// it does not fit in the terminal, otherwise write it straight
+    /// to stdout.
+    fn write_paged(&mut self, f: impl FnOnce(&mut dyn WriteColor) -> Result<()>) -> Result<()> {
+        let pager = match Pager::new(self.pager.as_deref()) {
+            Some(pager) if self.paging => pager,
+            _ => return f(self.writer.as_mut()),
+        };
+
+        let mut buffer = if self.writer.supports_color() {
+            Buffer::ansi()
+        } else {
+            Buffer::no_color()
+        };
+
+        f(&mut buffer)?;
+
+        if Pager::should_page(buffer.as_slice()) {
+            match pager.page(buffer.as_slice()) {
+                Ok(()) => return Ok(()),
+                Err(err) => {
+                    warn!("{err}, writing output to stdout instead");
+                    debug!("{err:?}");
+                }
+            }
+        }
+
+        self.writer
+            .write_all(buffer.as_slice())
+            .context("cannot write output to stdout")
     }
 }
 
@@ -53,7 +108,7 @@ impl Printer for StdoutPrinter {
 
     fn print<T: Debug + Print + serde::Serialize>(&mut self, data: T) -> Result<()> {
         match self.fmt {
-            OutputFmt::Plain => data.print(self.writer.as_mut()),
+            OutputFmt::Plain => self.write_paged(|writer| data.print(writer)),
             OutputFmt::Json => serde_json::to_writer(self.writer.as_mut(), &data)
                 .context("cannot write json to writer"),
         }
@@ -65,7 +120,7 @@ impl Printer for StdoutPrinter {
         opts: PrintTableOpts,
     ) -> Result<()> {
         match self.fmt {
-            OutputFmt::Plain => data.print_table(self.writer.as_mut(), opts),
+            OutputFmt::Plain => self.write_paged(|writer| data.print_table(writer, opts)),
             OutputFmt::Json => {
                 let json = &mut serde_json::Serializer::new(self.writer.as_mut());
                 let ser = &mut <dyn erased_serde::Serializer>::erase(json);
@@ -75,6 +130,23 @@ impl Printer for StdoutPrinter {
         }
     }
 
+    fn print_stream<T: Debug + Print + serde::Serialize>(&mut self, data: T) -> Result<()> {
+        match self.fmt {
+            OutputFmt::Plain => data.print(self.writer.as_mut())?,
+            OutputFmt::Json => {
+                serde_json::to_writer(self.writer.as_mut(), &data)
+                    .context("cannot write json to writer")?;
+                writeln!(self.writer)?;
+            }
+        }
+
+        self.writer.flush().context("cannot flush writer")
+    }
+
+    fn set_pager(&mut self, pager: Option<String>) {
+        self.pager = pager;
+    }
+
     fn is_json(&self) -> bool {
         self.fmt == OutputFmt::Json
     }
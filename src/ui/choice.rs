@@ -1,5 +1,9 @@
 use anyhow::Result;
-use dialoguer::Select;
+use chrono::{DateTime, Local};
+use dialoguer::{Input, Select};
+use std::fmt;
+
+use crate::outbox::parse_send_at;
 
 use super::THEME;
 
@@ -10,12 +14,12 @@ pub enum PreEditChoice {
     Quit,
 }
 
-impl ToString for PreEditChoice {
-    fn to_string(&self) -> String {
+impl fmt::Display for PreEditChoice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Self::Edit => "Edit it".into(),
-            Self::Discard => "Discard it".into(),
-            Self::Quit => "Quit".into(),
+            Self::Edit => write!(f, "Edit it"),
+            Self::Discard => write!(f, "Discard it"),
+            Self::Quit => write!(f, "Quit"),
         }
     }
 }
@@ -39,20 +43,22 @@ pub fn pre_edit() -> Result<PreEditChoice> {
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum PostEditChoice {
     Send,
+    SendLater,
     Edit,
     LocalDraft,
     RemoteDraft,
     Discard,
 }
 
-impl ToString for PostEditChoice {
-    fn to_string(&self) -> String {
+impl fmt::Display for PostEditChoice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Self::Send => "Send it".into(),
-            Self::Edit => "Edit it again".into(),
-            Self::LocalDraft => "Save it as local draft".into(),
-            Self::RemoteDraft => "Save it as remote draft".into(),
-            Self::Discard => "Discard it".into(),
+            Self::Send => write!(f, "Send it"),
+            Self::SendLater => write!(f, "Send it later"),
+            Self::Edit => write!(f, "Edit it again"),
+            Self::LocalDraft => write!(f, "Save it as local draft"),
+            Self::RemoteDraft => write!(f, "Save it as remote draft"),
+            Self::Discard => write!(f, "Discard it"),
         }
     }
 }
@@ -60,6 +66,7 @@ impl ToString for PostEditChoice {
 pub fn post_edit() -> Result<PostEditChoice> {
     let choices = [
         PostEditChoice::Send,
+        PostEditChoice::SendLater,
         PostEditChoice::Edit,
         PostEditChoice::LocalDraft,
         PostEditChoice::RemoteDraft,
@@ -74,3 +81,12 @@ pub fn post_edit() -> Result<PostEditChoice> {
 
     Ok(choices[choice_idx].clone())
 }
+
+pub fn send_at() -> Result<DateTime<Local>> {
+    let date: String = Input::with_theme(&*THEME)
+        .with_prompt("When would you like to send this message? (YYYY-MM-DD HH:MM)")
+        .validate_with(|date: &String| parse_send_at(date).map(|_| ()))
+        .interact()?;
+
+    parse_send_at(&date)
+}
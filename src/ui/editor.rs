@@ -4,7 +4,6 @@ use email::{
     email::utils::{local_draft_path, remove_local_draft},
     flag::{Flag, Flags},
     folder::DRAFTS,
-    message::{add::AddMessage, send::SendMessageThenSaveCopy},
 };
 use log::debug;
 use mml::MmlCompilerBuilder;
@@ -13,6 +12,7 @@ use std::{env, fs, sync::Arc};
 
 use crate::{
     backend::Backend,
+    outbox::{send_message_or_queue, Outbox},
     printer::Printer,
     ui::choice::{self, PostEditChoice, PreEditChoice},
 };
@@ -25,7 +25,7 @@ pub async fn open_with_tpl(tpl: String) -> Result<String> {
 
     debug!("open editor");
     let editor = env::var("EDITOR").context("cannot get editor from env var")?;
-    SingleCmd::from(format!("{editor} {}", &path.to_string_lossy()))
+    SingleCmd::from(format!("{editor} {}", path.to_string_lossy()))
         .with_output_piped(false)
         .run()
         .await
@@ -45,13 +45,72 @@ pub async fn open_with_local_draft() -> Result<String> {
     open_with_tpl(content).await
 }
 
-#[allow(unused)]
 pub async fn edit_tpl_with_editor<P: Printer>(
     config: Arc<AccountConfig>,
     printer: &mut P,
     backend: &Backend,
-    mut tpl: String,
+    tpl: String,
 ) -> Result<()> {
+    edit_tpl(config, printer, backend, tpl).await?;
+    Ok(())
+}
+
+/// Edit the given template of the remote draft matching the given
+/// folder and id.
+///
+/// The remote draft is removed once its new version has been sent,
+/// scheduled or saved as remote draft. It is kept untouched otherwise,
+/// including when the local draft has been edited instead.
+pub async fn edit_draft_with_editor<P: Printer>(
+    config: Arc<AccountConfig>,
+    printer: &mut P,
+    backend: &Backend,
+    folder: &str,
+    id: usize,
+    tpl: String,
+) -> Result<()> {
+    let Some(edited) = edit_tpl(config, printer, backend, tpl).await? else {
+        return Ok(());
+    };
+
+    if let PostEditChoice::Send | PostEditChoice::SendLater | PostEditChoice::RemoteDraft =
+        edited.choice
+    {
+        if edited.from_local_draft {
+            printer.print_log(format!(
+                "Previous draft {id} kept, since the local draft has been edited instead"
+            ))?;
+        } else {
+            backend
+                .delete_messages(folder, &[id])
+                .await
+                .with_context(|| format!("cannot remove previous draft {id}"))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Represents the outcome of the edition of a template.
+struct EditedTpl {
+    /// The applied post edit choice.
+    choice: PostEditChoice,
+    /// Whether the local draft has been edited instead of the given
+    /// template.
+    from_local_draft: bool,
+}
+
+/// Edit the given template, then apply the post edit choice.
+///
+/// Returns the outcome of the edition, or `None` if the edition has
+/// been aborted before opening the editor.
+async fn edit_tpl<P: Printer>(
+    config: Arc<AccountConfig>,
+    printer: &mut P,
+    backend: &Backend,
+    mut tpl: String,
+) -> Result<Option<EditedTpl>> {
+    let mut from_local_draft = false;
     let draft = local_draft_path();
     if draft.exists() {
         loop {
@@ -59,13 +118,14 @@ pub async fn edit_tpl_with_editor<P: Printer>(
                 Ok(choice) => match choice {
                     PreEditChoice::Edit => {
                         tpl = open_with_local_draft().await?;
+                        from_local_draft = true;
                         break;
                     }
                     PreEditChoice::Discard => {
                         tpl = open_with_tpl(tpl).await?;
                         break;
                     }
-                    PreEditChoice::Quit => return Ok(()),
+                    PreEditChoice::Quit => return Ok(None),
                 },
                 Err(err) => {
                     println!("{}", err);
@@ -77,7 +137,7 @@ pub async fn edit_tpl_with_editor<P: Printer>(
         tpl = open_with_tpl(tpl).await?;
     }
 
-    loop {
+    let choice = loop {
         match choice::post_edit() {
             Ok(PostEditChoice::Send) => {
                 printer.print_log("Sending email…")?;
@@ -90,11 +150,37 @@ pub async fn edit_tpl_with_editor<P: Printer>(
 
                 let email = compiler.build(tpl.as_str())?.compile().await?.into_vec()?;
 
-                backend.send_message_then_save_copy(&email).await?;
+                let queued = send_message_or_queue(backend, &email).await?;
 
                 remove_local_draft()?;
-                printer.print("Done!")?;
-                break;
+                match queued {
+                    Some(entry) => printer.print(format!(
+                        "Email could not be sent, queued to the outbox as {}",
+                        entry.short_id()
+                    ))?,
+                    None => printer.print("Done!")?,
+                }
+                break PostEditChoice::Send;
+            }
+            Ok(PostEditChoice::SendLater) => {
+                let send_at = choice::send_at()?;
+
+                #[allow(unused_mut)]
+                let mut compiler = MmlCompilerBuilder::new();
+
+                #[cfg(feature = "pgp")]
+                compiler.set_some_pgp(config.pgp.clone());
+
+                let email = compiler.build(tpl.as_str())?.compile().await?.into_vec()?;
+
+                Outbox::new(&config)?.push(&email, send_at)?;
+
+                remove_local_draft()?;
+                printer.print(format!(
+                    "Email successfully scheduled for {}",
+                    send_at.format("%F %R")
+                ))?;
+                break PostEditChoice::SendLater;
             }
             Ok(PostEditChoice::Edit) => {
                 tpl = open_with_tpl(tpl).await?;
@@ -102,7 +188,7 @@ pub async fn edit_tpl_with_editor<P: Printer>(
             }
             Ok(PostEditChoice::LocalDraft) => {
                 printer.print("Email successfully saved locally")?;
-                break;
+                break PostEditChoice::LocalDraft;
             }
             Ok(PostEditChoice::RemoteDraft) => {
                 #[allow(unused_mut)]
@@ -122,18 +208,21 @@ pub async fn edit_tpl_with_editor<P: Printer>(
                     .await?;
                 remove_local_draft()?;
                 printer.print("Email successfully saved to drafts")?;
-                break;
+                break PostEditChoice::RemoteDraft;
             }
             Ok(PostEditChoice::Discard) => {
                 remove_local_draft()?;
-                break;
+                break PostEditChoice::Discard;
             }
             Err(err) => {
                 println!("{}", err);
                 continue;
             }
         }
-    }
+    };
 
-    Ok(())
+    Ok(Some(EditedTpl {
+        choice,
+        from_local_draft,
+    }))
 }
@@ -1,5 +1,6 @@
 pub mod choice;
 pub mod editor;
+pub(crate) mod progress;
 pub(crate) mod prompt;
 pub mod table;
 
@@ -0,0 +1,24 @@
+//! Module dedicated to progress bars.
+//!
+//! Long-running commands (synchronization, export, import) share the
+//! same progress bar styles.
+
+use indicatif::ProgressStyle;
+use once_cell::sync::Lazy;
+
+pub(crate) static MAIN_PROGRESS_STYLE: Lazy<ProgressStyle> = Lazy::new(|| {
+    ProgressStyle::with_template(" {spinner:.dim} {msg:.dim}\n {wide_bar:.cyan/blue} \n").unwrap()
+});
+
+#[cfg(feature = "account-sync")]
+pub(crate) static SUB_PROGRESS_STYLE: Lazy<ProgressStyle> = Lazy::new(|| {
+    ProgressStyle::with_template(
+        "   {prefix:.bold} — {wide_msg:.dim} \n   {wide_bar:.black/black} {percent}% ",
+    )
+    .unwrap()
+});
+
+#[cfg(feature = "account-sync")]
+pub(crate) static SUB_PROGRESS_DONE_STYLE: Lazy<ProgressStyle> = Lazy::new(|| {
+    ProgressStyle::with_template("   {prefix:.bold} \n   {wide_bar:.green} {percent}% ").unwrap()
+});
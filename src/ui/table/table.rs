@@ -29,6 +29,9 @@ pub struct Cell {
     style: ColorSpec,
     /// Represents the content of the cell.
     value: String,
+    /// Represents the colored parts of the cell content, as byte
+    /// offsets where the color changes.
+    spans: Vec<(usize, Option<Color>)>,
     /// (Dis)allowes the cell to shrink when the table exceeds the container width.
     shrinkable: bool,
 }
@@ -36,18 +39,19 @@ pub struct Cell {
 impl Cell {
     pub fn new<T: AsRef<str>>(value: T) -> Self {
         Self {
-            // Removes carriage returns, new line feeds, tabulations
-            // and [variation selectors].
-            //
-            // [variation selectors]: https://en.wikipedia.org/wiki/Variation_Selectors_(Unicode_block)
-            value: String::from(value.as_ref()).replace(
-                |c| ['\r', '\n', '\t', '\u{fe0e}', '\u{fe0f}'].contains(&c),
-                "",
-            ),
+            value: sanitize(value.as_ref()),
             ..Self::default()
         }
     }
 
+    /// Appends the given value to the cell content, with its own
+    /// color. When no color is given, the cell color is used.
+    pub fn push<T: AsRef<str>>(mut self, value: T, color: Option<Color>) -> Self {
+        self.spans.push((self.value.len(), color));
+        self.value.push_str(&sanitize(value.as_ref()));
+        self
+    }
+
     /// Returns the unicode width of the cell's value.
     pub fn unicode_width(&self) -> usize {
         UnicodeWidthStr::width(self.value.as_str())
@@ -110,6 +114,12 @@ impl Cell {
         self
     }
 
+    /// Applies the magenta color to the cell.
+    pub fn magenta(mut self) -> Self {
+        self.style.set_fg(Some(Color::Magenta));
+        self
+    }
+
     /// Applies the white color to the cell.
     pub fn white(mut self) -> Self {
         self.style.set_fg(Some(Color::White));
@@ -127,17 +137,62 @@ impl Cell {
 impl Print for Cell {
     fn print(&self, writer: &mut dyn WriteColor) -> Result<()> {
         // Applies colors to the cell
-        writer
-            .set_color(&self.style)
-            .context(format!(r#"cannot apply colors to cell "{}""#, self.value))?;
+        let mut parts = vec![(0, None)];
+        parts.extend(self.spans.iter().cloned());
+
+        for (i, (start, color)) in parts.iter().enumerate() {
+            let end = parts.get(i + 1).map(|(end, _)| *end);
+            let (start, end) = (
+                char_boundary(&self.value, *start),
+                end.map(|end| char_boundary(&self.value, end))
+                    .unwrap_or(self.value.len()),
+            );
+
+            if start >= end {
+                continue;
+            }
+
+            let mut style = self.style.clone();
+            if let Some(color) = color {
+                style.set_fg(Some(*color));
+            }
+
+            // Applies colors to the cell
+            writer
+                .set_color(&style)
+                .context(format!(r#"cannot apply colors to cell "{}""#, self.value))?;
+
+            // Writes the colorized cell to stdout
+            write!(writer, "{}", &self.value[start..end])
+                .context(format!(r#"cannot print cell "{}""#, self.value))?;
+        }
 
-        // Writes the colorized cell to stdout
-        write!(writer, "{}", self.value)
-            .context(format!(r#"cannot print cell "{}""#, self.value))?;
         Ok(writer.reset()?)
     }
 }
 
+/// Removes carriage returns, new line feeds, tabulations and
+/// [variation selectors] from the given value.
+///
+/// [variation selectors]: https://en.wikipedia.org/wiki/Variation_Selectors_(Unicode_block)
+fn sanitize(value: &str) -> String {
+    value.replace(
+        |c| ['\r', '\n', '\t', '\u{fe0e}', '\u{fe0f}'].contains(&c),
+        "",
+    )
+}
+
+/// Returns the closest char boundary of the given value before the
+/// given offset. Offsets may be out of bounds once the cell value has
+/// been shrunk.
+fn char_boundary(value: &str, offset: usize) -> usize {
+    let mut offset = offset.min(value.len());
+    while !value.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
 /// Represents a row in a table.
 #[derive(Debug, Default)]
 pub struct Row(
@@ -169,100 +224,109 @@ where
 
     /// Writes the table to the writer.
     fn print(writer: &mut dyn WriteColor, items: &[Self], opts: PrintTableOpts) -> Result<()> {
-        let is_format_flowed = matches!(opts.format, EmailTextPlainFormat::Flowed);
-        let max_width = match opts.format {
-            EmailTextPlainFormat::Fixed(width) => opts.max_width.unwrap_or(*width),
-            EmailTextPlainFormat::Flowed => 0,
-            EmailTextPlainFormat::Auto => opts
-                .max_width
-                .or_else(|| terminal_size().map(|(w, _)| w.0 as usize))
-                .unwrap_or(DEFAULT_TERM_WIDTH),
-        };
-        let mut table = vec![Self::head()];
-        let mut cell_widths: Vec<usize> =
-            table[0].0.iter().map(|cell| cell.unicode_width()).collect();
-        table.extend(
-            items
-                .iter()
-                .map(|item| {
-                    let row = item.row();
-                    row.0.iter().enumerate().for_each(|(i, cell)| {
-                        cell_widths[i] = cell_widths[i].max(cell.unicode_width());
-                    });
-                    row
-                })
-                .collect::<Vec<_>>(),
-        );
-        trace!("cell widths: {:?}", cell_widths);
-
-        let spaces_plus_separators_len = cell_widths.len() * 2 - 1;
-        let table_width = cell_widths.iter().sum::<usize>() + spaces_plus_separators_len;
-        trace!("table width: {}", table_width);
-
-        for row in table.iter_mut() {
-            let mut glue = Cell::default();
-            for (i, cell) in row.0.iter_mut().enumerate() {
-                glue.print(writer)?;
-
-                let table_is_overflowing = table_width > max_width;
-                if table_is_overflowing && !is_format_flowed && cell.is_shrinkable() {
-                    trace!("table is overflowing and cell is shrinkable");
-
-                    let shrink_width = table_width - max_width;
-                    trace!("shrink width: {}", shrink_width);
-                    let cell_width = if shrink_width + MAX_SHRINK_WIDTH < cell_widths[i] {
-                        cell_widths[i] - shrink_width
-                    } else {
-                        MAX_SHRINK_WIDTH
-                    };
-                    trace!("cell width: {}", cell_width);
-                    trace!("cell unicode width: {}", cell.unicode_width());
-
-                    let cell_is_overflowing = cell.unicode_width() > cell_width;
-                    if cell_is_overflowing {
-                        trace!("cell is overflowing");
-
-                        let mut value = String::new();
-                        let mut chars_width = 0;
-
-                        for c in cell.value.chars() {
-                            let char_width = UnicodeWidthStr::width(c.to_string().as_str());
-                            if chars_width + char_width >= cell_width {
-                                break;
-                            }
-
-                            chars_width += char_width;
-                            value.push(c);
+        print_rows(writer, Self::head(), items.iter().map(Self::row), opts)
+    }
+}
+
+/// Writes the table composed of the given header row and rows to the
+/// writer.
+///
+/// This is useful for tables whose columns are only known at runtime.
+pub fn print_rows(
+    writer: &mut dyn WriteColor,
+    head: Row,
+    rows: impl IntoIterator<Item = Row>,
+    opts: PrintTableOpts,
+) -> Result<()> {
+    let is_format_flowed = matches!(opts.format, EmailTextPlainFormat::Flowed);
+    let max_width = match opts.format {
+        EmailTextPlainFormat::Fixed(width) => opts.max_width.unwrap_or(*width),
+        EmailTextPlainFormat::Flowed => 0,
+        EmailTextPlainFormat::Auto => opts
+            .max_width
+            .or_else(|| terminal_size().map(|(w, _)| w.0 as usize))
+            .unwrap_or(DEFAULT_TERM_WIDTH),
+    };
+    let mut table = vec![head];
+    let mut cell_widths: Vec<usize> = table[0].0.iter().map(|cell| cell.unicode_width()).collect();
+    table.extend(
+        rows.into_iter()
+            .inspect(|row| {
+                row.0.iter().enumerate().for_each(|(i, cell)| {
+                    cell_widths[i] = cell_widths[i].max(cell.unicode_width());
+                });
+            })
+            .collect::<Vec<_>>(),
+    );
+    trace!("cell widths: {:?}", cell_widths);
+
+    let spaces_plus_separators_len = (cell_widths.len() * 2).saturating_sub(1);
+    let table_width = cell_widths.iter().sum::<usize>() + spaces_plus_separators_len;
+    trace!("table width: {}", table_width);
+
+    for row in table.iter_mut() {
+        let mut glue = Cell::default();
+        for (i, cell) in row.0.iter_mut().enumerate() {
+            glue.print(writer)?;
+
+            let table_is_overflowing = table_width > max_width;
+            if table_is_overflowing && !is_format_flowed && cell.is_shrinkable() {
+                trace!("table is overflowing and cell is shrinkable");
+
+                let shrink_width = table_width - max_width;
+                trace!("shrink width: {}", shrink_width);
+                let cell_width = if shrink_width + MAX_SHRINK_WIDTH < cell_widths[i] {
+                    cell_widths[i] - shrink_width
+                } else {
+                    MAX_SHRINK_WIDTH
+                };
+                trace!("cell width: {}", cell_width);
+                trace!("cell unicode width: {}", cell.unicode_width());
+
+                let cell_is_overflowing = cell.unicode_width() > cell_width;
+                if cell_is_overflowing {
+                    trace!("cell is overflowing");
+
+                    let mut value = String::new();
+                    let mut chars_width = 0;
+
+                    for c in cell.value.chars() {
+                        let char_width = UnicodeWidthStr::width(c.to_string().as_str());
+                        if chars_width + char_width >= cell_width {
+                            break;
                         }
 
-                        value.push_str("… ");
-                        trace!("chars width: {}", chars_width);
-                        trace!("shrunk value: {}", value);
-                        let spaces_count = cell_width - chars_width - 1;
-                        trace!("number of spaces added to shrunk value: {}", spaces_count);
-                        value.push_str(&" ".repeat(spaces_count));
-                        cell.value = value;
-                    } else {
-                        trace!("cell is not overflowing");
-                        let spaces_count = cell_width - cell.unicode_width() + 1;
-                        trace!("number of spaces added to value: {}", spaces_count);
-                        cell.value.push_str(&" ".repeat(spaces_count));
+                        chars_width += char_width;
+                        value.push(c);
                     }
+
+                    value.push_str("… ");
+                    trace!("chars width: {}", chars_width);
+                    trace!("shrunk value: {}", value);
+                    let spaces_count = cell_width - chars_width - 1;
+                    trace!("number of spaces added to shrunk value: {}", spaces_count);
+                    value.push_str(&" ".repeat(spaces_count));
+                    cell.value = value;
                 } else {
-                    trace!("table is not overflowing or cell is not shrinkable");
-                    trace!("cell width: {}", cell_widths[i]);
-                    trace!("cell unicode width: {}", cell.unicode_width());
-                    let spaces_count = cell_widths[i] - cell.unicode_width() + 1;
+                    trace!("cell is not overflowing");
+                    let spaces_count = cell_width - cell.unicode_width() + 1;
                     trace!("number of spaces added to value: {}", spaces_count);
                     cell.value.push_str(&" ".repeat(spaces_count));
                 }
-                cell.print(writer)?;
-                glue = Cell::new("│").ansi_256(8);
+            } else {
+                trace!("table is not overflowing or cell is not shrinkable");
+                trace!("cell width: {}", cell_widths[i]);
+                trace!("cell unicode width: {}", cell.unicode_width());
+                let spaces_count = cell_widths[i] - cell.unicode_width() + 1;
+                trace!("number of spaces added to value: {}", spaces_count);
+                cell.value.push_str(&" ".repeat(spaces_count));
             }
-            writeln!(writer)?;
+            cell.print(writer)?;
+            glue = Cell::new("│").ansi_256(8);
         }
-        Ok(())
+        writeln!(writer)?;
     }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -279,8 +343,7 @@ mod tests {
 
     impl io::Write for StringWriter {
         fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-            self.content
-                .push_str(&String::from_utf8(buf.to_vec()).unwrap());
+            self.content.push_str(std::str::from_utf8(buf).unwrap());
             Ok(buf.len())
         }
 
@@ -443,4 +506,39 @@ mod tests {
         ];
         assert_eq!(expected, writer.content);
     }
+
+    #[test]
+    fn colored_parts() {
+        let mut writer = StringWriter::default();
+        let head = Row::new().cell(Cell::new("FLAGS")).cell(Cell::new("NAME"));
+        let rows = [
+            Row::new()
+                .cell(Cell::new("✷").push(" junk", Some(Color::Red)))
+                .cell(Cell::new("a")),
+            Row::new()
+                .cell(Cell::new("").push("é", None).push("☢", Some(Color::Blue)))
+                .cell(Cell::new("b")),
+        ];
+        let opts = PrintTableOpts {
+            format: &EmailTextPlainFormat::Auto,
+            max_width: Some(20),
+        };
+        print_rows(&mut writer, head, rows, opts).unwrap();
+
+        let expected = concat!["FLAGS  │NAME \n", "✷ junk │a    \n", "é☢     │b    \n",];
+        assert_eq!(expected, writer.content);
+    }
+
+    #[test]
+    fn no_columns() {
+        let mut writer = StringWriter::default();
+        let rows = [Row::new(), Row::new()];
+        let opts = PrintTableOpts {
+            format: &EmailTextPlainFormat::Auto,
+            max_width: Some(20),
+        };
+        print_rows(&mut writer, Row::new(), rows, opts).unwrap();
+
+        assert_eq!("\n\n\n", writer.content);
+    }
 }